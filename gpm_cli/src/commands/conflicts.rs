@@ -0,0 +1,86 @@
+use crate::commands::install::{load_profile, open_repository};
+use crate::reporter::HumanReporter;
+use gpm_core::conflict::{find_conflicts, load_order as package_load_order};
+use gpm_core::conflict::{profile_staged_packages, ConflictError};
+use gpm_core::display::list::format_str_id_list;
+use gpm_core::install::{redeploy, InstallError};
+use gpm_core::install_strategy::StrategySet;
+use gpm_core::profile::ProfileError;
+use gpm_core::repository::RepositoryError;
+use std::path::PathBuf;
+
+pub struct ConflictsParameter {
+    pub home: PathBuf,
+    pub profile: Option<String>,
+}
+
+pub struct LoadOrderParameter {
+    pub home: PathBuf,
+    pub profile: Option<String>,
+    pub repository: Option<PathBuf>,
+    /// the packages to load after the others, or None to only display the load order
+    pub overrides: Option<Vec<String>>,
+}
+
+#[derive(thiserror::Error, Debug)]
+#[allow(clippy::enum_variant_names)]
+pub enum ConflictsCommandError {
+    #[error("error with the profile")]
+    ProfileError(#[from] ProfileError),
+    #[error("can't open the package repository")]
+    RepositoryError(#[from] RepositoryError),
+    #[error("can't list the files of the installed mods")]
+    ConflictError(#[from] ConflictError),
+    #[error("error while deploying the mods in the new order")]
+    InstallError(#[from] InstallError),
+    #[error("the package {0} isn't installed in this profile")]
+    NotInstalledError(String),
+}
+
+pub fn conflicts(parameter: ConflictsParameter) -> Result<(), ConflictsCommandError> {
+    let profile = load_profile(&parameter.home, &parameter.profile)?;
+    let packages = profile_staged_packages(&profile)?;
+    let conflicts = find_conflicts(&packages, &StrategySet::default())?;
+    if conflicts.is_empty() {
+        println!(
+            "no conflict between the mods of the profile {}",
+            profile.name
+        );
+    };
+    for conflict in &conflicts {
+        println!(
+            "{} is written by {} ({} wins)",
+            conflict.path,
+            format_str_id_list(&conflict.packages),
+            format_str_id_list(&[conflict.winner()])
+        );
+    }
+    Ok(())
+}
+
+pub fn load_order(parameter: LoadOrderParameter) -> Result<(), ConflictsCommandError> {
+    let mut profile = load_profile(&parameter.home, &parameter.profile)?;
+    if let Some(overrides) = parameter.overrides {
+        for identifier in &overrides {
+            if profile.lock_file.dependency_source(identifier).is_none() {
+                return Err(ConflictsCommandError::NotInstalledError(identifier.clone()));
+            };
+        }
+        let repository = open_repository(&parameter.repository, &profile)?;
+        profile.load_order = overrides;
+        // the new load order is saved by the deployment, only if it succeed
+        redeploy(
+            &mut profile,
+            repository.as_ref(),
+            &StrategySet::default(),
+            &HumanReporter,
+        )?;
+    };
+    let packages = profile_staged_packages(&profile)?;
+    println!(
+        "load order of the profile {}: {}",
+        profile.name,
+        format_str_id_list(&package_load_order(&packages, &profile.load_order))
+    );
+    Ok(())
+}
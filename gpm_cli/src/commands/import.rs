@@ -0,0 +1,131 @@
+use crate::commands::init::{
+    ask_information, identifier_from_name, InformationParameter, InitError,
+};
+use crate::reporter::MessageFormat;
+use gpm_core::archive::ArchiveFormat;
+use gpm_core::display::list::format_str_id_list;
+use gpm_core::import::{import_archive, import_archive_as_project, ArchiveLayout, ImportError};
+use gpm_core::install_strategy::StrategySet;
+use gpm_core::package_writer::{source_date_epoch, CreatePackageError, PackageOptions};
+use gpm_core::report::Event;
+use std::fs::File;
+use std::io;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+
+pub struct ImportParameter {
+    /// the zip or 7z archive of the mod to import
+    pub archive_file: PathBuf,
+    /// the package to create
+    pub output_file: Option<PathBuf>,
+    /// the mod project to create instead of a package, to edit it before packaging it
+    pub project_dir: Option<PathBuf>,
+    pub format: ArchiveFormat,
+    pub message_format: MessageFormat,
+    pub information: InformationParameter,
+}
+
+#[derive(thiserror::Error, Debug)]
+#[allow(clippy::enum_variant_names)]
+pub enum ImportCommandError {
+    #[error("error while getting the information about the mod")]
+    InitError(#[from] InitError),
+    #[error("error while importing the archive")]
+    ImportError(#[from] ImportError),
+    #[error("error while creating the package")]
+    CreatePackageError(#[from] CreatePackageError),
+    #[error("error while create the destination file {0}")]
+    CreateDestinationError(PathBuf, #[source] io::Error),
+    #[error("error flushing the destination file {0}")]
+    FlushDestinationError(PathBuf, #[source] io::Error),
+    #[error("neither the output file nor the project directory were provided")]
+    MissingOutputError,
+}
+
+/// tell the user what was guessed about the layout of the imported archive. Only the files
+/// that won't be installed are reported in json, as warnings.
+fn print_layout(layout: &ArchiveLayout, message_format: MessageFormat) {
+    let reporter = message_format.reporter();
+    for file in &layout.unhandled {
+        reporter.report(Event::Warning {
+            message: format!("the file {:?} won't be installed", file),
+        });
+    }
+    if message_format == MessageFormat::Json {
+        return;
+    };
+    if let Some(root) = &layout.root {
+        println!("the mod is in the folder {:?} of the archive", root);
+    };
+    if layout.install_strategies.is_empty() {
+        println!("no known folder found, the files will be installed as is in the game directory");
+    } else {
+        println!(
+            "guessed the install strategies {}",
+            format_str_id_list(&layout.install_strategies)
+        );
+    };
+}
+
+pub fn import(parameter: ImportParameter) -> Result<(), ImportCommandError> {
+    let default_identifier = parameter
+        .archive_file
+        .file_name()
+        .map(|name| name.to_string_lossy())
+        .map(|name| {
+            let format = ArchiveFormat::WRITABLE
+                .iter()
+                .chain(&[ArchiveFormat::SevenZ])
+                .find(|format| name.ends_with(&format!(".{}", format.name())));
+            let stem = match format {
+                Some(format) => &name[..name.len() - format.name().len() - 1],
+                None => &name,
+            };
+            identifier_from_name(stem)
+        });
+    let information = ask_information(&parameter.information, default_identifier.as_deref())?;
+
+    let output_file = match (parameter.output_file, parameter.project_dir) {
+        (_, Some(project_dir)) => {
+            let layout =
+                import_archive_as_project(&parameter.archive_file, information, &project_dir)?;
+            print_layout(&layout, parameter.message_format);
+            if parameter.message_format == MessageFormat::Human {
+                println!(
+                    "imported {:?} as the mod project {:?}",
+                    parameter.archive_file, project_dir
+                );
+            };
+            return Ok(());
+        }
+        (Some(output_file), None) => output_file,
+        (None, None) => return Err(ImportCommandError::MissingOutputError),
+    };
+
+    let strategies = StrategySet::default();
+    let mut options = PackageOptions::new(&strategies);
+    options.timestamp = source_date_epoch()?;
+    options.format = parameter.format;
+    let reporter = parameter.message_format.reporter();
+    options.reporter = reporter.as_ref();
+    let mut destination_file = BufWriter::new(File::create(&output_file).map_err(|err| {
+        ImportCommandError::CreateDestinationError(output_file.to_path_buf(), err)
+    })?);
+    let layout = import_archive(
+        &parameter.archive_file,
+        information,
+        &mut destination_file,
+        &options,
+    )?;
+    destination_file
+        .flush()
+        .map_err(|err| ImportCommandError::FlushDestinationError(output_file.to_path_buf(), err))?;
+    print_layout(&layout, parameter.message_format);
+    if parameter.message_format == MessageFormat::Human {
+        println!(
+            "imported {:?} as the package {:?}",
+            parameter.archive_file, output_file
+        );
+    };
+    Ok(())
+}
@@ -0,0 +1,35 @@
+use crate::reporter::MessageFormat;
+use gpm_core::display::info::format_package_info;
+use gpm_core::info::{package_info, InfoError};
+use std::path::PathBuf;
+
+pub struct InfoParameter {
+    /// the mod project directory or package archive to describe
+    pub path: PathBuf,
+    pub message_format: MessageFormat,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum InfoCommandError {
+    #[error("can't read the package")]
+    InfoError(#[from] InfoError),
+    #[error("can't encode the package information in json. Probably internal error")]
+    EncodeJsonError(#[source] serde_json::Error),
+}
+
+pub fn info(parameter: InfoParameter) -> Result<(), InfoCommandError> {
+    let info = package_info(&parameter.path)?;
+    match parameter.message_format {
+        MessageFormat::Human => {
+            for unknown_key in &info.unknown_keys {
+                println!("warning: {}", unknown_key);
+            }
+            println!("{}", format_package_info(&info));
+        }
+        MessageFormat::Json => println!(
+            "{}",
+            serde_json::to_string_pretty(&info).map_err(InfoCommandError::EncodeJsonError)?
+        ),
+    };
+    Ok(())
+}
@@ -1,6 +1,150 @@
-use std::result::Result;
+use gpm_core::display::list::format_str_id_list;
+use gpm_core::package::{Package, PackageInformation};
+use gpm_core::semver::Version;
+use gpm_core::store_project::InitProjectError;
+use std::io;
+use std::io::{BufRead, Write};
+use std::path::PathBuf;
 
-pub fn init() -> Result<(), anyhow::Error> {
-    println!("init not implemented"); //TODO:
+/// The information about a mod given on the command line, completed by prompting the user
+pub struct InformationParameter {
+    pub interactive: bool,
+    pub creator: Option<String>,
+    pub identifier: Option<String>,
+    pub version: Option<String>,
+    pub display_name: Option<String>,
+    pub description: Option<String>,
+    pub license: Option<String>,
+    pub website_url: Option<String>,
+}
+
+pub struct InitParameter {
+    pub project_dir: PathBuf,
+    pub force: bool,
+    pub information: InformationParameter,
+}
+
+#[derive(thiserror::Error, Debug)]
+#[allow(clippy::enum_variant_names)] // error variants are suffixed with `Error` across the workspace
+pub enum InitError {
+    #[error("error while creating the mod project")]
+    InitProjectError(#[from] InitProjectError),
+    #[error("error while reading the user input")]
+    PromptError(#[source] io::Error),
+    #[error("the version {0:?} isn't a valid semantic version (like 1.0.0)")]
+    InvalidVersionError(String, #[source] gpm_core::semver::Error),
+    #[error("the following field are required, but were not provided : {0}")]
+    MissingFieldError(String), //formatted missing field
+}
+
+/// ask the user for a value on the standard input. Return ``default`` if the user enter an empty
+/// line, or None if there is no default value.
+fn prompt(name: &str, default: Option<&str>) -> Result<Option<String>, InitError> {
+    match default {
+        Some(default) => print!("{} [{}]: ", name, default),
+        None => print!("{}: ", name),
+    };
+    io::stdout().flush().map_err(InitError::PromptError)?;
+    let mut line = String::new();
+    io::stdin()
+        .lock()
+        .read_line(&mut line)
+        .map_err(InitError::PromptError)?;
+    let line = line.trim();
+    if line.is_empty() {
+        Ok(default.map(str::to_string))
+    } else {
+        Ok(Some(line.to_string()))
+    }
+}
+
+/// return ``value`` if it is known, prompt the user for it in interactive mode, or fallback on
+/// ``default`` otherwise.
+fn get_field(
+    parameter: &InformationParameter,
+    name: &str,
+    value: &Option<String>,
+    default: Option<&str>,
+) -> Result<Option<String>, InitError> {
+    if value.is_some() {
+        Ok(value.clone())
+    } else if parameter.interactive {
+        prompt(name, default)
+    } else {
+        Ok(default.map(str::to_string))
+    }
+}
+
+/// the information about a mod, from the values given in ``parameter`` or asked to the user.
+/// Fail if a field required to publish the mod is missing.
+pub fn ask_information(
+    parameter: &InformationParameter,
+    default_identifier: Option<&str>,
+) -> Result<PackageInformation, InitError> {
+    let identifier = get_field(
+        parameter,
+        "identifier",
+        &parameter.identifier,
+        default_identifier,
+    )?;
+    let display_name = get_field(
+        parameter,
+        "display name",
+        &parameter.display_name,
+        identifier.as_deref(),
+    )?;
+    let version = get_field(parameter, "version", &parameter.version, Some("0.1.0"))?
+        .map(|version| {
+            Version::parse(&version).map_err(|err| InitError::InvalidVersionError(version, err))
+        })
+        .transpose()?;
+    let creator = get_field(parameter, "creator", &parameter.creator, None)?;
+    let description = get_field(parameter, "description", &parameter.description, None)?;
+    let license = get_field(parameter, "license", &parameter.license, None)?;
+
+    let package_information = PackageInformation {
+        creator,
+        identifier,
+        version,
+        display_name,
+        description,
+        license,
+        website_url: parameter.website_url.clone(),
+        dependencies: Vec::new(),
+        tags: Vec::new(),
+        install_strategies: Vec::new(),
+        uncompressed: Vec::new(),
+        include: Vec::new(),
+        exclude: Vec::new(),
+        extra_data: Vec::new(),
+    };
+
+    let missing_publish_field = package_information.missing_publish_field();
+    if !missing_publish_field.is_empty() {
+        return Err(InitError::MissingFieldError(format_str_id_list(
+            &missing_publish_field,
+        )));
+    };
+    Ok(package_information)
+}
+
+/// the default identifier of a mod named ``name``
+pub fn identifier_from_name(name: &str) -> String {
+    name.to_lowercase().replace(' ', "_")
+}
+
+pub fn init(parameter: InitParameter) -> Result<(), InitError> {
+    let default_identifier = parameter
+        .project_dir
+        .canonicalize()
+        .unwrap_or_else(|_| parameter.project_dir.clone())
+        .file_name()
+        .map(|name| identifier_from_name(&name.to_string_lossy()));
+    let package_information =
+        ask_information(&parameter.information, default_identifier.as_deref())?;
+
+    Package::new(package_information)
+        .init_project_directory(&parameter.project_dir, parameter.force)?;
+    println!("created a mod project in {:?}", parameter.project_dir);
     Ok(())
 }
@@ -0,0 +1,130 @@
+use crate::reporter::HumanReporter;
+use gpm_core::display::list::format_str_id_list;
+use gpm_core::install::{install as install_package, remove as remove_package};
+use gpm_core::install::{InstallError, InstallRequest};
+use gpm_core::install_strategy::StrategySet;
+use gpm_core::profile::{Profile, ProfileError, ProfileStore};
+use gpm_core::repository::{LocalRepository, RepositoryError};
+use gpm_core::semver::Version;
+use std::path::{Path, PathBuf};
+
+pub struct InstallParameter {
+    pub home: PathBuf,
+    pub profile: Option<String>,
+    pub repository: Option<PathBuf>,
+    /// the package to install, as `identifier` or `identifier@version`
+    pub package: Option<String>,
+    pub path: Option<PathBuf>,
+}
+
+pub struct RemoveParameter {
+    pub home: PathBuf,
+    pub profile: Option<String>,
+    pub repository: Option<PathBuf>,
+    pub identifier: String,
+}
+
+#[derive(thiserror::Error, Debug)]
+#[allow(clippy::enum_variant_names)]
+pub enum InstallCommandError {
+    #[error("error with the profile")]
+    ProfileError(#[from] ProfileError),
+    #[error("can't open the package repository")]
+    RepositoryError(#[from] RepositoryError),
+    #[error("error while installing the mod")]
+    InstallError(#[from] InstallError),
+    #[error("the version in {0:?} isn't a valid semantic version (like 1.0.0)")]
+    InvalidVersionError(String, #[source] gpm_core::semver::Error),
+    #[error("a package identifier or a path is required")]
+    MissingPackageError,
+}
+
+/// load the profile given by name, or the active one
+pub fn load_profile(home: &Path, name: &Option<String>) -> Result<Profile, ProfileError> {
+    let store = ProfileStore::open(home)?;
+    match name {
+        Some(name) => store.load(name),
+        None => store.load_active(),
+    }
+}
+
+/// open the repository given on the command line, or the one of the profile
+pub fn open_repository(
+    repository: &Option<PathBuf>,
+    profile: &Profile,
+) -> Result<Option<LocalRepository>, RepositoryError> {
+    repository
+        .as_ref()
+        .or(profile.repository.as_ref())
+        .map(|path| LocalRepository::open(path))
+        .transpose()
+}
+
+/// split `identifier@version` in its two parts
+fn parse_package(package: &str) -> Result<InstallRequest, InstallCommandError> {
+    let mut parts = package.splitn(2, '@');
+    let identifier = parts.next().unwrap_or_default().to_string(); //splitn always return a first part
+    let version = parts
+        .next()
+        .map(|version| {
+            Version::parse(version)
+                .map_err(|err| InstallCommandError::InvalidVersionError(package.to_string(), err))
+        })
+        .transpose()?;
+    Ok(InstallRequest::Repository {
+        identifier,
+        version,
+    })
+}
+
+pub fn install(parameter: InstallParameter) -> Result<(), InstallCommandError> {
+    let request = match (&parameter.package, parameter.path) {
+        (_, Some(path)) => InstallRequest::Path(
+            std::env::current_dir()
+                .map(|current_dir| current_dir.join(&path))
+                .unwrap_or(path),
+        ),
+        (Some(package), None) => parse_package(package)?,
+        (None, None) => return Err(InstallCommandError::MissingPackageError),
+    };
+    let mut profile = load_profile(&parameter.home, &parameter.profile)?;
+    let repository = open_repository(&parameter.repository, &profile)?;
+    let changed = install_package(
+        &mut profile,
+        request,
+        repository.as_ref(),
+        &StrategySet::default(),
+        &HumanReporter,
+    )?;
+    if changed.is_empty() {
+        println!(
+            "everything is already installed in the profile {}",
+            profile.name
+        );
+    } else {
+        println!(
+            "installed {} in the profile {}",
+            format_str_id_list(&changed),
+            profile.name
+        );
+    };
+    Ok(())
+}
+
+pub fn remove(parameter: RemoveParameter) -> Result<(), InstallCommandError> {
+    let mut profile = load_profile(&parameter.home, &parameter.profile)?;
+    let repository = open_repository(&parameter.repository, &profile)?;
+    remove_package(
+        &mut profile,
+        &parameter.identifier,
+        repository.as_ref(),
+        &StrategySet::default(),
+        &HumanReporter,
+    )?;
+    println!(
+        "removed {} from the profile {}",
+        format_str_id_list(&[&parameter.identifier]),
+        profile.name
+    );
+    Ok(())
+}
@@ -0,0 +1,82 @@
+use crate::commands::install::load_profile;
+use gpm_core::profile::ProfileError;
+use gpm_core::signature::{
+    generate_key, public_key_path, write_key, SignatureError, SignaturePolicy, TrustedKeys,
+};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// How the public key to trust is given
+pub enum PublicKeySource {
+    /// the hex-encoded public key
+    Hex(String),
+    /// the path of a public key file, as written by `gpm key generate`
+    File(PathBuf),
+}
+
+pub struct KeyTrustParameter {
+    pub home: PathBuf,
+    pub profile: Option<String>,
+    pub name: String,
+    pub public_key: PublicKeySource,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum KeyError {
+    #[error("error with the profile")]
+    ProfileError(#[from] ProfileError),
+    #[error("error with the key")]
+    SignatureError(#[from] SignatureError),
+}
+
+pub fn key_generate(output: &Path) -> Result<(), KeyError> {
+    let key = generate_key();
+    write_key(&key, output)?;
+    println!(
+        "wrote the secret key to {:?} and the public key to {:?}. Keep the secret key private",
+        output,
+        public_key_path(output)
+    );
+    Ok(())
+}
+
+pub fn key_trust(parameter: KeyTrustParameter) -> Result<(), KeyError> {
+    let profile = load_profile(&parameter.home, &parameter.profile)?;
+    let public_key = match parameter.public_key {
+        PublicKeySource::Hex(public_key) => public_key,
+        PublicKeySource::File(path) => fs::read_to_string(&path)
+            .map_err(|err| SignatureError::FileIOError(path.clone(), err))?,
+    };
+    let mut trusted_keys = TrustedKeys::load(&profile)?;
+    trusted_keys.add(&parameter.name, &public_key)?;
+    trusted_keys.save(&profile)?;
+    println!(
+        "the profile {} now trusts the key {}",
+        profile.name, parameter.name
+    );
+    Ok(())
+}
+
+pub fn key_list(home: PathBuf, profile: Option<String>) -> Result<(), KeyError> {
+    let profile = load_profile(&home, &profile)?;
+    println!("signature policy: {}", profile.signature_policy);
+    for (name, public_key) in TrustedKeys::load(&profile)?.keys {
+        println!("{} {}", name, public_key);
+    }
+    Ok(())
+}
+
+pub fn key_policy(
+    home: PathBuf,
+    profile: Option<String>,
+    policy: SignaturePolicy,
+) -> Result<(), KeyError> {
+    let mut profile = load_profile(&home, &profile)?;
+    profile.signature_policy = policy;
+    profile.save()?;
+    println!(
+        "the signature policy of the profile {} is now {}",
+        profile.name, policy
+    );
+    Ok(())
+}
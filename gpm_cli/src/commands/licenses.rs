@@ -0,0 +1,63 @@
+use crate::commands::install::load_profile;
+use gpm_core::conflict::{profile_staged_packages, ConflictError};
+use gpm_core::display::list::format_str_id_list;
+use gpm_core::license::{license_report, LicenseError, LicenseIssue, LicensePolicy};
+use gpm_core::profile::ProfileError;
+use std::path::PathBuf;
+
+pub struct LicensesParameter {
+    pub home: PathBuf,
+    pub profile: Option<String>,
+    /// the license policy file to use instead of the one of the profile
+    pub policy: Option<PathBuf>,
+    /// check that the mods of the profile can be redistributed together, like in a modpack
+    pub redistribute: bool,
+}
+
+#[derive(thiserror::Error, Debug)]
+#[allow(clippy::enum_variant_names)]
+pub enum LicensesCommandError {
+    #[error("error with the profile")]
+    ProfileError(#[from] ProfileError),
+    #[error("can't read the information of the installed mods")]
+    ConflictError(#[from] ConflictError),
+    #[error("can't load the license policy")]
+    LicenseError(#[from] LicenseError),
+    #[error("{0} license problems found in the profile")]
+    IncompatibleLicenseError(usize),
+}
+
+pub fn licenses(parameter: LicensesParameter) -> Result<(), LicensesCommandError> {
+    let profile = load_profile(&parameter.home, &parameter.profile)?;
+    let policy = match &parameter.policy {
+        Some(policy_path) => LicensePolicy::load_file(policy_path)?,
+        None => LicensePolicy::load(&profile)?,
+    };
+    let packages = profile_staged_packages(&profile)?;
+    let report = license_report(
+        packages.iter().map(|package| &package.information),
+        &policy,
+        parameter.redistribute,
+    );
+
+    println!("licenses of the mods of the profile {}:", profile.name);
+    for (license, identifiers) in &report.licenses {
+        println!("{}: {}", license, format_str_id_list(identifiers));
+    }
+    let mut problems = 0;
+    for issue in &report.issues {
+        match issue {
+            LicenseIssue::Missing { .. } | LicenseIssue::NotSpdx { .. } => {
+                println!("warning: {}", issue)
+            }
+            LicenseIssue::NoRedistribution { .. } | LicenseIssue::Incompatible { .. } => {
+                problems += 1;
+                println!("error: {}", issue)
+            }
+        };
+    }
+    if problems > 0 {
+        return Err(LicensesCommandError::IncompatibleLicenseError(problems));
+    };
+    Ok(())
+}
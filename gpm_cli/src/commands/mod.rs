@@ -1,2 +1,12 @@
+pub mod conflicts;
+pub mod import;
+pub mod info;
 pub mod init;
+pub mod install;
+pub mod key;
+pub mod licenses;
 pub mod package;
+pub mod profile;
+pub mod repository;
+pub mod validate;
+pub mod verify;
@@ -1,15 +1,37 @@
-use gpm_core::package_writer::{create_package, CreatePackageError};
+use crate::reporter::MessageFormat;
+use gpm_core::archive::ArchiveFormat;
+use gpm_core::display::tree::format_package_plan;
+use gpm_core::install_strategy::StrategySet;
+use gpm_core::package_writer::{
+    create_package, plan_package, source_date_epoch, CreatePackageError, LinkPolicy, PackageOptions,
+};
+use gpm_core::signature::{load_signing_key, SignatureError};
 use std::fs::File;
 use std::io;
 use std::io::{BufWriter, Write};
 use std::path::PathBuf;
+use std::thread;
 
 pub struct PackageParameter {
     pub input_dir: PathBuf,
-    pub output_file: PathBuf,
+    /// the archive to create. Only optional for dry runs.
+    pub output_file: Option<PathBuf>,
+    /// only show what would be in the archive, without writing it
+    pub dry_run: bool,
+    /// gitignore-style patterns of more files not to package
+    pub exclude: Vec<String>,
+    /// what to do with the links leading outside of the project
+    pub links: LinkPolicy,
+    /// the secret key to sign the package with
+    pub signing_key: Option<PathBuf>,
+    /// the number of threads compressing files, or 0 to use every core
+    pub threads: usize,
+    pub format: ArchiveFormat,
+    pub message_format: MessageFormat,
 }
 
 #[derive(thiserror::Error, Debug)]
+#[allow(clippy::enum_variant_names)] // error variants are suffixed with `Error` across the workspace
 pub enum PackageError {
     #[error("error while creating the compressed package")]
     CreatePackageError(#[from] CreatePackageError),
@@ -17,16 +39,58 @@ pub enum PackageError {
     CreateDestinationError(PathBuf, #[source] io::Error),
     #[error("error flushing the destination file {0}")]
     FlushDestinationError(PathBuf, #[source] io::Error),
+    #[error("can't load the signing key")]
+    SignatureError(#[from] SignatureError),
+    #[error("can't encode the content of the package in json. Probably internal error")]
+    EncodeJsonError(#[source] serde_json::Error),
+    #[error("the output file wasn't provided")]
+    MissingOutputError,
 }
 
 pub fn package(parameter: PackageParameter) -> Result<(), PackageError> {
-    let mut destination_file =
-        BufWriter::new(File::create(&parameter.output_file).map_err(|err| {
-            PackageError::CreateDestinationError(parameter.output_file.to_path_buf(), err)
-        })?);
-    create_package(&parameter.input_dir, &mut destination_file)?;
-    destination_file.flush().map_err(|err| {
-        PackageError::FlushDestinationError(parameter.output_file.to_path_buf(), err)
-    })?;
+    let signing_key = parameter
+        .signing_key
+        .as_deref()
+        .map(load_signing_key)
+        .transpose()?;
+    let strategies = StrategySet::default();
+    let mut options = PackageOptions::new(&strategies);
+    options.signing_key = signing_key.as_ref();
+    options.timestamp = source_date_epoch()?;
+    options.format = parameter.format;
+    options.walk.exclude = &parameter.exclude;
+    options.walk.output = parameter.output_file.as_deref();
+    options.walk.links = parameter.links;
+    let reporter = parameter.message_format.reporter();
+    options.reporter = reporter.as_ref();
+    options.threads = match parameter.threads {
+        0 => thread::available_parallelism().map_or(1, usize::from),
+        threads => threads,
+    };
+
+    if parameter.dry_run {
+        let plan = plan_package(&parameter.input_dir, &options)?;
+        match parameter.message_format {
+            MessageFormat::Human => println!("{}", format_package_plan(&plan)),
+            MessageFormat::Json => println!(
+                "{}",
+                serde_json::to_string(&plan).map_err(PackageError::EncodeJsonError)?
+            ),
+        };
+        return Ok(());
+    };
+
+    let output_file = parameter
+        .output_file
+        .as_deref()
+        .ok_or(PackageError::MissingOutputError)?;
+    let mut destination_file = BufWriter::new(
+        File::create(output_file)
+            .map_err(|err| PackageError::CreateDestinationError(output_file.to_path_buf(), err))?,
+    );
+    create_package(&parameter.input_dir, &mut destination_file, &options)?;
+    destination_file
+        .flush()
+        .map_err(|err| PackageError::FlushDestinationError(output_file.to_path_buf(), err))?;
     Ok(())
 }
@@ -0,0 +1,69 @@
+use gpm_core::profile::{ProfileError, ProfileStore};
+use std::path::PathBuf;
+
+pub struct ProfileCreateParameter {
+    pub home: PathBuf,
+    pub name: String,
+    pub game_dir: PathBuf,
+    pub staging_dir: Option<PathBuf>,
+    pub repository: Option<PathBuf>,
+}
+
+/// make ``path`` absolute, so the profile doesn't depend on the directory gpm is run from
+fn absolute_path(path: PathBuf) -> PathBuf {
+    std::env::current_dir()
+        .map(|current_dir| current_dir.join(&path))
+        .unwrap_or(path)
+}
+
+pub fn profile_create(parameter: ProfileCreateParameter) -> Result<(), ProfileError> {
+    let mut store = ProfileStore::open(&parameter.home)?;
+    let mut profile = store.create(
+        &parameter.name,
+        &absolute_path(parameter.game_dir),
+        parameter.staging_dir.map(absolute_path).as_deref(),
+    )?;
+    if let Some(repository) = parameter.repository {
+        profile.repository = Some(absolute_path(repository));
+        profile.save()?;
+    };
+    println!(
+        "created the profile {} for the game in {:?}",
+        profile.name, profile.game_dir
+    );
+    Ok(())
+}
+
+pub fn profile_list(home: PathBuf) -> Result<(), ProfileError> {
+    let store = ProfileStore::open(&home)?;
+    for name in store.list()? {
+        let profile = store.load(&name)?;
+        let marker = if store.active() == Some(name.as_str()) {
+            "*"
+        } else {
+            " "
+        };
+        println!(
+            "{} {} ({} mods, game in {:?})",
+            marker,
+            name,
+            profile.lock_file.dependencies.len(),
+            profile.game_dir
+        );
+    }
+    Ok(())
+}
+
+pub fn profile_switch(home: PathBuf, name: &str) -> Result<(), ProfileError> {
+    let mut store = ProfileStore::open(&home)?;
+    store.switch(name)?;
+    println!("the active profile is now {}", name);
+    Ok(())
+}
+
+pub fn profile_delete(home: PathBuf, name: &str) -> Result<(), ProfileError> {
+    let mut store = ProfileStore::open(&home)?;
+    store.delete(name)?;
+    println!("deleted the profile {}", name);
+    Ok(())
+}
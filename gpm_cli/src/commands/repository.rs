@@ -0,0 +1,38 @@
+use gpm_core::display::list::format_str_id_list;
+use gpm_core::repository::{LocalRepository, RepositoryError};
+use std::path::PathBuf;
+
+pub struct RepositoryAddParameter {
+    pub repository_dir: PathBuf,
+    pub archive_file: PathBuf,
+}
+
+pub struct RepositoryListParameter {
+    pub repository_dir: PathBuf,
+    pub tag: Option<String>,
+}
+
+pub fn repository_add(parameter: RepositoryAddParameter) -> Result<(), RepositoryError> {
+    let mut repository = LocalRepository::create(&parameter.repository_dir)?;
+    let entry = repository.add_archive(&parameter.archive_file)?;
+    println!(
+        "added {} {} to the repository {:?}",
+        entry.identifier, entry.version, parameter.repository_dir
+    );
+    Ok(())
+}
+
+pub fn repository_list(parameter: RepositoryListParameter) -> Result<(), RepositoryError> {
+    let repository = LocalRepository::open(&parameter.repository_dir)?;
+    let entries = match &parameter.tag {
+        Some(tag) => repository.find_by_tag(tag),
+        None => repository.entries().iter().collect(),
+    };
+    for entry in entries {
+        println!("{} {}", entry.identifier, entry.version);
+        if !entry.tags.is_empty() {
+            println!("  tags: {}", format_str_id_list(&entry.tags));
+        };
+    }
+    Ok(())
+}
@@ -0,0 +1,67 @@
+use crate::reporter::MessageFormat;
+use gpm_core::install_strategy::StrategySet;
+use gpm_core::validate::{
+    has_error, validate_project, Diagnostic, Severity, ValidateError, ValidateOptions,
+};
+use std::path::PathBuf;
+
+pub struct ValidateParameter {
+    pub project_dir: PathBuf,
+    /// the size, in bytes, above which a file is reported
+    pub max_file_size: Option<u64>,
+    /// print the diagnostics as text or as a json object
+    pub message_format: MessageFormat,
+}
+
+#[derive(thiserror::Error, Debug)]
+#[allow(clippy::enum_variant_names)]
+pub enum ValidateCommandError {
+    #[error("can't validate the mod project")]
+    ValidateError(#[from] ValidateError),
+    #[error("can't encode the diagnostics in json. Probably internal error")]
+    EncodeJsonError(#[source] serde_json::Error),
+    #[error("the mod project has {0} errors")]
+    InvalidProjectError(usize),
+}
+
+fn count(diagnostics: &[Diagnostic], severity: Severity) -> usize {
+    diagnostics
+        .iter()
+        .filter(|diagnostic| diagnostic.severity == severity)
+        .count()
+}
+
+pub fn validate(parameter: ValidateParameter) -> Result<(), ValidateCommandError> {
+    let strategies = StrategySet::default();
+    let mut options = ValidateOptions::new(&strategies);
+    if let Some(max_file_size) = parameter.max_file_size {
+        options.max_file_size = max_file_size;
+    };
+    let diagnostics = validate_project(&parameter.project_dir, &options)?;
+    let errors = count(&diagnostics, Severity::Error);
+
+    if parameter.message_format == MessageFormat::Json {
+        let output = serde_json::json!({
+            "valid": !has_error(&diagnostics),
+            "diagnostics": diagnostics,
+        });
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&output).map_err(ValidateCommandError::EncodeJsonError)?
+        );
+    } else {
+        for diagnostic in &diagnostics {
+            println!("{}", diagnostic);
+        }
+        println!(
+            "{} errors, {} warnings in the mod project {:?}",
+            errors,
+            count(&diagnostics, Severity::Warning),
+            parameter.project_dir
+        );
+    };
+    if errors > 0 {
+        return Err(ValidateCommandError::InvalidProjectError(errors));
+    };
+    Ok(())
+}
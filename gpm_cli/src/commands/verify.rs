@@ -0,0 +1,51 @@
+use crate::commands::install::load_profile;
+use gpm_core::deploy::{verify_deployment, DeployError, DeploymentIssue, DeploymentManifest};
+use gpm_core::profile::ProfileError;
+use std::path::PathBuf;
+
+pub struct VerifyParameter {
+    pub home: PathBuf,
+    pub profile: Option<String>,
+}
+
+#[derive(thiserror::Error, Debug)]
+#[allow(clippy::enum_variant_names)]
+pub enum VerifyError {
+    #[error("error with the profile")]
+    ProfileError(#[from] ProfileError),
+    #[error("can't check the deployed files")]
+    DeployError(#[from] DeployError),
+    #[error("{0} deployed files don't match the deployment manifest")]
+    MismatchError(usize),
+}
+
+pub fn verify(parameter: VerifyParameter) -> Result<(), VerifyError> {
+    let profile = load_profile(&parameter.home, &parameter.profile)?;
+    let manifest = DeploymentManifest::load(&profile)?;
+    let issues = verify_deployment(&profile)?;
+    for issue in &issues {
+        let state = match issue {
+            DeploymentIssue::Missing(_) => "is missing",
+            DeploymentIssue::Modified(_) => "was modified",
+        };
+        println!(
+            "{} {} (deployed by {})",
+            issue.path(),
+            state,
+            manifest
+                .files
+                .get(issue.path())
+                .map(String::as_str)
+                .unwrap_or("an unknown package")
+        );
+    }
+    if !issues.is_empty() {
+        return Err(VerifyError::MismatchError(issues.len()));
+    };
+    println!(
+        "the {} files deployed in the profile {} are intact",
+        manifest.files.len(),
+        profile.name
+    );
+    Ok(())
+}
@@ -1,16 +1,134 @@
-use clap::{App, Arg, SubCommand};
-use std::path::PathBuf;
+use clap::{App, Arg, ArgMatches, SubCommand};
+use gpm_core::archive::ArchiveFormat;
+use gpm_core::package_writer::LinkPolicy;
+use gpm_core::profile::{ProfileStore, GPM_HOME_ENV};
+use reporter::MessageFormat;
+use std::path::{Path, PathBuf};
 mod commands;
+mod reporter;
+
+/// return the gpm home directory given with `--home`, or the default one
+fn gpm_home(arg: &ArgMatches) -> Result<PathBuf, anyhow::Error> {
+    arg.value_of("home")
+        .map(PathBuf::from)
+        .or_else(ProfileStore::default_home)
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "can't find the gpm home directory, use --home or the {} environment variable",
+                GPM_HOME_ENV
+            )
+        })
+}
+
+/// the arguments of the commands asking for the information about a mod
+fn information_args<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    vec![
+        Arg::with_name("no_prompt")
+            .long("no-prompt")
+            .help("don't ask for the missing values, use the default ones"),
+        Arg::with_name("identifier")
+            .long("identifier")
+            .takes_value(true)
+            .help("the unique identifier of the mod"),
+        Arg::with_name("creator")
+            .long("creator")
+            .takes_value(true)
+            .help("the creator of the mod"),
+        Arg::with_name("version")
+            .long("mod-version")
+            .takes_value(true)
+            .help("the version of the mod"),
+        Arg::with_name("display_name")
+            .long("display-name")
+            .takes_value(true)
+            .help("the human readable name of the mod"),
+        Arg::with_name("description")
+            .long("description")
+            .takes_value(true)
+            .help("a short description of the mod"),
+        Arg::with_name("license")
+            .long("license")
+            .takes_value(true)
+            .help("the license of the mod"),
+        Arg::with_name("website_url")
+            .long("website-url")
+            .takes_value(true)
+            .help("the website of the mod"),
+    ]
+}
+
+/// the [`commands::init::InformationParameter`] from the arguments of [`information_args`]
+fn information_parameter(arg: &ArgMatches) -> commands::init::InformationParameter {
+    let optional_string = |name| arg.value_of(name).map(str::to_string);
+    commands::init::InformationParameter {
+        interactive: !arg.is_present("no_prompt"),
+        creator: optional_string("creator"),
+        identifier: optional_string("identifier"),
+        version: optional_string("version"),
+        display_name: optional_string("display_name"),
+        description: optional_string("description"),
+        license: optional_string("license"),
+        website_url: optional_string("website_url"),
+    }
+}
+
+/// the archive format given with `--format`, or the default one
+fn archive_format(arg: &ArgMatches) -> Result<ArchiveFormat, anyhow::Error> {
+    Ok(arg
+        .value_of("format")
+        .map(str::parse)
+        .transpose()
+        .map_err(anyhow::Error::msg)?
+        .unwrap_or_default())
+}
+
+/// the `--message-format` argument, choosing how the messages of a command are printed
+fn message_format_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("message_format")
+        .long("message-format")
+        .takes_value(true)
+        .possible_values(MessageFormat::NAMES)
+        .help("print the messages as text, or as one json object per line (default to human)")
+}
+
+/// the message format given with `--message-format`, or the default one
+fn message_format(arg: &ArgMatches) -> Result<MessageFormat, anyhow::Error> {
+    Ok(arg
+        .value_of("message_format")
+        .map(str::parse)
+        .transpose()
+        .map_err(anyhow::Error::msg)?
+        .unwrap_or_default())
+}
 
 fn main() -> Result<(), anyhow::Error> {
     let matches = App::new("gpm")
         .version("0.1")
         .author("TODO <TODO@users.noreply.github.com>")
         .about("Games Package Manager utility")
+        .arg(
+            Arg::with_name("home")
+                .long("home")
+                .takes_value(true)
+                .global(true)
+                .help("the directory where gpm store its profiles (default to ~/.gpm)"),
+        )
         .subcommand(
             SubCommand::with_name("init")
                 .version("0.1")
-                .about("creates an mod project in the current directory"),
+                .about("creates an mod project in the current directory")
+                .arg(
+                    Arg::with_name("directory")
+                        .short("d")
+                        .takes_value(true)
+                        .help("the directory to create the mod project in"),
+                )
+                .arg(
+                    Arg::with_name("force")
+                        .long("force")
+                        .help("overwrite the configuration of an existing project"),
+                )
+                .args(&information_args()),
         )
         .subcommand(
             SubCommand::with_name("package")
@@ -25,20 +143,558 @@ fn main() -> Result<(), anyhow::Error> {
                     Arg::with_name("output_file")
                         .short("o")
                         .takes_value(true)
-                        .required(true)
+                        .required_unless("dry_run")
                         .help("the output file to create"),
+                )
+                .arg(
+                    Arg::with_name("dry_run")
+                        .long("dry-run")
+                        .help("show the files that would be in the archive, and why the others are ignored, without writing it"),
+                )
+                .arg(
+                    Arg::with_name("exclude")
+                        .long("exclude")
+                        .takes_value(true)
+                        .multiple(true)
+                        .number_of_values(1)
+                        .help("a gitignore-style pattern of files not to package, can be repeated"),
+                )
+                .arg(
+                    Arg::with_name("links")
+                        .long("links")
+                        .takes_value(true)
+                        .possible_values(LinkPolicy::NAMES)
+                        .help("what to do with the links leading outside of the mod project (default to follow, with a warning)"),
+                )
+                .arg(
+                    Arg::with_name("sign")
+                        .long("sign")
+                        .takes_value(true)
+                        .help("sign the package with the secret key in this file"),
+                )
+                .arg(
+                    Arg::with_name("jobs")
+                        .short("j")
+                        .long("jobs")
+                        .takes_value(true)
+                        .validator(|jobs| jobs.parse::<usize>().map(|_| ()).map_err(|err| err.to_string()))
+                        .help("the number of threads compressing files, 0 to use every core (default to 1)"),
+                )
+                .arg(
+                    Arg::with_name("format")
+                        .long("format")
+                        .takes_value(true)
+                        .possible_values(&["zip", "tar.zst", "tar.gz"])
+                        .help("the format of the archive (default to zip)"),
+                )
+                .arg(message_format_arg()),
+        )
+        .subcommand(
+            SubCommand::with_name("validate")
+                .about("check a mod project for problems, reporting all of them")
+                .arg(
+                    Arg::with_name("directory")
+                        .short("d")
+                        .takes_value(true)
+                        .help("the directory of the mod project (default to the current one)"),
+                )
+                .arg(
+                    Arg::with_name("max_file_size")
+                        .long("max-file-size")
+                        .takes_value(true)
+                        .validator(|size| size.parse::<u64>().map(|_| ()).map_err(|err| err.to_string()))
+                        .help("the size, in MiB, above which a file is reported (default to 1024)"),
+                )
+                .arg(message_format_arg()),
+        )
+        .subcommand(
+            SubCommand::with_name("info")
+                .alias("show")
+                .about("show the information and the files of a mod project or package archive")
+                .arg(
+                    Arg::with_name("path")
+                        .help("the mod project directory or package archive (default to the current directory)"),
+                )
+                .arg(message_format_arg()),
+        )
+        .subcommand(
+            SubCommand::with_name("import")
+                .about("convert the zip or 7z archive of a mod made without gpm into a package")
+                .arg(
+                    Arg::with_name("archive_file")
+                        .required(true)
+                        .help("the archive of the mod to import"),
+                )
+                .arg(
+                    Arg::with_name("output_file")
+                        .short("o")
+                        .takes_value(true)
+                        .required_unless("directory")
+                        .help("the package file to create"),
+                )
+                .arg(
+                    Arg::with_name("directory")
+                        .short("d")
+                        .takes_value(true)
+                        .conflicts_with("output_file")
+                        .help("create a mod project in this directory instead of a package, to edit it before packaging it"),
+                )
+                .arg(
+                    Arg::with_name("format")
+                        .long("format")
+                        .takes_value(true)
+                        .possible_values(&["zip", "tar.zst", "tar.gz"])
+                        .help("the format of the package (default to zip)"),
+                )
+                .arg(message_format_arg())
+                .args(&information_args()),
+        )
+        .subcommand(
+            SubCommand::with_name("repository")
+                .about("manage a local package repository")
+                .arg(
+                    Arg::with_name("repository_dir")
+                        .short("r")
+                        .long("repository")
+                        .takes_value(true)
+                        .required(true)
+                        .help("the directory of the repository"),
+                )
+                .subcommand(
+                    SubCommand::with_name("add")
+                        .about("add a package archive to the repository")
+                        .arg(
+                            Arg::with_name("archive_file")
+                                .required(true)
+                                .help("the package archive to add"),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("list")
+                        .about("list the packages of the repository")
+                        .arg(
+                            Arg::with_name("tag")
+                                .long("tag")
+                                .takes_value(true)
+                                .help("only list the packages with this tag"),
+                        ),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("profile")
+                .about("manage the sets of installed mods")
+                .subcommand(
+                    SubCommand::with_name("create")
+                        .about("create a new profile")
+                        .arg(Arg::with_name("name").required(true))
+                        .arg(
+                            Arg::with_name("game_dir")
+                                .long("game-dir")
+                                .takes_value(true)
+                                .required(true)
+                                .help("the directory of the game to install the mods in"),
+                        )
+                        .arg(
+                            Arg::with_name("staging_dir")
+                                .long("staging-dir")
+                                .takes_value(true)
+                                .help("where to extract the mods before installing them"),
+                        )
+                        .arg(
+                            Arg::with_name("repository_dir")
+                                .short("r")
+                                .long("repository")
+                                .takes_value(true)
+                                .help("the package repository to install the mods from"),
+                        ),
+                )
+                .subcommand(SubCommand::with_name("list").about("list the profiles"))
+                .subcommand(
+                    SubCommand::with_name("switch")
+                        .about("change the active profile")
+                        .arg(Arg::with_name("name").required(true)),
+                )
+                .subcommand(
+                    SubCommand::with_name("delete")
+                        .about("delete a profile")
+                        .arg(Arg::with_name("name").required(true)),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("install")
+                .about("install a mod in a profile")
+                .arg(
+                    Arg::with_name("package")
+                        .required_unless("path")
+                        .help("the mod to install, as identifier or identifier@version"),
+                )
+                .arg(
+                    Arg::with_name("path")
+                        .long("path")
+                        .takes_value(true)
+                        .conflicts_with("package")
+                        .help("install the mod project directory or package archive at this path"),
+                )
+                .arg(
+                    Arg::with_name("profile")
+                        .short("p")
+                        .long("profile")
+                        .takes_value(true)
+                        .help("the profile to install the mod in (default to the active one)"),
+                )
+                .arg(
+                    Arg::with_name("repository_dir")
+                        .short("r")
+                        .long("repository")
+                        .takes_value(true)
+                        .help("the package repository (default to the one of the profile)"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("remove")
+                .about("remove a mod from a profile")
+                .arg(
+                    Arg::with_name("identifier")
+                        .required(true)
+                        .help("the identifier of the mod to remove"),
+                )
+                .arg(
+                    Arg::with_name("profile")
+                        .short("p")
+                        .long("profile")
+                        .takes_value(true)
+                        .help("the profile to remove the mod from (default to the active one)"),
+                )
+                .arg(
+                    Arg::with_name("repository_dir")
+                        .short("r")
+                        .long("repository")
+                        .takes_value(true)
+                        .help("the package repository (default to the one of the profile)"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("conflicts")
+                .about("list the files written by more than one mod of a profile")
+                .arg(
+                    Arg::with_name("profile")
+                        .short("p")
+                        .long("profile")
+                        .takes_value(true)
+                        .help("the profile to check (default to the active one)"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("load-order")
+                .about("display or change the order the mods of a profile are deployed in")
+                .arg(Arg::with_name("identifier").multiple(true).help(
+                    "the mods to load after the others, in this order. They win the conflicts",
+                ))
+                .arg(
+                    Arg::with_name("clear")
+                        .long("clear")
+                        .conflicts_with("identifier")
+                        .help("go back to the default load order"),
+                )
+                .arg(
+                    Arg::with_name("profile")
+                        .short("p")
+                        .long("profile")
+                        .takes_value(true)
+                        .help("the profile to use (default to the active one)"),
+                )
+                .arg(
+                    Arg::with_name("repository_dir")
+                        .short("r")
+                        .long("repository")
+                        .takes_value(true)
+                        .help("the package repository (default to the one of the profile)"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("licenses")
+                .about("list the licenses of the mods of a profile, and check they can be used together")
+                .arg(
+                    Arg::with_name("profile")
+                        .short("p")
+                        .long("profile")
+                        .takes_value(true)
+                        .help("the profile to check (default to the active one)"),
+                )
+                .arg(
+                    Arg::with_name("policy")
+                        .long("policy")
+                        .takes_value(true)
+                        .help("the license policy file to use (default to the one of the profile)"),
+                )
+                .arg(
+                    Arg::with_name("redistribute")
+                        .long("redistribute")
+                        .help("check that the mods can be redistributed together, like in a modpack"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("verify")
+                .about("check that the files deployed in a profile weren't modified")
+                .arg(
+                    Arg::with_name("profile")
+                        .short("p")
+                        .long("profile")
+                        .takes_value(true)
+                        .help("the profile to check (default to the active one)"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("key")
+                .about("manage the keys used to sign packages")
+                .subcommand(
+                    SubCommand::with_name("generate")
+                        .about("generate a new signing key")
+                        .arg(
+                            Arg::with_name("output")
+                                .required(true)
+                                .help("the file to write the secret key to. The public key is written next to it, with a .pub extension"),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("trust")
+                        .about("trust the packages signed by a key in a profile")
+                        .arg(
+                            Arg::with_name("name")
+                                .required(true)
+                                .help("the name of the key, usually its owner"),
+                        )
+                        .arg(
+                            Arg::with_name("public_key")
+                                .required_unless("file")
+                                .conflicts_with("file")
+                                .help("the hex-encoded public key"),
+                        )
+                        .arg(
+                            Arg::with_name("file")
+                                .short("f")
+                                .long("file")
+                                .takes_value(true)
+                                .help("read the public key from this file instead"),
+                        )
+                        .arg(
+                            Arg::with_name("profile")
+                                .short("p")
+                                .long("profile")
+                                .takes_value(true)
+                                .help("the profile to use (default to the active one)"),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("list")
+                        .about("list the keys trusted by a profile")
+                        .arg(
+                            Arg::with_name("profile")
+                                .short("p")
+                                .long("profile")
+                                .takes_value(true)
+                                .help("the profile to use (default to the active one)"),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("policy")
+                        .about("choose how a profile handle packages that aren't signed by a trusted key")
+                        .arg(
+                            Arg::with_name("policy")
+                                .required(true)
+                                .possible_values(&["strict", "warn", "off"])
+                                .help("strict refuse them, warn install them with a warning, off doesn't check signatures"),
+                        )
+                        .arg(
+                            Arg::with_name("profile")
+                                .short("p")
+                                .long("profile")
+                                .takes_value(true)
+                                .help("the profile to use (default to the active one)"),
+                        ),
                 ),
         )
         .get_matches();
 
     match matches.subcommand() {
-        ("init", _) => commands::init::init()?,
+        ("init", Some(init_arg)) => {
+            commands::init::init(commands::init::InitParameter {
+                project_dir: PathBuf::from(init_arg.value_of("directory").unwrap_or(".")),
+                force: init_arg.is_present("force"),
+                information: information_parameter(init_arg),
+            })?;
+        }
         ("package", Some(archive_arg)) => {
             commands::package::package(commands::package::PackageParameter {
                 input_dir: PathBuf::from(archive_arg.value_of("input_dir").unwrap_or(".")),
-                output_file: PathBuf::from(archive_arg.value_of("output_file").unwrap()), //unwrap: output_file is required
+                output_file: archive_arg.value_of("output_file").map(PathBuf::from),
+                dry_run: archive_arg.is_present("dry_run"),
+                exclude: archive_arg
+                    .values_of("exclude")
+                    .map(|patterns| patterns.map(str::to_string).collect())
+                    .unwrap_or_default(),
+                links: archive_arg
+                    .value_of("links")
+                    .map(str::parse)
+                    .transpose()
+                    .map_err(anyhow::Error::msg)?
+                    .unwrap_or_default(),
+                signing_key: archive_arg.value_of("sign").map(PathBuf::from),
+                threads: archive_arg
+                    .value_of("jobs")
+                    .map(|jobs| jobs.parse().unwrap()) //unwrap: checked by the validator
+                    .unwrap_or(1),
+                format: archive_format(archive_arg)?,
+                message_format: message_format(archive_arg)?,
+            })?;
+        }
+        ("validate", Some(validate_arg)) => {
+            commands::validate::validate(commands::validate::ValidateParameter {
+                project_dir: PathBuf::from(validate_arg.value_of("directory").unwrap_or(".")),
+                max_file_size: validate_arg
+                    .value_of("max_file_size")
+                    .map(|size| size.parse::<u64>().unwrap() * 1024 * 1024), //unwrap: checked by the validator
+                message_format: message_format(validate_arg)?,
             })?;
         }
+        ("info", Some(info_arg)) => commands::info::info(commands::info::InfoParameter {
+            path: PathBuf::from(info_arg.value_of("path").unwrap_or(".")),
+            message_format: message_format(info_arg)?,
+        })?,
+        ("import", Some(import_arg)) => {
+            commands::import::import(commands::import::ImportParameter {
+                archive_file: PathBuf::from(import_arg.value_of("archive_file").unwrap()), //unwrap: archive_file is required
+                output_file: import_arg.value_of("output_file").map(PathBuf::from),
+                project_dir: import_arg.value_of("directory").map(PathBuf::from),
+                format: archive_format(import_arg)?,
+                message_format: message_format(import_arg)?,
+                information: information_parameter(import_arg),
+            })?;
+        }
+        ("repository", Some(repository_arg)) => {
+            let repository_dir = PathBuf::from(repository_arg.value_of("repository_dir").unwrap()); //unwrap: repository_dir is required
+            match repository_arg.subcommand() {
+                ("add", Some(add_arg)) => {
+                    commands::repository::repository_add(
+                        commands::repository::RepositoryAddParameter {
+                            repository_dir,
+                            archive_file: PathBuf::from(add_arg.value_of("archive_file").unwrap()), //unwrap: archive_file is required
+                        },
+                    )?
+                }
+                ("list", Some(list_arg)) => commands::repository::repository_list(
+                    commands::repository::RepositoryListParameter {
+                        repository_dir,
+                        tag: list_arg.value_of("tag").map(str::to_string),
+                    },
+                )?,
+                _ => println!("sub command unknown or unspecified"),
+            }
+        }
+        ("profile", Some(profile_arg)) => match profile_arg.subcommand() {
+            ("create", Some(create_arg)) => {
+                commands::profile::profile_create(commands::profile::ProfileCreateParameter {
+                    home: gpm_home(create_arg)?,
+                    name: create_arg.value_of("name").unwrap().to_string(), //unwrap: name is required
+                    game_dir: PathBuf::from(create_arg.value_of("game_dir").unwrap()), //unwrap: game_dir is required
+                    staging_dir: create_arg.value_of("staging_dir").map(PathBuf::from),
+                    repository: create_arg.value_of("repository_dir").map(PathBuf::from),
+                })?
+            }
+            ("list", Some(list_arg)) => commands::profile::profile_list(gpm_home(list_arg)?)?,
+            ("switch", Some(switch_arg)) => commands::profile::profile_switch(
+                gpm_home(switch_arg)?,
+                switch_arg.value_of("name").unwrap(), //unwrap: name is required
+            )?,
+            ("delete", Some(delete_arg)) => commands::profile::profile_delete(
+                gpm_home(delete_arg)?,
+                delete_arg.value_of("name").unwrap(), //unwrap: name is required
+            )?,
+            _ => println!("sub command unknown or unspecified"),
+        },
+        ("install", Some(install_arg)) => {
+            commands::install::install(commands::install::InstallParameter {
+                home: gpm_home(install_arg)?,
+                profile: install_arg.value_of("profile").map(str::to_string),
+                repository: install_arg.value_of("repository_dir").map(PathBuf::from),
+                package: install_arg.value_of("package").map(str::to_string),
+                path: install_arg.value_of("path").map(PathBuf::from),
+            })?
+        }
+        ("remove", Some(remove_arg)) => {
+            commands::install::remove(commands::install::RemoveParameter {
+                home: gpm_home(remove_arg)?,
+                profile: remove_arg.value_of("profile").map(str::to_string),
+                repository: remove_arg.value_of("repository_dir").map(PathBuf::from),
+                identifier: remove_arg.value_of("identifier").unwrap().to_string(), //unwrap: identifier is required
+            })?
+        }
+        ("conflicts", Some(conflicts_arg)) => {
+            commands::conflicts::conflicts(commands::conflicts::ConflictsParameter {
+                home: gpm_home(conflicts_arg)?,
+                profile: conflicts_arg.value_of("profile").map(str::to_string),
+            })?
+        }
+        ("load-order", Some(order_arg)) => {
+            commands::conflicts::load_order(commands::conflicts::LoadOrderParameter {
+                home: gpm_home(order_arg)?,
+                profile: order_arg.value_of("profile").map(str::to_string),
+                repository: order_arg.value_of("repository_dir").map(PathBuf::from),
+                overrides: if order_arg.is_present("clear") {
+                    Some(Vec::new())
+                } else {
+                    order_arg
+                        .values_of("identifier")
+                        .map(|identifiers| identifiers.map(str::to_string).collect())
+                },
+            })?
+        }
+        ("licenses", Some(licenses_arg)) => {
+            commands::licenses::licenses(commands::licenses::LicensesParameter {
+                home: gpm_home(licenses_arg)?,
+                profile: licenses_arg.value_of("profile").map(str::to_string),
+                policy: licenses_arg.value_of("policy").map(PathBuf::from),
+                redistribute: licenses_arg.is_present("redistribute"),
+            })?
+        }
+        ("verify", Some(verify_arg)) => {
+            commands::verify::verify(commands::verify::VerifyParameter {
+                home: gpm_home(verify_arg)?,
+                profile: verify_arg.value_of("profile").map(str::to_string),
+            })?
+        }
+        ("key", Some(key_arg)) => match key_arg.subcommand() {
+            ("generate", Some(generate_arg)) => commands::key::key_generate(Path::new(
+                generate_arg.value_of("output").unwrap(), //unwrap: output is required
+            ))?,
+            ("trust", Some(trust_arg)) => {
+                commands::key::key_trust(commands::key::KeyTrustParameter {
+                    home: gpm_home(trust_arg)?,
+                    profile: trust_arg.value_of("profile").map(str::to_string),
+                    name: trust_arg.value_of("name").unwrap().to_string(), //unwrap: name is required
+                    public_key: match trust_arg.value_of("file") {
+                        Some(file) => commands::key::PublicKeySource::File(PathBuf::from(file)),
+                        None => commands::key::PublicKeySource::Hex(
+                            trust_arg.value_of("public_key").unwrap().to_string(), //unwrap: public_key is required without file
+                        ),
+                    },
+                })?
+            }
+            ("list", Some(list_arg)) => commands::key::key_list(
+                gpm_home(list_arg)?,
+                list_arg.value_of("profile").map(str::to_string),
+            )?,
+            ("policy", Some(policy_arg)) => commands::key::key_policy(
+                gpm_home(policy_arg)?,
+                policy_arg.value_of("profile").map(str::to_string),
+                policy_arg
+                    .value_of("policy")
+                    .unwrap() //unwrap: policy is required
+                    .parse()
+                    .map_err(anyhow::Error::msg)?,
+            )?,
+            _ => println!("sub command unknown or unspecified"),
+        },
         _ => println!("sub command unknown or unspecified"),
     };
 
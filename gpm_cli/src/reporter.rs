@@ -0,0 +1,84 @@
+//! Render the [`Event`]s reported by gpm_core, as text for humans or as newline-delimited JSON
+//! for the programs running gpm.
+
+use gpm_core::report::{Event, Reporter};
+use std::str::FromStr;
+
+/// How the commands print their messages, chosen with `--message-format`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MessageFormat {
+    #[default]
+    Human,
+    Json,
+}
+
+impl MessageFormat {
+    pub const NAMES: &'static [&'static str] = &["human", "json"];
+
+    /// the reporter printing the events in this format
+    pub fn reporter(self) -> Box<dyn Reporter> {
+        match self {
+            Self::Human => Box::new(HumanReporter),
+            Self::Json => Box::new(JsonReporter),
+        }
+    }
+}
+
+impl FromStr for MessageFormat {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "human" => Ok(Self::Human),
+            "json" => Ok(Self::Json),
+            _ => Err(format!("unknown message format {:?}", name)),
+        }
+    }
+}
+
+/// print the events as sentences
+pub struct HumanReporter;
+
+impl Reporter for HumanReporter {
+    fn report(&self, event: Event) {
+        match event {
+            Event::FileAdded {
+                path,
+                compressed: true,
+            } => println!("adding the file {:?} to the archive", path),
+            Event::FileAdded {
+                path,
+                compressed: false,
+            } => println!(
+                "adding the file {:?} to the archive, without compression",
+                path
+            ),
+            Event::DirectoryAdded { path } => {
+                println!("adding the directory {:?} to the archive", path)
+            }
+            Event::FileIgnored { path } => println!("ignored {:?}", path),
+            Event::Warning { message } => println!("warning: {}", message),
+            Event::Finished {
+                files,
+                directories,
+                ignored,
+            } => println!(
+                "added {} files and {} directories to the archive, {} ignored",
+                files, directories, ignored
+            ),
+        }
+    }
+}
+
+/// print each event as a json object, on its own line
+pub struct JsonReporter;
+
+impl Reporter for JsonReporter {
+    fn report(&self, event: Event) {
+        match serde_json::to_string(&event) {
+            Ok(line) => println!("{}", line),
+            // stdout only has valid json lines, the error is printed apart
+            Err(err) => eprintln!("can't encode the event {:?} as json: {}", event, err),
+        };
+    }
+}
@@ -0,0 +1,855 @@
+//! The archive formats package archives can be stored in.
+//!
+//! Packages are zip archives by default, and can also be tar archives compressed with zstd or
+//! gzip. Each format is written through the [`ArchiveWriter`] trait, and [`ArchiveReader`] read
+//! any of them, detecting the format from the first bytes. 7z archives can be read too, but
+//! only to import existing mods, gpm doesn't write them.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::File;
+use std::io;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+use crate::checksum::{HashingReader, HashingWriter};
+
+use flate2::write::GzEncoder;
+use zip::read::ZipArchive;
+use zip::write::{FileOptions, ZipWriter};
+use zip::{CompressionMethod, DateTime};
+
+/// the permissions of the files in written archives
+const FILE_PERMISSIONS: u32 = 0o644;
+/// the permissions of the directories in written archives
+const DIRECTORY_PERMISSIONS: u32 = 0o755;
+/// the size above which a file need the zip64 extension
+const LARGE_FILE_SIZE: u64 = 0xFFFF_FFFF;
+/// the zstd compression level of tar.zst archives
+const ZSTD_LEVEL: i32 = 9;
+/// the file type bits of an unix mode
+const UNIX_FILE_TYPE_MASK: u32 = 0o170000;
+/// the file type bits of a symbolic link in an unix mode
+const UNIX_SYMLINK: u32 = 0o120000;
+/// set in the windows attributes of a 7z entry when the upper 16 bits are an unix mode
+const SEVEN_Z_UNIX_EXTENSION: u32 = 0x8000;
+
+#[derive(thiserror::Error, Debug)]
+pub enum ArchiveError {
+    #[error("io error with the file {0}")]
+    FileIOError(PathBuf, #[source] io::Error),
+    #[error("error while reading or writing the archive")]
+    ArchiveIOError(#[source] io::Error),
+    #[error("error while handling the zip archive")]
+    ZipError(#[from] zip::result::ZipError),
+    #[error("error while reading the 7z archive")]
+    SevenZError(#[from] sevenz_rust::Error),
+    #[error("error while copying {0} into the archive")]
+    CopyFileError(PathBuf, #[source] io::Error),
+    #[error("can't create a temporary file to compress in parallel")]
+    TemporaryFileError(#[source] io::Error),
+    #[error("the format of the archive isn't recognized. The known formats are zip, tar.zst, tar.gz and 7z")]
+    UnknownFormatError,
+    #[error("gpm can read {0} archives, but can't write them")]
+    ReadOnlyFormatError(ArchiveFormat),
+}
+
+/// The format of an archive
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ArchiveFormat {
+    #[default]
+    Zip,
+    /// a tar archive compressed with zstd
+    TarZst,
+    /// a tar archive compressed with gzip
+    TarGz,
+    /// read only, to import existing mods
+    SevenZ,
+}
+
+impl ArchiveFormat {
+    /// the formats package archives can be written in
+    pub const WRITABLE: [ArchiveFormat; 3] = [Self::Zip, Self::TarZst, Self::TarGz];
+
+    /// the name of the format, also used as the file extension
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Zip => "zip",
+            Self::TarZst => "tar.zst",
+            Self::TarGz => "tar.gz",
+            Self::SevenZ => "7z",
+        }
+    }
+
+    /// detect the format of the archive read by ``reader`` from the first bytes of the stream.
+    /// The position of ``reader`` is restored.
+    pub fn detect<R: Read + Seek>(reader: &mut R) -> io::Result<Option<Self>> {
+        let position = reader.stream_position()?;
+        reader.seek(SeekFrom::Start(0))?;
+        let mut magic = Vec::new();
+        reader.take(6).read_to_end(&mut magic)?;
+        reader.seek(SeekFrom::Start(position))?;
+        Ok(
+            if magic.starts_with(b"PK\x03\x04") || magic.starts_with(b"PK\x05\x06") {
+                Some(Self::Zip)
+            } else if magic.starts_with(&[0x28, 0xB5, 0x2F, 0xFD]) {
+                Some(Self::TarZst)
+            } else if magic.starts_with(&[0x1F, 0x8B]) {
+                Some(Self::TarGz)
+            } else if magic.starts_with(b"7z\xBC\xAF\x27\x1C") {
+                Some(Self::SevenZ)
+            } else {
+                None
+            },
+        )
+    }
+
+    /// create a writer of this format, writing to ``destination``
+    pub fn writer<'a, D: Write + Seek + 'a>(
+        self,
+        destination: &'a mut D,
+        settings: WriterSettings,
+    ) -> Result<Box<dyn ArchiveWriter + 'a>, ArchiveError> {
+        match self {
+            Self::Zip => Ok(Box::new(ZipArchiveWriter::new(destination, settings))),
+            Self::TarZst => {
+                let mut encoder = zstd::stream::write::Encoder::new(destination, ZSTD_LEVEL)
+                    .map_err(ArchiveError::ArchiveIOError)?;
+                // the output is the same for any number of workers, but not without worker
+                encoder
+                    .multithread(settings.threads.max(1) as u32)
+                    .map_err(ArchiveError::ArchiveIOError)?;
+                Ok(Box::new(TarArchiveWriter::new(
+                    TarEncoder::Zstd(encoder),
+                    settings,
+                )))
+            }
+            Self::TarGz => Ok(Box::new(TarArchiveWriter::new(
+                TarEncoder::Gzip(GzEncoder::new(destination, flate2::Compression::default())),
+                settings,
+            ))),
+            Self::SevenZ => Err(ArchiveError::ReadOnlyFormatError(self)),
+        }
+    }
+}
+
+impl fmt::Display for ArchiveFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for ArchiveFormat {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        [Self::Zip, Self::TarZst, Self::TarGz, Self::SevenZ]
+            .iter()
+            .find(|format| format.name() == name)
+            .copied()
+            .ok_or_else(|| {
+                format!(
+                    "unknown archive format {:?}, expected zip, tar.zst, tar.gz or 7z",
+                    name
+                )
+            })
+    }
+}
+
+/// The settings shared by the archive writers
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WriterSettings {
+    /// the modification time of every entry, in seconds since the unix epoch. None use the
+    /// earliest time a zip can store, 1980-01-01.
+    pub timestamp: Option<u64>,
+    /// the number of threads compressing. The archive is the same whatever the number.
+    pub threads: usize,
+}
+
+/// A file or a directory to write in an archive
+#[derive(Debug, Clone, PartialEq)]
+pub enum SourceEntry {
+    Directory {
+        name: String,
+    },
+    File {
+        name: String,
+        /// the file to copy in the archive
+        path: PathBuf,
+        /// false for files that should be stored without compression, if the format compress
+        /// each file separately
+        compress: bool,
+    },
+}
+
+/// Write an archive. Entries are written in the given order, with normalized permissions and
+/// timestamps, so the same entries always give the same archive.
+pub trait ArchiveWriter {
+    /// stream ``entries`` into the archive, returning the SHA-256 hash of each file, by name.
+    /// ``written`` is called with the index of each entry once it is in the archive.
+    fn write_entries(
+        &mut self,
+        entries: &[SourceEntry],
+        written: &mut dyn FnMut(usize),
+    ) -> Result<BTreeMap<String, String>, ArchiveError>;
+
+    /// write a file named ``name`` containing ``content``
+    fn write_data(&mut self, name: &str, content: &[u8]) -> Result<(), ArchiveError>;
+
+    /// complete the archive
+    fn finish(self: Box<Self>) -> Result<(), ArchiveError>;
+}
+
+/// convert ``timestamp``, in seconds since the unix epoch, to a zip date, clamped to the dates a
+/// zip can store
+fn zip_date_time(timestamp: u64) -> DateTime {
+    // days to civil date, from http://howardhinnant.github.io/date_algorithms.html
+    let days = timestamp / 86400;
+    let seconds = timestamp % 86400;
+    let shifted = days + 719_468;
+    let era = shifted / 146_097;
+    let day_of_era = shifted % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+
+    if year < 1980 {
+        return DateTime::default();
+    };
+    if year > 2107 {
+        return DateTime::from_date_and_time(2107, 12, 31, 23, 59, 58)
+            .expect("the last date of zip is valid");
+    };
+    DateTime::from_date_and_time(
+        year as u16,
+        month as u8,
+        day as u8,
+        (seconds / 3600) as u8,
+        (seconds / 60 % 60) as u8,
+        (seconds % 60) as u8,
+    )
+    .expect("the date is in the range of zip")
+}
+
+/// stream the file at ``path`` into a new entry ``name`` of ``zip``, returning its checksum
+fn write_zip_file<W: Write + Seek>(
+    zip: &mut ZipWriter<W>,
+    name: &str,
+    path: &Path,
+    options: FileOptions,
+) -> Result<String, ArchiveError> {
+    let mut file =
+        File::open(path).map_err(|err| ArchiveError::FileIOError(path.to_path_buf(), err))?;
+    let size = file
+        .metadata()
+        .map_err(|err| ArchiveError::FileIOError(path.to_path_buf(), err))?
+        .len();
+    zip.start_file(name, options.large_file(size > LARGE_FILE_SIZE))?;
+    let mut writer = HashingWriter::new(zip);
+    io::copy(&mut file, &mut writer)
+        .map_err(|err| ArchiveError::CopyFileError(path.to_path_buf(), err))?;
+    Ok(writer.finish().1)
+}
+
+/// A file compressed by [`compress_in_parallel`]
+struct CompressedFile {
+    /// the index of the temporary archive containing the file
+    archive: usize,
+    checksum: String,
+}
+
+/// compress ``files`` (entry name, path, options) with ``threads`` threads, each writing into
+/// its own temporary zip archive. Return the temporary archives, and where each file was
+/// compressed.
+fn compress_in_parallel(
+    files: &[(&str, &Path, FileOptions)],
+    threads: usize,
+) -> Result<(Vec<ZipArchive<File>>, Vec<CompressedFile>), ArchiveError> {
+    let next = AtomicUsize::new(0);
+    let results = thread::scope(|scope| {
+        let workers = (0..threads)
+            .map(|_| {
+                scope.spawn(|| {
+                    let temporary =
+                        tempfile::tempfile().map_err(ArchiveError::TemporaryFileError)?;
+                    let mut zip = ZipWriter::new(temporary);
+                    let mut checksums = Vec::new();
+                    loop {
+                        let index = next.fetch_add(1, Ordering::Relaxed);
+                        let (name, path, options) = match files.get(index) {
+                            Some(file) => file,
+                            None => break,
+                        };
+                        checksums.push((index, write_zip_file(&mut zip, name, path, *options)?));
+                    }
+                    Ok((zip.finish()?, checksums))
+                })
+            })
+            .collect::<Vec<_>>();
+        workers
+            .into_iter()
+            .map(|worker| {
+                worker
+                    .join()
+                    .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+            })
+            .collect::<Result<Vec<_>, ArchiveError>>()
+    })?;
+
+    let mut archives = Vec::new();
+    let mut compressed = (0..files.len()).map(|_| None).collect::<Vec<_>>();
+    for (temporary, checksums) in results {
+        for (index, checksum) in checksums {
+            compressed[index] = Some(CompressedFile {
+                archive: archives.len(),
+                checksum,
+            });
+        }
+        archives.push(ZipArchive::new(temporary)?);
+    }
+    Ok((
+        archives,
+        compressed
+            .into_iter()
+            .map(|file| file.expect("every file is compressed by a thread"))
+            .collect(),
+    ))
+}
+
+/// Write zip archives, compressing each file with deflate. With more than one thread, files are
+/// compressed into temporary archives, then copied in order.
+struct ZipArchiveWriter<'a, D: Write + Seek> {
+    zip: ZipWriter<&'a mut D>,
+    file_options: FileOptions,
+    directory_options: FileOptions,
+    threads: usize,
+}
+
+impl<'a, D: Write + Seek> ZipArchiveWriter<'a, D> {
+    fn new(destination: &'a mut D, settings: WriterSettings) -> Self {
+        let options = FileOptions::default()
+            .compression_method(CompressionMethod::Deflated)
+            .last_modified_time(settings.timestamp.map(zip_date_time).unwrap_or_default());
+        Self {
+            zip: ZipWriter::new(destination),
+            file_options: options.unix_permissions(FILE_PERMISSIONS),
+            directory_options: options.unix_permissions(DIRECTORY_PERMISSIONS),
+            threads: settings.threads,
+        }
+    }
+
+    fn options(&self, compress: bool) -> FileOptions {
+        if compress {
+            self.file_options
+        } else {
+            self.file_options
+                .compression_method(CompressionMethod::Stored)
+        }
+    }
+}
+
+impl<'a, D: Write + Seek> ArchiveWriter for ZipArchiveWriter<'a, D> {
+    fn write_entries(
+        &mut self,
+        entries: &[SourceEntry],
+        written: &mut dyn FnMut(usize),
+    ) -> Result<BTreeMap<String, String>, ArchiveError> {
+        // files too large to be copied between archives are compressed while writing the
+        // archive
+        let parallel_files = if self.threads > 1 {
+            entries
+                .iter()
+                .filter_map(|entry| match entry {
+                    SourceEntry::File {
+                        name,
+                        path,
+                        compress,
+                    } => Some((name.as_str(), path.as_path(), self.options(*compress))),
+                    SourceEntry::Directory { .. } => None,
+                })
+                .filter(|(_, path, _)| {
+                    path.metadata()
+                        .map(|metadata| metadata.len() <= LARGE_FILE_SIZE)
+                        .unwrap_or(false)
+                })
+                .collect::<Vec<_>>()
+        } else {
+            Vec::new()
+        };
+        let (mut temporary_archives, compressed_files) =
+            compress_in_parallel(&parallel_files, self.threads.min(parallel_files.len()))?;
+        let compressed = parallel_files
+            .iter()
+            .map(|(name, _, _)| *name)
+            .zip(compressed_files)
+            .collect::<BTreeMap<_, _>>();
+
+        let mut checksums = BTreeMap::new();
+        for (index, entry) in entries.iter().enumerate() {
+            match entry {
+                SourceEntry::Directory { name } => {
+                    self.zip.add_directory(name, self.directory_options)?
+                }
+                SourceEntry::File {
+                    name,
+                    path,
+                    compress,
+                } => {
+                    let checksum = match compressed.get(name.as_str()) {
+                        Some(compressed) => {
+                            self.zip.raw_copy_file(
+                                temporary_archives[compressed.archive].by_name(name)?,
+                            )?;
+                            compressed.checksum.clone()
+                        }
+                        None => {
+                            let options = self.options(*compress);
+                            write_zip_file(&mut self.zip, name, path, options)?
+                        }
+                    };
+                    checksums.insert(name.clone(), checksum);
+                }
+            }
+            written(index);
+        }
+        Ok(checksums)
+    }
+
+    fn write_data(&mut self, name: &str, content: &[u8]) -> Result<(), ArchiveError> {
+        self.zip.start_file(name, self.file_options)?;
+        self.zip
+            .write_all(content)
+            .map_err(ArchiveError::ArchiveIOError)
+    }
+
+    fn finish(mut self: Box<Self>) -> Result<(), ArchiveError> {
+        self.zip.finish()?;
+        Ok(())
+    }
+}
+
+/// The compression of a tar archive
+enum TarEncoder<W: Write> {
+    Zstd(zstd::stream::write::Encoder<'static, W>),
+    Gzip(GzEncoder<W>),
+}
+
+impl<W: Write> TarEncoder<W> {
+    fn finish(self) -> io::Result<W> {
+        match self {
+            Self::Zstd(encoder) => encoder.finish(),
+            Self::Gzip(encoder) => encoder.finish(),
+        }
+    }
+}
+
+impl<W: Write> Write for TarEncoder<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Self::Zstd(encoder) => encoder.write(buf),
+            Self::Gzip(encoder) => encoder.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Self::Zstd(encoder) => encoder.flush(),
+            Self::Gzip(encoder) => encoder.flush(),
+        }
+    }
+}
+
+/// Write compressed tar archives. The whole archive is compressed as one stream, so files can't
+/// be stored without compression.
+struct TarArchiveWriter<W: Write> {
+    builder: tar::Builder<TarEncoder<W>>,
+    timestamp: u64,
+}
+
+impl<W: Write> TarArchiveWriter<W> {
+    fn new(encoder: TarEncoder<W>, settings: WriterSettings) -> Self {
+        Self {
+            builder: tar::Builder::new(encoder),
+            // the same default as zip archives, 1980-01-01
+            timestamp: settings.timestamp.unwrap_or(315_532_800),
+        }
+    }
+
+    fn header(&self, entry_type: tar::EntryType, mode: u32, size: u64) -> tar::Header {
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(entry_type);
+        header.set_mode(mode);
+        header.set_size(size);
+        header.set_mtime(self.timestamp);
+        header.set_uid(0);
+        header.set_gid(0);
+        header
+    }
+}
+
+impl<W: Write> ArchiveWriter for TarArchiveWriter<W> {
+    fn write_entries(
+        &mut self,
+        entries: &[SourceEntry],
+        written: &mut dyn FnMut(usize),
+    ) -> Result<BTreeMap<String, String>, ArchiveError> {
+        let mut checksums = BTreeMap::new();
+        for (index, entry) in entries.iter().enumerate() {
+            match entry {
+                SourceEntry::Directory { name } => {
+                    let mut header =
+                        self.header(tar::EntryType::Directory, DIRECTORY_PERMISSIONS, 0);
+                    self.builder
+                        .append_data(&mut header, name, io::empty())
+                        .map_err(ArchiveError::ArchiveIOError)?;
+                }
+                SourceEntry::File { name, path, .. } => {
+                    let file = File::open(path)
+                        .map_err(|err| ArchiveError::FileIOError(path.to_path_buf(), err))?;
+                    let size = file
+                        .metadata()
+                        .map_err(|err| ArchiveError::FileIOError(path.to_path_buf(), err))?
+                        .len();
+                    let mut header = self.header(tar::EntryType::Regular, FILE_PERMISSIONS, size);
+                    let mut reader = HashingReader::new(file);
+                    self.builder
+                        .append_data(&mut header, name, &mut reader)
+                        .map_err(|err| ArchiveError::CopyFileError(path.to_path_buf(), err))?;
+                    checksums.insert(name.clone(), reader.finish().1);
+                }
+            }
+            written(index);
+        }
+        Ok(checksums)
+    }
+
+    fn write_data(&mut self, name: &str, content: &[u8]) -> Result<(), ArchiveError> {
+        let mut header = self.header(
+            tar::EntryType::Regular,
+            FILE_PERMISSIONS,
+            content.len() as u64,
+        );
+        self.builder
+            .append_data(&mut header, name, content)
+            .map_err(ArchiveError::ArchiveIOError)
+    }
+
+    fn finish(self: Box<Self>) -> Result<(), ArchiveError> {
+        self.builder
+            .into_inner()
+            .and_then(TarEncoder::finish)
+            .map_err(ArchiveError::ArchiveIOError)?;
+        Ok(())
+    }
+}
+
+/// The type of an archive entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryKind {
+    File,
+    Directory,
+    /// a symbolic or hard link
+    Link,
+    /// a device, a fifo or another special file
+    Other,
+}
+
+/// An entry of an archive, as stored in it
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArchiveEntry {
+    /// the name of the entry. It may be unsafe to extract, see
+    /// [`crate::package_reader::safe_relative_path`].
+    pub name: String,
+    pub kind: EntryKind,
+    /// the uncompressed size of the entry, in bytes
+    pub size: u64,
+}
+
+/// convert an entry of a 7z archive
+fn seven_z_entry(file: &sevenz_rust::SevenZArchiveEntry) -> ArchiveEntry {
+    let unix_mode =
+        if file.has_windows_attributes && file.windows_attributes & SEVEN_Z_UNIX_EXTENSION != 0 {
+            Some(file.windows_attributes >> 16)
+        } else {
+            None
+        };
+    let kind = if unix_mode.is_some_and(|mode| mode & UNIX_FILE_TYPE_MASK == UNIX_SYMLINK) {
+        EntryKind::Link
+    } else if file.is_directory {
+        EntryKind::Directory
+    } else {
+        EntryKind::File
+    };
+    ArchiveEntry {
+        name: file.name.clone(),
+        kind,
+        size: file.size,
+    }
+}
+
+/// Read an archive of any [`ArchiveFormat`]
+pub enum ArchiveReader<R: Read + Seek> {
+    Zip(ZipArchive<R>),
+    /// a compressed tar archive. It can only be read sequentially, so it is read again from the
+    /// start for each operation.
+    Tar(ArchiveFormat, R),
+    SevenZ(Box<sevenz_rust::SevenZReader<R>>),
+}
+
+impl<R: Read + Seek> ArchiveReader<R> {
+    /// read the archive from ``reader``, detecting its format
+    pub fn new(mut reader: R) -> Result<Self, ArchiveError> {
+        let format = ArchiveFormat::detect(&mut reader)
+            .map_err(ArchiveError::ArchiveIOError)?
+            .ok_or(ArchiveError::UnknownFormatError)?;
+        Ok(match format {
+            ArchiveFormat::Zip => Self::Zip(ZipArchive::new(reader)?),
+            ArchiveFormat::TarZst | ArchiveFormat::TarGz => Self::Tar(format, reader),
+            ArchiveFormat::SevenZ => {
+                let length = reader
+                    .seek(SeekFrom::End(0))
+                    .map_err(ArchiveError::ArchiveIOError)?;
+                reader
+                    .seek(SeekFrom::Start(0))
+                    .map_err(ArchiveError::ArchiveIOError)?;
+                Self::SevenZ(Box::new(sevenz_rust::SevenZReader::new(
+                    reader,
+                    length,
+                    sevenz_rust::Password::empty(),
+                )?))
+            }
+        })
+    }
+
+    pub fn format(&self) -> ArchiveFormat {
+        match self {
+            Self::Zip(_) => ArchiveFormat::Zip,
+            Self::Tar(format, _) => *format,
+            Self::SevenZ(_) => ArchiveFormat::SevenZ,
+        }
+    }
+
+    /// call ``visitor`` with each entry of the archive and its content, in archive order. The
+    /// content doesn't need to be fully read.
+    pub fn visit<E, F>(&mut self, mut visitor: F) -> Result<(), E>
+    where
+        E: From<ArchiveError>,
+        F: FnMut(&ArchiveEntry, &mut dyn Read) -> Result<(), E>,
+    {
+        match self {
+            Self::Zip(archive) => {
+                for index in 0..archive.len() {
+                    let mut file = archive.by_index(index).map_err(ArchiveError::from)?;
+                    let kind = if file
+                        .unix_mode()
+                        .is_some_and(|mode| mode & UNIX_FILE_TYPE_MASK == UNIX_SYMLINK)
+                    {
+                        EntryKind::Link
+                    } else if file.is_dir() {
+                        EntryKind::Directory
+                    } else {
+                        EntryKind::File
+                    };
+                    let entry = ArchiveEntry {
+                        name: file.name().to_string(),
+                        kind,
+                        size: file.size(),
+                    };
+                    visitor(&entry, &mut file)?;
+                }
+                Ok(())
+            }
+            Self::Tar(format, reader) => {
+                reader
+                    .seek(SeekFrom::Start(0))
+                    .map_err(ArchiveError::ArchiveIOError)?;
+                let decoder: Box<dyn Read> = match format {
+                    ArchiveFormat::TarZst => Box::new(
+                        zstd::stream::read::Decoder::new(reader)
+                            .map_err(ArchiveError::ArchiveIOError)?,
+                    ),
+                    _ => Box::new(flate2::read::GzDecoder::new(reader)),
+                };
+                let mut archive = tar::Archive::new(decoder);
+                for file in archive.entries().map_err(ArchiveError::ArchiveIOError)? {
+                    let mut file = file.map_err(ArchiveError::ArchiveIOError)?;
+                    let entry_type = file.header().entry_type();
+                    let kind = if entry_type.is_file() {
+                        EntryKind::File
+                    } else if entry_type.is_dir() {
+                        EntryKind::Directory
+                    } else if entry_type.is_symlink() || entry_type.is_hard_link() {
+                        EntryKind::Link
+                    } else {
+                        EntryKind::Other
+                    };
+                    let entry = ArchiveEntry {
+                        name: String::from_utf8_lossy(&file.path_bytes()).to_string(),
+                        kind,
+                        size: file.size(),
+                    };
+                    visitor(&entry, &mut file)?;
+                }
+                Ok(())
+            }
+            Self::SevenZ(archive) => {
+                let mut visitor_error = None;
+                archive
+                    .for_each_entries(|file, content| {
+                        let entry = seven_z_entry(file);
+                        if let Err(err) = visitor(&entry, content) {
+                            visitor_error = Some(err);
+                            return Ok(false);
+                        };
+                        // the following entries are decoded from the same stream
+                        io::copy(content, &mut io::sink())?;
+                        Ok(true)
+                    })
+                    .map_err(ArchiveError::from)?;
+                match visitor_error {
+                    Some(err) => Err(err),
+                    None => Ok(()),
+                }
+            }
+        }
+    }
+
+    /// list the entries of the archive, in archive order
+    pub fn entries(&mut self) -> Result<Vec<ArchiveEntry>, ArchiveError> {
+        match self {
+            Self::SevenZ(archive) => {
+                Ok(archive.archive().files.iter().map(seven_z_entry).collect())
+            }
+            _ => {
+                let mut entries = Vec::new();
+                self.visit(|entry, _| {
+                    entries.push(entry.clone());
+                    Ok::<(), ArchiveError>(())
+                })?;
+                Ok(entries)
+            }
+        }
+    }
+
+    /// read the content of the files named ``names``, by name. Missing files aren't part of the
+    /// result.
+    pub fn read_files(
+        &mut self,
+        names: &[&str],
+    ) -> Result<BTreeMap<String, Vec<u8>>, ArchiveError> {
+        let mut files = BTreeMap::new();
+        if let Self::Zip(archive) = self {
+            for name in names {
+                match archive.by_name(name) {
+                    Ok(mut file) => {
+                        let mut content = Vec::new();
+                        file.read_to_end(&mut content)
+                            .map_err(ArchiveError::ArchiveIOError)?;
+                        files.insert(name.to_string(), content);
+                    }
+                    Err(zip::result::ZipError::FileNotFound) => (),
+                    Err(err) => return Err(ArchiveError::from(err)),
+                }
+            }
+            return Ok(files);
+        };
+        self.visit(|entry, content| {
+            if entry.kind == EntryKind::File && names.contains(&entry.name.as_str()) {
+                let mut buffer = Vec::new();
+                content
+                    .read_to_end(&mut buffer)
+                    .map_err(ArchiveError::ArchiveIOError)?;
+                files.insert(entry.name.clone(), buffer);
+            };
+            Ok::<(), ArchiveError>(())
+        })?;
+        Ok(files)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::archive::{
+        zip_date_time, ArchiveFormat, ArchiveReader, EntryKind, SourceEntry, WriterSettings,
+    };
+    use std::fs;
+    use std::io::Cursor;
+    use zip::DateTime;
+
+    #[test]
+    fn test_archive_formats() {
+        let work_dir = tempfile::tempdir().unwrap();
+        let file_path = work_dir.path().join("file.txt");
+        fs::write(&file_path, "content ".repeat(100)).unwrap();
+        let entries = vec![
+            SourceEntry::Directory {
+                name: "folder".to_string(),
+            },
+            SourceEntry::File {
+                name: "folder/file.txt".to_string(),
+                path: file_path,
+                compress: true,
+            },
+        ];
+
+        for format in &ArchiveFormat::WRITABLE {
+            let mut archives = Vec::new();
+            for threads in &[1, 4] {
+                let mut buffer = Cursor::new(Vec::new());
+                let settings = WriterSettings {
+                    timestamp: Some(1_600_000_000),
+                    threads: *threads,
+                };
+                let mut writer = format.writer(&mut buffer, settings).unwrap();
+                let mut written = Vec::new();
+                let checksums = writer
+                    .write_entries(&entries, &mut |index| written.push(index))
+                    .unwrap();
+                assert_eq!(checksums.len(), 1);
+                assert_eq!(written, vec![0, 1]);
+                writer.write_data("data.json", b"{}").unwrap();
+                writer.finish().unwrap();
+                archives.push(buffer.into_inner());
+            }
+            assert_eq!(archives[0], archives[1], "{} isn't reproducible", format);
+
+            let mut reader = ArchiveReader::new(Cursor::new(archives.remove(0))).unwrap();
+            assert_eq!(reader.format(), *format);
+            let entries = reader.entries().unwrap();
+            assert_eq!(entries.len(), 3);
+            assert_eq!(entries[0].kind, EntryKind::Directory);
+            assert_eq!(entries[1].size, 800);
+            let files = reader.read_files(&["data.json", "missing"]).unwrap();
+            assert_eq!(files.len(), 1);
+            assert_eq!(files["data.json"], b"{}");
+            let mut content = String::new();
+            reader
+                .visit(|entry, reader| {
+                    if entry.name == "folder/file.txt" {
+                        reader.read_to_string(&mut content).unwrap();
+                    };
+                    Ok::<(), crate::archive::ArchiveError>(())
+                })
+                .unwrap();
+            assert_eq!(content, "content ".repeat(100));
+        }
+
+        assert!(matches!(
+            ArchiveReader::new(Cursor::new(b"not an archive".to_vec())),
+            Err(crate::archive::ArchiveError::UnknownFormatError)
+        ));
+        assert_eq!(zip_date_time(0).year(), DateTime::default().year());
+        assert_eq!(zip_date_time(u64::MAX).year(), 2107);
+    }
+}
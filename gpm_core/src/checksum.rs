@@ -0,0 +1,85 @@
+//! SHA-256 checksums of archives and files, hex-encoded.
+
+use std::fs::File;
+use std::io;
+use std::io::{Read, Write};
+use std::path::Path;
+
+use sha2::{Digest, Sha256};
+
+/// compute the hex-encoded SHA-256 hash of ``content``
+pub fn sha256_bytes(content: &[u8]) -> String {
+    hex::encode(Sha256::digest(content))
+}
+
+/// compute the hex-encoded SHA-256 hash of the content of ``reader``
+pub fn sha256_reader<R: Read>(reader: &mut R) -> io::Result<String> {
+    let mut hasher = Sha256::new();
+    io::copy(reader, &mut hasher)?;
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// compute the hex-encoded SHA-256 hash of the file at ``path``
+pub fn sha256_file(path: &Path) -> io::Result<String> {
+    sha256_reader(&mut File::open(path)?)
+}
+
+/// A writer computing the SHA-256 hash of what is written to the inner writer
+pub struct HashingWriter<W> {
+    inner: W,
+    hasher: Sha256,
+}
+
+impl<W: Write> HashingWriter<W> {
+    pub fn new(inner: W) -> Self {
+        Self {
+            inner,
+            hasher: Sha256::new(),
+        }
+    }
+
+    /// return the inner writer, and the hex-encoded hash of what was written
+    pub fn finish(self) -> (W, String) {
+        (self.inner, hex::encode(self.hasher.finalize()))
+    }
+}
+
+impl<W: Write> Write for HashingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.hasher.update(&buf[..written]);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// A reader computing the SHA-256 hash of what is read from the inner reader
+pub struct HashingReader<R> {
+    inner: R,
+    hasher: Sha256,
+}
+
+impl<R: Read> HashingReader<R> {
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            hasher: Sha256::new(),
+        }
+    }
+
+    /// return the inner reader, and the hex-encoded hash of what was read
+    pub fn finish(self) -> (R, String) {
+        (self.inner, hex::encode(self.hasher.finalize()))
+    }
+}
+
+impl<R: Read> Read for HashingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.hasher.update(&buf[..read]);
+        Ok(read)
+    }
+}
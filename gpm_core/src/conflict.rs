@@ -0,0 +1,219 @@
+//! Find the files written by more than one package of a profile, and decide which package wins.
+//!
+//! Packages are deployed in load order, and the last package writing a file wins. The default
+//! load order put each package after its dependencies, breaking ties by identifier, so a patch
+//! wins over the mod it patches. The identifiers in [`Profile::load_order`] are then moved to the
+//! end, in the given order.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::deploy::{read_staged_information, staged_files, DeployError, StagedPackage};
+use crate::install_strategy::StrategySet;
+use crate::profile::Profile;
+
+#[derive(thiserror::Error, Debug)]
+pub enum ConflictError {
+    #[error("error while reading the staged packages")]
+    DeployError(#[from] DeployError),
+    #[error("the package {0} isn't staged. Reinstall it to fix this")]
+    NotStagedError(String),
+}
+
+/// A file written by more than one package
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileConflict {
+    /// the path of the file, relative to the game directory
+    pub path: String,
+    /// the identifiers of the packages writing this file, in load order
+    pub packages: Vec<String>,
+}
+
+impl FileConflict {
+    /// the package whose version of the file is deployed
+    pub fn winner(&self) -> &str {
+        self.packages
+            .last()
+            .expect("a conflict involve at least two packages")
+    }
+}
+
+/// the identifiers of ``packages`` in load order, with the identifiers of ``overrides`` moved to
+/// the end. Overrides that aren't part of ``packages`` are ignored.
+pub fn load_order(packages: &[StagedPackage], overrides: &[String]) -> Vec<String> {
+    let mut remaining: BTreeMap<&str, BTreeSet<&str>> = packages
+        .iter()
+        .map(|package| (package.identifier.as_str(), BTreeSet::new()))
+        .collect();
+    for package in packages {
+        for dependency in &package.information.dependencies {
+            if remaining.contains_key(dependency.identifier.as_str()) {
+                remaining
+                    .get_mut(package.identifier.as_str())
+                    .expect("every package is in remaining")
+                    .insert(dependency.identifier.as_str());
+            };
+        }
+    }
+
+    let mut order = Vec::new();
+    while !remaining.is_empty() {
+        // the first package without unloaded dependency, or the first one if there is a cycle
+        let next = remaining
+            .iter()
+            .find(|(_, dependencies)| dependencies.is_empty())
+            .or_else(|| remaining.iter().next())
+            .map(|(identifier, _)| *identifier)
+            .expect("remaining isn't empty");
+        remaining.remove(next);
+        for dependencies in remaining.values_mut() {
+            dependencies.remove(next);
+        }
+        order.push(next.to_string());
+    }
+
+    for identifier in overrides {
+        if let Some(position) = order.iter().position(|loaded| loaded == identifier) {
+            let identifier = order.remove(position);
+            order.push(identifier);
+        };
+    }
+    order
+}
+
+/// sort ``packages`` in load order, as returned by [`load_order`]
+pub fn sort_by_load_order(packages: &mut [StagedPackage], overrides: &[String]) {
+    let order = load_order(packages, overrides);
+    packages.sort_by_key(|package| {
+        order
+            .iter()
+            .position(|identifier| identifier == &package.identifier)
+    });
+}
+
+/// the packages of the [`crate::lockfile::LockFile`] of ``profile``, as currently staged, in
+/// load order
+pub fn profile_staged_packages(profile: &Profile) -> Result<Vec<StagedPackage>, ConflictError> {
+    let mut packages = Vec::new();
+    for (identifier, source) in profile.lock_file.iter_dependency_source() {
+        let staged_dir = profile.staging_dir.join(identifier);
+        if !staged_dir.is_dir() {
+            return Err(ConflictError::NotStagedError(identifier.clone()));
+        };
+        packages.push(StagedPackage {
+            identifier: identifier.clone(),
+            source: source.clone(),
+            information: read_staged_information(&staged_dir)?,
+            staged_dir,
+            restaged: false,
+        });
+    }
+    sort_by_load_order(&mut packages, &profile.load_order);
+    Ok(packages)
+}
+
+/// the files written by more than one of ``packages``, which must be in load order
+pub fn find_conflicts(
+    packages: &[StagedPackage],
+    strategies: &StrategySet,
+) -> Result<Vec<FileConflict>, ConflictError> {
+    let mut owners: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for package in packages {
+        for (path, _) in staged_files(package, strategies)? {
+            owners
+                .entry(path)
+                .or_default()
+                .push(package.identifier.clone());
+        }
+    }
+    Ok(owners
+        .into_iter()
+        .filter(|(_, packages)| packages.len() > 1)
+        .map(|(path, packages)| FileConflict { path, packages })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::conflict::{find_conflicts, load_order, sort_by_load_order};
+    use crate::constants::JSON_CONFIG_PATH;
+    use crate::deploy::StagedPackage;
+    use crate::install_strategy::StrategySet;
+    use crate::lockfile::LockSource;
+    use crate::package::{PackageDependency, PackageInformation};
+    use crate::store_project::get_project_config_json;
+    use semver::{Version, VersionReq};
+    use std::fs;
+    use std::path::Path;
+
+    fn stage(
+        work_dir: &Path,
+        identifier: &str,
+        dependencies: &[&str],
+        files: &[&str],
+    ) -> StagedPackage {
+        let mut information = PackageInformation::new(
+            "modder",
+            identifier,
+            Version::new(1, 0, 0),
+            identifier,
+            "a test mod",
+            "MIT",
+        );
+        information.dependencies = dependencies
+            .iter()
+            .map(|dependency| PackageDependency::new(dependency, VersionReq::STAR))
+            .collect();
+        let staged_dir = work_dir.join(identifier);
+        fs::create_dir_all(&staged_dir).unwrap();
+        fs::write(
+            staged_dir.join(JSON_CONFIG_PATH),
+            get_project_config_json(&information).unwrap(),
+        )
+        .unwrap();
+        for file in files {
+            fs::write(staged_dir.join(file), identifier).unwrap();
+        }
+        StagedPackage {
+            identifier: identifier.to_string(),
+            source: LockSource::Path {
+                path: staged_dir.clone(),
+                checksum: None,
+            },
+            staged_dir,
+            restaged: false,
+            information,
+        }
+    }
+
+    #[test]
+    fn test_conflicts() {
+        let temporary_dir = tempfile::tempdir().unwrap();
+        let work_dir = temporary_dir.path();
+        let mut packages = vec![
+            stage(
+                work_dir,
+                "a_patch",
+                &["z_base"],
+                &["shared.txt", "patch.txt"],
+            ),
+            stage(work_dir, "m_other", &[], &["shared.txt"]),
+            stage(work_dir, "z_base", &[], &["shared.txt", "base.txt"]),
+        ];
+
+        assert_eq!(
+            load_order(&packages, &[]),
+            vec!["m_other", "z_base", "a_patch"]
+        );
+        assert_eq!(
+            load_order(&packages, &["m_other".to_string(), "unknown".to_string()]),
+            vec!["z_base", "a_patch", "m_other"]
+        );
+
+        sort_by_load_order(&mut packages, &[]);
+        let conflicts = find_conflicts(&packages, &StrategySet::default()).unwrap();
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].path, "shared.txt");
+        assert_eq!(conflicts[0].packages, vec!["m_other", "z_base", "a_patch"]);
+        assert_eq!(conflicts[0].winner(), "a_patch");
+    }
+}
@@ -0,0 +1,661 @@
+//! Copy the staged files of the packages of a profile into its game directory.
+//!
+//! Every modification of the game directory is recorded in a [`Journal`], so a deployment that
+//! fail halfway can be rolled back, leaving the game directory as it was before.
+
+use std::collections::BTreeMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use crate::checksum::sha256_file;
+use crate::constants::JSON_CONFIG_PATH;
+use crate::install_strategy::{InstallStrategyError, StrategySet};
+use crate::lockfile::LockSource;
+use crate::package::PackageInformation;
+use crate::profile::Profile;
+use crate::store_project::load_package_from_json;
+
+use serde::{Deserialize, Serialize};
+use walkdir::WalkDir;
+
+/// the file, in the profile folder, that record what is currently deployed
+pub const DEPLOYMENT_MANIFEST_PATH: &str = "deployment.toml";
+/// the folder, in the profile folder, where the game files replaced by a mod are kept
+pub const BACKUP_DIRECTORY: &str = "backup";
+/// the folder, in the profile folder, used to store the replaced files during a deployment
+pub const JOURNAL_DIRECTORY: &str = ".journal";
+/// the file, in the journal directory, listing the recorded modifications, one json object per line
+const JOURNAL_ACTIONS_PATH: &str = "actions.json";
+/// the file, in the journal directory, created when the recorded modifications are being kept
+const JOURNAL_COMMITTED_PATH: &str = "committed";
+
+#[derive(thiserror::Error, Debug)]
+pub enum DeployError {
+    #[error("io error with the file {0}")]
+    FileIOError(PathBuf, #[source] io::Error),
+    #[error("error while parsing the deployment manifest {0}")]
+    TomlDecodeError(PathBuf, #[source] toml::de::Error),
+    #[error("can't encode the deployment manifest. Probably internal error")]
+    TomlEncodeError(#[source] toml::ser::Error),
+    #[error("error while walking the staging directory")]
+    WalkDirError(#[from] walkdir::Error),
+    #[error("can't encode the journal. Probably internal error")]
+    JournalEncodeError(#[source] serde_json::Error),
+    #[error("error while parsing the journal {0}")]
+    JournalDecodeError(PathBuf, #[source] serde_json::Error),
+    #[error("the journal {0} of an interrupted operation doesn't list its modifications. Restore the game files it contains by hand, then delete it")]
+    UnknownJournalError(PathBuf),
+    #[error("can't undo the interrupted operation recorded in the journal {0}")]
+    RecoverJournalError(PathBuf, #[source] Box<DeployError>),
+    #[error("can't parse the configuration of the staged package in {0}")]
+    DecodeJsonError(PathBuf, #[source] serde_json::Error),
+    #[error("can't install the package {0}")]
+    InstallStrategyError(String, #[source] InstallStrategyError),
+}
+
+/// The packages and files currently deployed in the game directory of a profile
+#[derive(Serialize, Deserialize, Default, Debug, Clone, PartialEq)]
+pub struct DeploymentManifest {
+    /// the source each deployed package was staged from, by package identifier
+    #[serde(default)]
+    pub packages: BTreeMap<String, LockSource>,
+    /// the package owning each deployed file, by path relative to the game directory
+    #[serde(default)]
+    pub files: BTreeMap<String, String>,
+    /// the hex-encoded SHA-256 hash of each deployed file, by path relative to the game
+    /// directory. Files deployed before hashes were recorded don't have one.
+    #[serde(default)]
+    pub checksums: BTreeMap<String, String>,
+}
+
+impl DeploymentManifest {
+    /// load the manifest of the given profile, or return an empty one if nothing was deployed yet
+    pub fn load(profile: &Profile) -> Result<Self, DeployError> {
+        let path = profile.path.join(DEPLOYMENT_MANIFEST_PATH);
+        match fs::read(&path) {
+            Ok(content) => {
+                toml::from_slice(&content).map_err(|err| DeployError::TomlDecodeError(path, err))
+            }
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(DeployError::FileIOError(path, err)),
+        }
+    }
+
+    /// write this manifest in the profile folder, through the journal
+    fn save(&self, profile: &Profile, journal: &mut Journal) -> Result<(), DeployError> {
+        let content = toml::to_vec(self).map_err(DeployError::TomlEncodeError)?;
+        journal.write_file(&profile.path.join(DEPLOYMENT_MANIFEST_PATH), &content)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum JournalAction {
+    /// a file was created at this path
+    Created(PathBuf),
+    /// a directory was created at this path
+    CreatedDirectory(PathBuf),
+    /// the empty directory at this path was removed
+    RemovedDirectory(PathBuf),
+    /// the file at ``original`` was moved to ``saved``
+    Moved { original: PathBuf, saved: PathBuf },
+    /// the directory at ``original`` was moved to ``saved``, to be deleted on commit. It is kept
+    /// next to the original, as the journal directory may be on another filesystem.
+    ReplacedDirectory { original: PathBuf, saved: PathBuf },
+}
+
+/// Record the modifications made to the filesystem, so they can be rolled back.
+///
+/// The actions are also written in the journal directory as they happen, so the modifications of
+/// an operation interrupted by a crash are rolled back by the next [`Journal::new`].
+pub struct Journal {
+    directory: PathBuf,
+    actions: Vec<JournalAction>,
+    log: File,
+}
+
+/// make the creation of the entries of ``directory`` durable
+fn sync_directory(directory: &Path) -> io::Result<()> {
+    // directories can't be opened as files on windows, where it isn't needed
+    #[cfg(unix)]
+    File::open(directory)?.sync_all()?;
+    Ok(())
+}
+
+/// move a file, even between two filesystems
+fn move_file(from: &Path, to: &Path) -> io::Result<()> {
+    if fs::rename(from, to).is_err() {
+        fs::copy(from, to)?;
+        fs::remove_file(from)?;
+    };
+    Ok(())
+}
+
+impl Journal {
+    /// start a new journal, storing the replaced files in ``directory``.
+    ///
+    /// A journal left in this directory by an interrupted operation is finished first: its
+    /// modifications are rolled back, or kept if it was committing. If that fails, the previous
+    /// journal is left untouched and no new one is started.
+    pub fn new(directory: &Path) -> Result<Self, DeployError> {
+        if directory.exists() {
+            Self::recover(directory).map_err(|err| {
+                DeployError::RecoverJournalError(directory.to_path_buf(), Box::new(err))
+            })?;
+        };
+        fs::create_dir_all(directory)
+            .map_err(|err| DeployError::FileIOError(directory.to_path_buf(), err))?;
+        let log_path = directory.join(JOURNAL_ACTIONS_PATH);
+        let log = File::create(&log_path).map_err(|err| DeployError::FileIOError(log_path, err))?;
+        sync_directory(directory)
+            .map_err(|err| DeployError::FileIOError(directory.to_path_buf(), err))?;
+        Ok(Self {
+            directory: directory.to_path_buf(),
+            actions: Vec::new(),
+            log,
+        })
+    }
+
+    /// finish the journal left in ``directory`` by an interrupted operation
+    fn recover(directory: &Path) -> Result<(), DeployError> {
+        let log_path = directory.join(JOURNAL_ACTIONS_PATH);
+        let content = match fs::read_to_string(&log_path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                // interrupted before its creation was finished, it can only be empty
+                let mut entries = fs::read_dir(directory)
+                    .map_err(|err| DeployError::FileIOError(directory.to_path_buf(), err))?;
+                if entries.next().is_some() {
+                    return Err(DeployError::UnknownJournalError(directory.to_path_buf()));
+                };
+                return fs::remove_dir(directory)
+                    .map_err(|err| DeployError::FileIOError(directory.to_path_buf(), err));
+            }
+            Err(err) => return Err(DeployError::FileIOError(log_path, err)),
+        };
+        let mut actions = Vec::new();
+        let mut lines = content.split_terminator('\n').peekable();
+        while let Some(line) = lines.next() {
+            match serde_json::from_str(line) {
+                Ok(action) => actions.push(action),
+                // the last action was being recorded, so it wasn't done yet
+                Err(_) if lines.peek().is_none() && !content.ends_with('\n') => (),
+                Err(err) => return Err(DeployError::JournalDecodeError(log_path, err)),
+            };
+        }
+        let log = OpenOptions::new()
+            .append(true)
+            .open(&log_path)
+            .map_err(|err| DeployError::FileIOError(log_path, err))?;
+        let journal = Self {
+            directory: directory.to_path_buf(),
+            actions,
+            log,
+        };
+        if directory.join(JOURNAL_COMMITTED_PATH).exists() {
+            journal.commit()
+        } else {
+            journal.rollback()
+        }
+    }
+
+    /// add ``action`` to the journal, and write it durably in the journal directory. The actions
+    /// that can lose data are recorded before being done, the others once done.
+    fn record(&mut self, action: JournalAction) -> Result<(), DeployError> {
+        let mut line = serde_json::to_vec(&action).map_err(DeployError::JournalEncodeError)?;
+        line.push(b'\n');
+        self.log
+            .write_all(&line)
+            .and_then(|()| self.log.sync_data())
+            .map_err(|err| {
+                DeployError::FileIOError(self.directory.join(JOURNAL_ACTIONS_PATH), err)
+            })?;
+        self.actions.push(action);
+        Ok(())
+    }
+
+    /// create the directory ``path`` and its missing parents
+    pub fn create_dir_all(&mut self, path: &Path) -> Result<(), DeployError> {
+        let mut missing = Vec::new();
+        let mut current = Some(path);
+        while let Some(directory) = current {
+            if directory.as_os_str().is_empty() || directory.is_dir() {
+                break;
+            };
+            missing.push(directory.to_path_buf());
+            current = directory.parent();
+        }
+        for directory in missing.into_iter().rev() {
+            fs::create_dir(&directory)
+                .map_err(|err| DeployError::FileIOError(directory.clone(), err))?;
+            self.record(JournalAction::CreatedDirectory(directory))?;
+        }
+        Ok(())
+    }
+
+    /// move the file at ``path`` in the journal directory. It will be deleted on commit, and put
+    /// back on rollback.
+    pub fn remove_file(&mut self, path: &Path) -> Result<(), DeployError> {
+        let saved = self.directory.join(self.actions.len().to_string());
+        self.record(JournalAction::Moved {
+            original: path.to_path_buf(),
+            saved: saved.clone(),
+        })?;
+        move_file(path, &saved).map_err(|err| DeployError::FileIOError(path.to_path_buf(), err))
+    }
+
+    /// copy the file ``from`` to ``to``, replacing it if it already exist
+    pub fn copy_file(&mut self, from: &Path, to: &Path) -> Result<(), DeployError> {
+        self.prepare_destination(to)?;
+        self.record(JournalAction::Created(to.to_path_buf()))?;
+        fs::copy(from, to).map_err(|err| DeployError::FileIOError(to.to_path_buf(), err))?;
+        Ok(())
+    }
+
+    /// write ``content`` to the file ``to``, replacing it if it already exist
+    pub fn write_file(&mut self, to: &Path, content: &[u8]) -> Result<(), DeployError> {
+        self.prepare_destination(to)?;
+        self.record(JournalAction::Created(to.to_path_buf()))?;
+        fs::write(to, content).map_err(|err| DeployError::FileIOError(to.to_path_buf(), err))
+    }
+
+    /// move the directory ``from`` to ``to``, replacing it if it already exist. Both should be
+    /// on the same filesystem.
+    pub fn move_directory(&mut self, from: &Path, to: &Path) -> Result<(), DeployError> {
+        if to.exists() {
+            self.remove_directory(to)?;
+        };
+        if let Some(parent) = to.parent() {
+            self.create_dir_all(parent)?;
+        };
+        self.record(JournalAction::Moved {
+            original: from.to_path_buf(),
+            saved: to.to_path_buf(),
+        })?;
+        fs::rename(from, to).map_err(|err| DeployError::FileIOError(from.to_path_buf(), err))
+    }
+
+    /// move the directory at ``path`` away. It will be deleted on commit, and put back on
+    /// rollback.
+    pub fn remove_directory(&mut self, path: &Path) -> Result<(), DeployError> {
+        let mut saved = path.as_os_str().to_owned();
+        saved.push(format!(".old{}", self.actions.len()));
+        let saved = PathBuf::from(saved);
+        self.record(JournalAction::ReplacedDirectory {
+            original: path.to_path_buf(),
+            saved: saved.clone(),
+        })?;
+        fs::rename(path, &saved).map_err(|err| DeployError::FileIOError(path.to_path_buf(), err))
+    }
+
+    fn prepare_destination(&mut self, to: &Path) -> Result<(), DeployError> {
+        if let Some(parent) = to.parent() {
+            self.create_dir_all(parent)?;
+        };
+        if to.exists() {
+            self.remove_file(to)?;
+        };
+        Ok(())
+    }
+
+    /// remove the directories that are empty, from ``path`` up to ``root`` (excluded)
+    pub fn remove_empty_parents(&mut self, path: &Path, root: &Path) -> Result<(), DeployError> {
+        let mut current = path.parent();
+        while let Some(directory) = current {
+            if directory == root || !directory.starts_with(root) {
+                break;
+            };
+            // fail if the directory isn't empty
+            if fs::remove_dir(directory).is_err() {
+                break;
+            };
+            self.record(JournalAction::RemovedDirectory(directory.to_path_buf()))?;
+            current = directory.parent();
+        }
+        Ok(())
+    }
+
+    /// keep the modifications, deleting the replaced files
+    pub fn commit(self) -> Result<(), DeployError> {
+        let Self {
+            directory,
+            actions,
+            log,
+        } = self;
+        drop(log);
+        // from here, an interrupted commit is finished instead of rolled back
+        let committed_path = directory.join(JOURNAL_COMMITTED_PATH);
+        File::create(&committed_path)
+            .and_then(|marker| marker.sync_all())
+            .map_err(|err| DeployError::FileIOError(committed_path, err))?;
+        sync_directory(&directory)
+            .map_err(|err| DeployError::FileIOError(directory.clone(), err))?;
+        for action in &actions {
+            if let JournalAction::ReplacedDirectory { saved, .. } = action {
+                match fs::remove_dir_all(saved) {
+                    Err(err) if err.kind() != io::ErrorKind::NotFound => {
+                        return Err(DeployError::FileIOError(saved.clone(), err))
+                    }
+                    _ => (),
+                };
+            };
+        }
+        fs::remove_dir_all(&directory).map_err(|err| DeployError::FileIOError(directory, err))
+    }
+
+    /// undo every recorded modification, in reverse order. It continues on error, and return the
+    /// first one. The actions that weren't done, or were already undone, are skipped, so an
+    /// interrupted rollback can be done again.
+    ///
+    /// On error, the journal directory is kept, as it may still contain replaced files.
+    pub fn rollback(self) -> Result<(), DeployError> {
+        let Self {
+            directory,
+            mut actions,
+            log,
+        } = self;
+        drop(log);
+        let mut first_error = None;
+        while let Some(action) = actions.pop() {
+            let result = match &action {
+                JournalAction::Created(path) => match fs::remove_file(path) {
+                    Err(err) if err.kind() != io::ErrorKind::NotFound => Err((path, err)),
+                    _ => Ok(()),
+                },
+                JournalAction::CreatedDirectory(path) => match fs::remove_dir(path) {
+                    Err(err) if err.kind() != io::ErrorKind::NotFound => Err((path, err)),
+                    _ => Ok(()),
+                },
+                JournalAction::RemovedDirectory(path) => match fs::create_dir(path) {
+                    Err(err) if err.kind() != io::ErrorKind::AlreadyExists => Err((path, err)),
+                    _ => Ok(()),
+                },
+                // the original is still there if the move didn't happen, or was undone
+                JournalAction::Moved { original, .. } if original.exists() => Ok(()),
+                JournalAction::Moved { original, saved } => if saved.is_dir() {
+                    fs::rename(saved, original)
+                } else {
+                    move_file(saved, original)
+                }
+                .map_err(|err| (original, err)),
+                JournalAction::ReplacedDirectory { saved, .. } if !saved.exists() => Ok(()),
+                JournalAction::ReplacedDirectory { original, saved } => {
+                    fs::rename(saved, original).map_err(|err| (original, err))
+                }
+            };
+            if let Err((path, err)) = result {
+                first_error.get_or_insert(DeployError::FileIOError(path.clone(), err));
+            };
+        }
+        match first_error {
+            Some(err) => Err(err),
+            None => {
+                let _ = fs::remove_dir_all(&directory);
+                Ok(())
+            }
+        }
+    }
+}
+
+/// read the information of the package staged in ``staged_dir``
+pub fn read_staged_information(staged_dir: &Path) -> Result<PackageInformation, DeployError> {
+    let config_path = staged_dir.join(JSON_CONFIG_PATH);
+    let config_content =
+        fs::read(&config_path).map_err(|err| DeployError::FileIOError(config_path.clone(), err))?;
+    Ok(load_package_from_json(&config_content)
+        .map_err(|err| DeployError::DecodeJsonError(config_path, err))?
+        .information)
+}
+
+/// list the files of a staged package, as paths relative to the game directory using `/`
+/// separators, following its install strategies
+pub fn staged_files(
+    package: &StagedPackage,
+    strategies: &StrategySet,
+) -> Result<Vec<(String, PathBuf)>, DeployError> {
+    let mut files = Vec::new();
+    for entry in WalkDir::new(&package.staged_dir).sort_by(|a, b| a.file_name().cmp(b.file_name()))
+    {
+        let entry = entry?;
+        if !entry.file_type().is_file() {
+            continue;
+        };
+        let relative_path = entry
+            .path()
+            .strip_prefix(&package.staged_dir)
+            .expect("walkdir entries are in the walked directory")
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        if relative_path == JSON_CONFIG_PATH {
+            continue;
+        };
+        let target = strategies
+            .target(
+                &package.identifier,
+                &package.information.install_strategies,
+                &relative_path,
+            )
+            .map_err(|err| DeployError::InstallStrategyError(package.identifier.clone(), err))?;
+        if let Some(target) = target {
+            files.push((target, entry.path().to_path_buf()));
+        };
+    }
+    Ok(files)
+}
+
+/// A package extracted in the staging directory of a profile, along with its
+/// [`JSON_CONFIG_PATH`] file
+pub struct StagedPackage {
+    pub identifier: String,
+    /// the source the package was extracted from
+    pub source: LockSource,
+    pub staged_dir: PathBuf,
+    /// true if the package was extracted again since the previous deployment, so its files need
+    /// to be copied even if they are already deployed.
+    pub restaged: bool,
+    /// the information of the package, as read by [`read_staged_information`]
+    pub information: PackageInformation,
+}
+
+/// Make the game directory of ``profile`` contain the files of ``packages``, in addition to the
+/// game files. The files are placed following the install strategies of each package, looked up
+/// in ``strategies``. When two packages contain the same file, the last one wins.
+///
+/// The files deployed previously but not part of any package anymore are removed, and the game
+/// files they replaced are restored. Every modification is recorded in ``journal``.
+pub fn deploy(
+    profile: &Profile,
+    packages: &[StagedPackage],
+    strategies: &StrategySet,
+    journal: &mut Journal,
+) -> Result<DeploymentManifest, DeployError> {
+    let previous = DeploymentManifest::load(profile)?;
+    let backup_dir = profile.path.join(BACKUP_DIRECTORY);
+
+    let mut manifest = DeploymentManifest::default();
+    let mut sources = BTreeMap::new();
+    for package in packages {
+        manifest
+            .packages
+            .insert(package.identifier.clone(), package.source.clone());
+        for (relative_path, staged_path) in staged_files(package, strategies)? {
+            manifest
+                .files
+                .insert(relative_path.clone(), package.identifier.clone());
+            sources.insert(relative_path, (package, staged_path));
+        }
+    }
+
+    // remove the files that aren't deployed anymore, and put back the game files
+    for relative_path in previous.files.keys() {
+        if manifest.files.contains_key(relative_path) {
+            continue;
+        };
+        let game_path = profile.game_dir.join(relative_path);
+        if game_path.exists() {
+            journal.remove_file(&game_path)?;
+        };
+        let backup_path = backup_dir.join(relative_path);
+        if backup_path.is_file() {
+            journal.copy_file(&backup_path, &game_path)?;
+            journal.remove_file(&backup_path)?;
+        } else {
+            journal.remove_empty_parents(&game_path, &profile.game_dir)?;
+        };
+    }
+
+    // copy the files of the packages, keeping a backup of the game files they replace
+    for (relative_path, (package, staged_path)) in &sources {
+        let game_path = profile.game_dir.join(relative_path);
+        let previous_owner = previous.files.get(relative_path);
+        if !package.restaged
+            && previous_owner == Some(&package.identifier)
+            && previous.packages.get(&package.identifier) == Some(&package.source)
+            && game_path.is_file()
+        {
+            if let Some(checksum) = previous.checksums.get(relative_path) {
+                manifest
+                    .checksums
+                    .insert(relative_path.clone(), checksum.clone());
+                continue;
+            };
+        };
+        if game_path.is_file() && previous_owner.is_none() {
+            journal.copy_file(&game_path, &backup_dir.join(relative_path))?;
+        };
+        journal.copy_file(staged_path, &game_path)?;
+        let checksum = sha256_file(&game_path)
+            .map_err(|err| DeployError::FileIOError(game_path.clone(), err))?;
+        manifest.checksums.insert(relative_path.clone(), checksum);
+    }
+
+    manifest.save(profile, journal)?;
+    Ok(manifest)
+}
+
+/// A deployed file that doesn't match the [`DeploymentManifest`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum DeploymentIssue {
+    /// the file at this path, relative to the game directory, was removed
+    Missing(String),
+    /// the content of the file at this path, relative to the game directory, changed
+    Modified(String),
+}
+
+impl DeploymentIssue {
+    pub fn path(&self) -> &str {
+        match self {
+            Self::Missing(path) | Self::Modified(path) => path,
+        }
+    }
+}
+
+/// check the files deployed in the game directory of ``profile`` against the hashes of its
+/// [`DeploymentManifest`], and return the ones that were removed or modified since. Files without
+/// a recorded hash are only checked for existence.
+pub fn verify_deployment(profile: &Profile) -> Result<Vec<DeploymentIssue>, DeployError> {
+    let manifest = DeploymentManifest::load(profile)?;
+    let mut issues = Vec::new();
+    for relative_path in manifest.files.keys() {
+        let game_path = profile.game_dir.join(relative_path);
+        if !game_path.is_file() {
+            issues.push(DeploymentIssue::Missing(relative_path.clone()));
+            continue;
+        };
+        if let Some(checksum) = manifest.checksums.get(relative_path) {
+            let actual = sha256_file(&game_path)
+                .map_err(|err| DeployError::FileIOError(game_path.clone(), err))?;
+            if &actual != checksum {
+                issues.push(DeploymentIssue::Modified(relative_path.clone()));
+            };
+        };
+    }
+    Ok(issues)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::deploy::{DeployError, Journal, JOURNAL_ACTIONS_PATH, JOURNAL_COMMITTED_PATH};
+    use std::fs::{self, OpenOptions};
+    use std::io::Write;
+
+    #[test]
+    fn test_recover_interrupted_journal() {
+        let work_dir = tempfile::tempdir().unwrap();
+        let game_dir = work_dir.path().join("game");
+        let journal_dir = work_dir.path().join("journal");
+        fs::create_dir_all(game_dir.join("textures")).unwrap();
+        fs::write(game_dir.join("game.txt"), "vanilla").unwrap();
+        let mod_file = work_dir.path().join("mod.txt");
+        fs::write(&mod_file, "mod").unwrap();
+
+        // the process stop during the deployment, without a commit or a rollback
+        let mut journal = Journal::new(&journal_dir).unwrap();
+        journal
+            .copy_file(&mod_file, &game_dir.join("game.txt"))
+            .unwrap();
+        journal
+            .copy_file(&mod_file, &game_dir.join("mods").join("mod.txt"))
+            .unwrap();
+        journal
+            .remove_directory(&game_dir.join("textures"))
+            .unwrap();
+        drop(journal);
+        // while an action was being recorded
+        OpenOptions::new()
+            .append(true)
+            .open(journal_dir.join(JOURNAL_ACTIONS_PATH))
+            .unwrap()
+            .write_all(b"{\"created\":")
+            .unwrap();
+        assert_eq!(
+            fs::read_to_string(game_dir.join("game.txt")).unwrap(),
+            "mod"
+        );
+
+        let journal = Journal::new(&journal_dir).unwrap();
+        assert_eq!(
+            fs::read_to_string(game_dir.join("game.txt")).unwrap(),
+            "vanilla"
+        );
+        assert!(!game_dir.join("mods").exists());
+        assert!(game_dir.join("textures").is_dir());
+        journal.commit().unwrap();
+        assert!(!journal_dir.exists());
+
+        // the process stop while committing
+        let mut journal = Journal::new(&journal_dir).unwrap();
+        journal
+            .copy_file(&mod_file, &game_dir.join("game.txt"))
+            .unwrap();
+        journal
+            .remove_directory(&game_dir.join("textures"))
+            .unwrap();
+        drop(journal);
+        fs::write(journal_dir.join(JOURNAL_COMMITTED_PATH), "").unwrap();
+        Journal::new(&journal_dir).unwrap().rollback().unwrap();
+        assert_eq!(
+            fs::read_to_string(game_dir.join("game.txt")).unwrap(),
+            "mod"
+        );
+        let mut game_files = fs::read_dir(&game_dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect::<Vec<_>>();
+        game_files.sort();
+        assert_eq!(game_files, vec!["game.txt"]);
+
+        // a journal without its actions isn't touched
+        fs::create_dir_all(&journal_dir).unwrap();
+        fs::write(journal_dir.join("0"), "vanilla").unwrap();
+        assert!(matches!(
+            Journal::new(&journal_dir),
+            Err(DeployError::RecoverJournalError(_, err))
+                if matches!(*err, DeployError::UnknownJournalError(_))
+        ));
+        assert!(journal_dir.join("0").is_file());
+    }
+}
@@ -0,0 +1,144 @@
+use crate::display::list::format_str_id_list;
+use crate::info::{PackageInfo, PackageKind};
+use crate::package::DependencySource;
+
+use console::style;
+use std::fmt::Write;
+
+/// Format ``size``, in bytes, with the largest binary unit keeping it above 1, like `1.5 MiB`
+pub fn format_size(size: u64) -> String {
+    const UNITS: &[&str] = &["KiB", "MiB", "GiB", "TiB"];
+    if size < 1024 {
+        return format!("{} B", size);
+    };
+    let mut value = size as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
+
+/// Format every field of ``info`` on its own line, followed by the dependencies, the files and
+/// what is missing to publish the package.
+pub fn format_package_info(info: &PackageInfo) -> String {
+    let information = &info.information;
+    let missing = |value: Option<&str>| match value {
+        Some(value) => value.to_string(),
+        None => format!("{}", style("(missing)").red()),
+    };
+    let kind = match info.kind {
+        PackageKind::Project => "mod project".to_string(),
+        PackageKind::Archive(format) => format!("{} package", format.name()),
+    };
+
+    // writing to a String can't fail
+    let mut output = String::new();
+    let _ = writeln!(
+        output,
+        "{} {} ({})",
+        style(missing(information.identifier.as_deref())).bold(),
+        missing(
+            information
+                .version
+                .as_ref()
+                .map(ToString::to_string)
+                .as_deref()
+        ),
+        kind
+    );
+    let fields = [
+        ("display name", information.display_name.as_deref()),
+        ("creator", information.creator.as_deref()),
+        ("description", information.description.as_deref()),
+        ("license", information.license.as_deref()),
+    ];
+    for (name, value) in fields {
+        let _ = writeln!(output, "{}: {}", name, missing(value));
+    }
+    if let Some(website_url) = &information.website_url {
+        let _ = writeln!(output, "website url: {}", website_url);
+    };
+    let lists = [
+        ("tags", &information.tags),
+        ("install strategies", &information.install_strategies),
+        ("uncompressed", &information.uncompressed),
+        ("include", &information.include),
+        ("exclude", &information.exclude),
+    ];
+    for (name, values) in lists {
+        if !values.is_empty() {
+            let _ = writeln!(output, "{}: {}", name, format_str_id_list(values));
+        };
+    }
+    for data in &information.extra_data {
+        let _ = writeln!(output, "extra data: {} = {:?}", data.key, data.value);
+    }
+
+    if information.dependencies.is_empty() {
+        let _ = writeln!(output, "no dependencies");
+    } else {
+        let _ = writeln!(output, "dependencies:");
+        for dependency in &information.dependencies {
+            let _ = write!(
+                output,
+                "  {} {}",
+                format_str_id_list(&[&dependency.identifier]),
+                dependency.version
+            );
+            if dependency.optional {
+                let _ = write!(output, " (optional)");
+            };
+            match &dependency.source {
+                DependencySource::Repository => (),
+                DependencySource::Path(path) => {
+                    let _ = write!(output, " from {:?}", path);
+                }
+                DependencySource::Url(url) => {
+                    let _ = write!(output, " from {}", url);
+                }
+            };
+            let _ = writeln!(output);
+        }
+    };
+
+    let _ = writeln!(
+        output,
+        "{} files, {}:",
+        info.files.len(),
+        format_size(info.total_size())
+    );
+    for file in &info.files {
+        let _ = writeln!(
+            output,
+            "  {:>10}  {}",
+            format_size(file.size),
+            file.path.to_string_lossy()
+        );
+    }
+
+    if info.missing_publish_field.is_empty() {
+        let _ = write!(output, "ready to be published");
+    } else {
+        let _ = write!(
+            output,
+            "can't be published, the fields {} are missing",
+            format_str_id_list(&info.missing_publish_field)
+        );
+    };
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::display::info::format_size;
+
+    #[test]
+    fn test_format_size() {
+        assert_eq!(format_size(0), "0 B");
+        assert_eq!(format_size(1023), "1023 B");
+        assert_eq!(format_size(1536), "1.5 KiB");
+        assert_eq!(format_size(5 * 1024 * 1024 * 1024), "5.0 GiB");
+    }
+}
@@ -20,7 +20,7 @@ mod tests {
         set_colors_enabled(false);
 
         let empty_list: &[String] = &[];
-        assert_eq!(&format_str_id_list(&empty_list), "");
+        assert_eq!(&format_str_id_list(empty_list), "");
         assert_eq!(&format_str_id_list(&["hello".to_string()]), "hello");
         assert_eq!(&format_str_id_list(&["hello", "world"]), "hello, world")
     }
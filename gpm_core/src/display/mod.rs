@@ -1 +1,4 @@
+pub mod info;
 pub mod list;
+pub mod suggestion;
+pub mod tree;
@@ -0,0 +1,42 @@
+/// the number of single character insertions, deletions or substitutions needed to change
+/// ``first`` into ``second``
+fn edit_distance(first: &str, second: &str) -> usize {
+    let second: Vec<char> = second.chars().collect();
+    let mut previous: Vec<usize> = (0..=second.len()).collect();
+    for (i, first_char) in first.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, second_char) in second.iter().enumerate() {
+            let substitution = previous[j] + usize::from(first_char != *second_char);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[second.len()]
+}
+
+/// the element of ``candidates`` closest to ``word``, if it is close enough to be a misspelling
+/// of it
+pub fn did_you_mean<'a, S: AsRef<str>>(word: &str, candidates: &'a [S]) -> Option<&'a str> {
+    let max_distance = (word.chars().count() / 3).max(1);
+    candidates
+        .iter()
+        .map(|candidate| (edit_distance(word, candidate.as_ref()), candidate.as_ref()))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::display::suggestion::did_you_mean;
+
+    #[test]
+    fn test_did_you_mean() {
+        let keys = ["license", "dependencies", "description"];
+        assert_eq!(did_you_mean("licence", &keys), Some("license"));
+        assert_eq!(did_you_mean("dependancies", &keys), Some("dependencies"));
+        assert_eq!(did_you_mean("Description", &keys), Some("description"));
+        assert_eq!(did_you_mean("website", &keys), None);
+        assert_eq!(did_you_mean("a", &["b", "cc"]), Some("b"));
+    }
+}
@@ -0,0 +1,124 @@
+use crate::display::info::format_size;
+use crate::package_writer::{PackagePlan, PlannedEntry};
+
+use console::style;
+
+/// Format the entries of ``plan`` as a tree, like the `tree` command, with the size of the files
+/// and the ignore rule matching them, followed by the totals.
+pub fn format_package_plan(plan: &PackagePlan) -> String {
+    let mut lines = vec![format!(
+        "{} {}",
+        style(
+            plan.information
+                .identifier
+                .as_deref()
+                .unwrap_or("(unnamed)")
+        )
+        .bold(),
+        plan.information
+            .version
+            .as_ref()
+            .map(ToString::to_string)
+            .unwrap_or_default()
+    )];
+    // for each depth of the current entry, true if the ancestor at this depth was the last of
+    // its siblings, so its branch is over
+    let mut last_ancestors: Vec<bool> = Vec::new();
+    for (index, planned) in plan.entries.iter().enumerate() {
+        let depth = planned.entry.relative_path.components().count() - 1;
+        let is_last = plan.entries[index + 1..]
+            .iter()
+            .map(|next| next.entry.relative_path.components().count() - 1)
+            .find(|next_depth| *next_depth <= depth)
+            .is_none_or(|next_depth| next_depth != depth);
+        last_ancestors.truncate(depth);
+
+        let mut line = String::new();
+        for last in &last_ancestors {
+            line.push_str(if *last { "    " } else { "│   " });
+        }
+        line.push_str(if is_last { "└── " } else { "├── " });
+        line.push_str(&format_entry(planned));
+        lines.push(line);
+        last_ancestors.push(is_last);
+    }
+
+    let (mut files, mut ignored) = (0, 0);
+    for planned in &plan.entries {
+        if planned.entry.ignored {
+            ignored += 1;
+        } else if planned.entry.is_file {
+            files += 1;
+        };
+    }
+    lines.push(format!(
+        "{} files, {} uncompressed, {} entries ignored",
+        files,
+        format_size(plan.total_size()),
+        ignored
+    ));
+    lines.join("\n")
+}
+
+/// the name of the entry, and how it is packaged
+fn format_entry(planned: &PlannedEntry) -> String {
+    let entry = &planned.entry;
+    let mut name = entry
+        .relative_path
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string();
+    let mut details = Vec::new();
+    if entry.is_file {
+        details.push(format_size(entry.size));
+    } else {
+        name.push('/');
+    };
+    if entry.is_file && !planned.compress && !entry.ignored {
+        details.push("stored without compression".to_string());
+    };
+    match &entry.rule {
+        Some(rule) if entry.ignored => details.push(format!("ignored: {}", rule)),
+        Some(rule) => details.push(format!("kept: {}", rule)),
+        None => (),
+    };
+    let text = if details.is_empty() {
+        name
+    } else {
+        format!("{} ({})", name, details.join(", "))
+    };
+    if entry.ignored {
+        format!("{}", style(text).dim())
+    } else {
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::display::tree::format_package_plan;
+    use crate::install_strategy::StrategySet;
+    use crate::package_writer::{plan_package, PackageOptions};
+    use crate::test_utils::test_mod_path;
+    use console::set_colors_enabled;
+
+    #[test]
+    fn test_format_package_plan() {
+        set_colors_enabled(false);
+        let test_mod = test_mod_path();
+        let plan = plan_package(&test_mod, &PackageOptions::new(&StrategySet::default())).unwrap();
+        assert_eq!(
+            format_package_plan(&plan),
+            "test_mod 0.0.0
+├── .modignore (24 B)
+├── another_file.txt (13 B)
+├── config.toml (137 B)
+├── ignored.txt (0 B, ignored: \"ignored.txt\" of .modignore)
+└── subfolder/
+    ├── file.arbitrary (5 B)
+    └── stuff.txt (24 B, ignored: \"*/stuff.txt\" of .modignore)
+4 files, 179 B uncompressed, 2 entries ignored"
+        );
+    }
+}
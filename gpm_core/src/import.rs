@@ -0,0 +1,332 @@
+//! Convert archives of existing mods, made without gpm, into gpm packages.
+//!
+//! The archive (zip or 7z) is extracted into a temporary mod project, described by the
+//! [`PackageInformation`] given by the user, which is then packaged with [`create_package`].
+//!
+//! The install strategies of the package are guessed from the known folders of the archive, see
+//! [`guess_layout`].
+
+use std::fs::File;
+use std::io;
+use std::io::{BufReader, Seek, Write};
+use std::path::{Component, Path, PathBuf};
+
+use crate::constants::{
+    CHECKSUMS_PATH, IGNORE_PATH, JSON_CONFIG_PATH, SIGNATURE_PATH, TOML_CONFIG_PATH,
+};
+use crate::install_strategy::DEFAULT_STRATEGY;
+use crate::package::PackageInformation;
+use crate::package_reader::{PackageReader, ReadPackageError};
+use crate::package_writer::{create_package, CreatePackageError, PackageOptions};
+use crate::store_project::get_project_config_toml;
+
+#[derive(thiserror::Error, Debug)]
+pub enum ImportError {
+    #[error("io error with the file {0}")]
+    FileIOError(PathBuf, #[source] io::Error),
+    #[error("can't read the archive {0}")]
+    ReadPackageError(PathBuf, #[source] ReadPackageError),
+    #[error("the archive contain a {0} file at its root, which has a special meaning for gpm")]
+    ReservedFileError(String),
+    #[error("the directory {0} already exist")]
+    ProjectExistError(PathBuf),
+    #[error("can't encode the toml configuration file. Probably internal error")]
+    TomlEncodeError(#[source] toml::ser::Error),
+    #[error("can't create the package")]
+    CreatePackageError(#[from] CreatePackageError),
+}
+
+/// the files at the root of a package that can't come from an imported archive
+const RESERVED_PATHS: &[&str] = &[
+    TOML_CONFIG_PATH,
+    JSON_CONFIG_PATH,
+    IGNORE_PATH,
+    CHECKSUMS_PATH,
+    SIGNATURE_PATH,
+];
+
+/// the folders of the game directory usually found in Cyberpunk 2077 mod archives, with the
+/// install strategy handling them. The files of folders without dedicated strategy are already
+/// at their place in the game directory, and are installed by the [`DEFAULT_STRATEGY`].
+pub const KNOWN_FOLDERS: &[(&str, &str)] = &[
+    ("archive/pc/mod", "archive"),
+    ("r6/scripts", "redscript"),
+    ("bin/x64/plugins", DEFAULT_STRATEGY),
+];
+
+/// The layout of an archive made without gpm, as guessed by [`guess_layout`]
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ArchiveLayout {
+    /// the folder wrapping every file of the archive, that isn't part of the package
+    pub root: Option<PathBuf>,
+    /// the install strategies of the package. Empty if no known folder was found.
+    pub install_strategies: Vec<String>,
+    /// the files, relative to [`Self::root`], that the install strategies doesn't install
+    pub unhandled: Vec<PathBuf>,
+}
+
+/// the strategy handling ``path`` in [`KNOWN_FOLDERS`]
+fn known_folder_strategy(path: &Path) -> Option<&'static str> {
+    KNOWN_FOLDERS
+        .iter()
+        .find(|(folder, _)| path.starts_with(folder))
+        .map(|(_, strategy)| *strategy)
+}
+
+/// guess the layout of a mod archive from the path of its ``files``.
+///
+/// Archives often wrap the mod in a folder named after it, which is detected when every file is
+/// in the same folder, and the known folders are only found inside it. The install strategies
+/// are the ones of the [`KNOWN_FOLDERS`] found, or only the [`DEFAULT_STRATEGY`] if one of them
+/// need it.
+pub fn guess_layout(files: &[PathBuf]) -> ArchiveLayout {
+    let is_known = |file: &Path| known_folder_strategy(file).is_some();
+    let root = files
+        .first()
+        .and_then(|file| match file.components().next() {
+            Some(Component::Normal(name)) => Some(PathBuf::from(name)),
+            _ => None,
+        })
+        .filter(|root| {
+            !files.iter().any(|file| is_known(file))
+                && files
+                    .iter()
+                    .all(|file| file.starts_with(root) && file != root)
+                && files
+                    .iter()
+                    .any(|file| file.strip_prefix(root).is_ok_and(is_known))
+        });
+    let files: Vec<&Path> = files
+        .iter()
+        .map(|file| match &root {
+            Some(root) => file.strip_prefix(root).unwrap(), //unwrap: every file is in root
+            None => file,
+        })
+        .collect();
+
+    let mut install_strategies: Vec<&str> = Vec::new();
+    for (_, strategy) in KNOWN_FOLDERS {
+        if !install_strategies.contains(strategy)
+            && files
+                .iter()
+                .any(|file| known_folder_strategy(file) == Some(strategy))
+        {
+            install_strategies.push(strategy);
+        };
+    }
+    let unhandled = if install_strategies.contains(&DEFAULT_STRATEGY) {
+        install_strategies = vec![DEFAULT_STRATEGY];
+        Vec::new()
+    } else if install_strategies.is_empty() {
+        Vec::new()
+    } else {
+        files
+            .iter()
+            .filter(|file| !is_known(file))
+            .map(|file| file.to_path_buf())
+            .collect()
+    };
+    ArchiveLayout {
+        root,
+        install_strategies: install_strategies.into_iter().map(str::to_string).collect(),
+        unhandled,
+    }
+}
+
+/// extract the archive of an existing mod at ``archive_path`` into ``project_dir``, and write
+/// the [`TOML_CONFIG_PATH`] file for ``information``, making it a mod project. ``project_dir``
+/// must not exist.
+///
+/// The install strategies of ``information`` are set to the guessed ones if it doesn't have
+/// any, and the wrapping folder of the archive is removed, see [`guess_layout`].
+///
+/// Return an error if the archive contain one of the files gpm use at the root of a project.
+pub fn import_archive_as_project(
+    archive_path: &Path,
+    information: PackageInformation,
+    project_dir: &Path,
+) -> Result<ArchiveLayout, ImportError> {
+    if project_dir.exists() {
+        return Err(ImportError::ProjectExistError(project_dir.to_path_buf()));
+    };
+    let read_error = |err| ImportError::ReadPackageError(archive_path.to_path_buf(), err);
+    let file = File::open(archive_path)
+        .map_err(|err| ImportError::FileIOError(archive_path.to_path_buf(), err))?;
+    let mut reader =
+        PackageReader::with_information(BufReader::new(file), information).map_err(read_error)?;
+    let files: Vec<PathBuf> = reader
+        .entries()
+        .map_err(read_error)?
+        .into_iter()
+        .filter(|entry| !entry.is_dir)
+        .map(|entry| entry.path)
+        .collect();
+    let layout = guess_layout(&files);
+    if let Some(reserved) = files.iter().find(|file| {
+        let file = match &layout.root {
+            Some(root) => file.strip_prefix(root).unwrap_or(file),
+            None => file,
+        };
+        RESERVED_PATHS.iter().any(|path| file == Path::new(path))
+    }) {
+        return Err(ImportError::ReservedFileError(
+            reserved.to_string_lossy().to_string(),
+        ));
+    };
+
+    // extracted next to the project, so the wrapping folder can be moved in place
+    let parent_dir = match project_dir.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    std::fs::create_dir_all(parent_dir)
+        .map_err(|err| ImportError::FileIOError(parent_dir.to_path_buf(), err))?;
+    let extract_dir = tempfile::tempdir_in(parent_dir)
+        .map_err(|err| ImportError::FileIOError(parent_dir.to_path_buf(), err))?;
+    reader.extract(extract_dir.path()).map_err(read_error)?;
+    let extracted = match &layout.root {
+        Some(root) => extract_dir.path().join(root),
+        None => extract_dir.path().to_path_buf(),
+    };
+    std::fs::rename(&extracted, project_dir)
+        .map_err(|err| ImportError::FileIOError(project_dir.to_path_buf(), err))?;
+
+    let mut information = reader.into_package().information;
+    if information.install_strategies.is_empty() {
+        information.install_strategies = layout.install_strategies.clone();
+    };
+    let config_content =
+        get_project_config_toml(&information).map_err(ImportError::TomlEncodeError)?;
+    let config_path = project_dir.join(TOML_CONFIG_PATH);
+    std::fs::write(&config_path, config_content)
+        .map_err(|err| ImportError::FileIOError(config_path, err))?;
+    Ok(layout)
+}
+
+/// convert the archive of an existing mod at ``archive_path`` into a package described by
+/// ``information``, written to ``destination``. Return the guessed layout of the archive.
+pub fn import_archive<D: Write + Seek>(
+    archive_path: &Path,
+    information: PackageInformation,
+    destination: &mut D,
+    options: &PackageOptions,
+) -> Result<ArchiveLayout, ImportError> {
+    let temporary_dir =
+        tempfile::tempdir().map_err(|err| ImportError::FileIOError(std::env::temp_dir(), err))?;
+    let project_dir = temporary_dir.path().join("project");
+    let layout = import_archive_as_project(archive_path, information, &project_dir)?;
+    create_package(&project_dir, destination, options)?;
+    Ok(layout)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::import::{guess_layout, import_archive, ArchiveLayout, ImportError};
+    use crate::install_strategy::StrategySet;
+    use crate::package::PackageInformation;
+    use crate::package_reader::PackageReader;
+    use crate::package_writer::PackageOptions;
+    use semver::Version;
+    use std::fs::File;
+    use std::io::{Cursor, Write};
+    use std::path::{Path, PathBuf};
+    use zip::write::{FileOptions, ZipWriter};
+
+    #[test]
+    fn test_import_archive() {
+        let work_dir = tempfile::tempdir().unwrap();
+        let write_archive = |name: &str, files: &[&str]| {
+            let path = work_dir.path().join(name);
+            let mut zip = ZipWriter::new(File::create(&path).unwrap());
+            for file in files {
+                zip.start_file(*file, FileOptions::default()).unwrap();
+                zip.write_all(file.as_bytes()).unwrap();
+            }
+            zip.finish().unwrap();
+            path
+        };
+        let information = || {
+            PackageInformation::new(
+                "modder",
+                "legacy",
+                Version::new(1, 0, 0),
+                "Legacy",
+                "an existing mod",
+                "MIT",
+            )
+        };
+        let strategies = StrategySet::default();
+
+        let legacy = write_archive("legacy.zip", &["archive/pc/mod/legacy.archive"]);
+        let mut buffer = Cursor::new(Vec::new());
+        import_archive(
+            &legacy,
+            information(),
+            &mut buffer,
+            &PackageOptions::new(&strategies),
+        )
+        .unwrap();
+        let mut reader = PackageReader::new(buffer).unwrap();
+        assert_eq!(
+            reader.package().information.install_strategies,
+            vec!["archive"]
+        );
+        assert_eq!(
+            reader.package().information.identifier.as_deref(),
+            Some("legacy")
+        );
+        let entries = reader.entries().unwrap();
+        assert!(entries
+            .iter()
+            .any(|entry| entry.path == Path::new("archive/pc/mod/legacy.archive")));
+
+        let reserved = write_archive("reserved.zip", &["config.toml"]);
+        assert!(matches!(
+            import_archive(
+                &reserved,
+                information(),
+                &mut Cursor::new(Vec::new()),
+                &PackageOptions::new(&strategies),
+            ),
+            Err(ImportError::ReservedFileError(_))
+        ));
+    }
+
+    #[test]
+    fn test_guess_layout() {
+        let paths = |paths: &[&str]| paths.iter().map(PathBuf::from).collect::<Vec<_>>();
+
+        assert_eq!(
+            guess_layout(&paths(&["readme.txt", "other/file.txt"])),
+            ArchiveLayout::default()
+        );
+        assert_eq!(
+            guess_layout(&paths(&[
+                "My Mod/archive/pc/mod/mod.archive",
+                "My Mod/r6/scripts/mod.reds",
+                "My Mod/readme.txt",
+            ])),
+            ArchiveLayout {
+                root: Some(PathBuf::from("My Mod")),
+                install_strategies: vec!["archive".to_string(), "redscript".to_string()],
+                unhandled: paths(&["readme.txt"]),
+            }
+        );
+        assert_eq!(
+            guess_layout(&paths(&[
+                "archive/pc/mod/mod.archive",
+                "bin/x64/plugins/cyber_engine_tweaks/mods/mod/init.lua",
+            ])),
+            ArchiveLayout {
+                root: None,
+                install_strategies: vec!["root".to_string()],
+                unhandled: Vec::new(),
+            }
+        );
+        // a single folder without known folder inside isn't a wrapping folder
+        assert_eq!(
+            guess_layout(&paths(&["mod/init.lua"])),
+            ArchiveLayout::default()
+        );
+    }
+}
@@ -0,0 +1,182 @@
+//! Describe a mod project or a package archive: its information, and the files it contains.
+//!
+//! This is what `gpm info` show. The description can be formatted with
+//! [`crate::display::info::format_package_info`], or serialized as JSON.
+
+use std::path::{Path, PathBuf};
+
+use crate::archive::ArchiveFormat;
+use crate::package::PackageInformation;
+use crate::package_reader::{PackageReader, ReadPackageError};
+use crate::package_writer::{project_entries, CreatePackageError, WalkOptions};
+use crate::store_project::{load_project_config, serialize_information};
+use crate::store_project::{LoadPackageFromProjectError, UnknownKey};
+
+use serde::{Serialize, Serializer};
+
+#[derive(thiserror::Error, Debug)]
+pub enum InfoError {
+    #[error("can't load the mod project in {0}")]
+    LoadPackageError(PathBuf, #[source] LoadPackageFromProjectError),
+    #[error("can't list the files of the mod project")]
+    ListFilesError(#[from] CreatePackageError),
+    #[error("can't read the package archive {0}")]
+    ReadPackageError(PathBuf, #[source] ReadPackageError),
+}
+
+/// Where the described package come from
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PackageKind {
+    /// a mod project directory
+    Project,
+    /// a package archive
+    Archive(ArchiveFormat),
+}
+
+impl Serialize for PackageKind {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Self::Project => serializer.serialize_str("project"),
+            Self::Archive(format) => serializer.serialize_str(format.name()),
+        }
+    }
+}
+
+/// A file of the package, with its uncompressed size
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct FileInfo {
+    /// the path of the file, relative to the root of the package
+    pub path: PathBuf,
+    pub size: u64,
+}
+
+/// What [`package_info`] know about a package
+#[derive(Serialize)]
+pub struct PackageInfo {
+    pub kind: PackageKind,
+    #[serde(serialize_with = "serialize_information")]
+    pub information: PackageInformation,
+    /// the files that are (or will be, for a project) in the package archive. Files ignored by
+    /// the project aren't listed.
+    pub files: Vec<FileInfo>,
+    /// the fields needed to publish the package, but not set
+    pub missing_publish_field: Vec<&'static str>,
+    /// the keys of the configuration unknown to gpm. Always empty for archives.
+    #[serde(skip)]
+    pub unknown_keys: Vec<UnknownKey>,
+}
+
+impl PackageInfo {
+    /// the sum of the size of the files
+    pub fn total_size(&self) -> u64 {
+        self.files.iter().map(|file| file.size).sum()
+    }
+}
+
+/// describe the mod project in ``project_dir``
+pub fn project_info(project_dir: &Path) -> Result<PackageInfo, InfoError> {
+    let config = load_project_config(project_dir)
+        .map_err(|err| InfoError::LoadPackageError(project_dir.to_path_buf(), err))?;
+    let mut files = Vec::new();
+    for entry in project_entries(
+        project_dir,
+        &config.package.information,
+        &WalkOptions::default(),
+    )? {
+        if entry.ignored || !entry.is_file {
+            continue;
+        };
+        files.push(FileInfo {
+            path: entry.relative_path,
+            size: entry.size,
+        });
+    }
+    let information = config.package.information;
+    Ok(PackageInfo {
+        kind: PackageKind::Project,
+        missing_publish_field: information.missing_publish_field(),
+        information,
+        files,
+        unknown_keys: config.unknown_keys,
+    })
+}
+
+/// describe the package archive at ``archive_path``
+pub fn archive_info(archive_path: &Path) -> Result<PackageInfo, InfoError> {
+    let read_error = |err| InfoError::ReadPackageError(archive_path.to_path_buf(), err);
+    let mut reader = PackageReader::open(archive_path).map_err(read_error)?;
+    let files = reader
+        .entries()
+        .map_err(read_error)?
+        .into_iter()
+        .filter(|entry| !entry.is_dir)
+        .map(|entry| FileInfo {
+            path: entry.path,
+            size: entry.size,
+        })
+        .collect();
+    let kind = PackageKind::Archive(reader.format());
+    let information = reader.into_package().information;
+    Ok(PackageInfo {
+        kind,
+        missing_publish_field: information.missing_publish_field(),
+        information,
+        files,
+        unknown_keys: Vec::new(),
+    })
+}
+
+/// describe the mod project or package archive at ``path``, depending on whether it is a
+/// directory
+pub fn package_info(path: &Path) -> Result<PackageInfo, InfoError> {
+    if path.is_dir() {
+        project_info(path)
+    } else {
+        archive_info(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::info::{package_info, FileInfo, PackageKind};
+    use crate::install_strategy::StrategySet;
+    use crate::package_writer::{create_package, PackageOptions};
+    use crate::test_utils::test_mod_path;
+    use std::fs::{self, File};
+    use std::path::{Path, PathBuf};
+
+    #[test]
+    fn test_package_info() {
+        let test_mod = test_mod_path();
+        let project = package_info(&test_mod).unwrap();
+        assert_eq!(project.kind, PackageKind::Project);
+        assert!(project.missing_publish_field.is_empty());
+        assert!(project.files.contains(&FileInfo {
+            path: PathBuf::from("subfolder/file.arbitrary"),
+            size: fs::metadata(test_mod.join("subfolder/file.arbitrary"))
+                .unwrap()
+                .len()
+        }));
+        assert!(!project
+            .files
+            .iter()
+            .any(|file| file.path == Path::new("ignored.txt")));
+
+        let work_dir = tempfile::tempdir().unwrap();
+        let archive_path = work_dir.path().join("test_mod.zip");
+        create_package(
+            &test_mod,
+            &mut File::create(&archive_path).unwrap(),
+            &PackageOptions::new(&StrategySet::default()),
+        )
+        .unwrap();
+        let archive = package_info(&archive_path).unwrap();
+        assert!(matches!(archive.kind, PackageKind::Archive(_)));
+        assert_eq!(
+            archive.information.identifier,
+            project.information.identifier
+        );
+        assert_eq!(archive.files, project.files);
+        assert_eq!(archive.total_size(), project.total_size());
+    }
+}
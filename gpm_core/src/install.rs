@@ -0,0 +1,738 @@
+//! Install and remove packages in a [`Profile`], updating its [`LockFile`] and deploying the
+//! packages files in its game directory.
+//!
+//! Each operation is transactional: if it fails, the lock file, the staging directory and the
+//! game directory are left as they were before.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::io::{Read, Seek};
+use std::path::{Path, PathBuf};
+
+use crate::checksum::sha256_file;
+use crate::conflict::sort_by_load_order;
+use crate::constants::{CHECKSUMS_PATH, JSON_CONFIG_PATH};
+use crate::deploy::{
+    deploy, read_staged_information, DeployError, DeploymentManifest, Journal, StagedPackage,
+    JOURNAL_DIRECTORY,
+};
+use crate::install_strategy::StrategySet;
+use crate::lockfile::{LockFile, LockSource};
+use crate::package::{DependencySource, PackageDependency};
+use crate::package_reader::{safe_relative_path, PackageReader, ReadPackageError};
+use crate::package_writer::{create_package, CreatePackageError, PackageOptions};
+use crate::profile::{Profile, ProfileError, PROFILE_CONFIG_PATH, PROFILE_LOCK_PATH};
+use crate::report::{Event, Reporter};
+use crate::repository::{LocalRepository, RepositoryError};
+use crate::resolver::{exact_requirement, resolve, ResolveError};
+use crate::signature::{SignatureError, SignaturePolicy, SignatureStatus, TrustedKeys};
+use crate::store_project::{
+    get_project_config_json, load_package_from_project, LoadPackageFromProjectError,
+};
+
+use semver::{Version, VersionReq};
+
+/// the folder, in the staging directory, where packages are extracted before being moved in place
+pub const PENDING_DIRECTORY: &str = ".pending";
+
+/// What to install with [`install`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum InstallRequest {
+    /// install the package with this identifier from the repository, at the given version or
+    /// the most recent compatible one, along with its dependencies.
+    Repository {
+        identifier: String,
+        version: Option<Version>,
+    },
+    /// install the mod project directory or the package archive at this path. Its dependencies
+    /// aren't installed.
+    Path(PathBuf),
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum InstallError {
+    #[error("error with the profile")]
+    ProfileError(#[from] ProfileError),
+    #[error("error while deploying the mods in the game directory")]
+    DeployError(#[from] DeployError),
+    #[error("error with the package repository")]
+    RepositoryError(#[from] RepositoryError),
+    #[error("can't resolve the dependencies")]
+    ResolveError(#[from] ResolveError),
+    #[error("error while reading the package archive {0}")]
+    ReadPackageError(PathBuf, #[source] ReadPackageError),
+    #[error("error while loading the mod project {0}")]
+    LoadPackageError(PathBuf, #[source] LoadPackageFromProjectError),
+    #[error("error while packaging the mod project {0}")]
+    CreatePackageError(PathBuf, #[source] CreatePackageError),
+    #[error("io error with the file {0}")]
+    FileIOError(PathBuf, #[source] io::Error),
+    #[error("installing {0} need a package repository, but the profile doesn't have one")]
+    MissingRepositoryError(String),
+    #[error("the package {0} {1} isn't in the repository")]
+    PackageNotFoundError(String, Version),
+    #[error("the package at {0} doesn't have an identifier")]
+    MissingIdentifierError(PathBuf),
+    #[error("the package identifier {0:?} can't be used as a folder name")]
+    InvalidIdentifierError(String),
+    #[error("the package {0} isn't installed in this profile")]
+    NotInstalledError(String),
+    #[error("can't write the configuration of the package {0:?}. Probably internal error")]
+    EncodeJsonError(Option<String>, #[source] serde_json::Error),
+    #[error("the archive {0} doesn't match the checksum of the lock file, it may have been modified or corrupted")]
+    ChecksumMismatchError(PathBuf),
+    #[error("error with the trusted keys of the profile")]
+    SignatureError(#[from] SignatureError),
+    #[error("the package archive {0} {1}")]
+    UntrustedPackageError(PathBuf, SignatureStatus),
+    #[error("the mod project {0} can't be signed, so it can't be installed with the strict signature policy")]
+    UnsignedProjectError(PathBuf),
+    #[error("{0}, and the previous state couldn't be fully restored")]
+    RollbackError(Box<InstallError>, #[source] DeployError),
+}
+
+/// Install a package in ``profile``, and return the identifiers of the packages that were added
+/// or changed.
+///
+/// ``repository`` is used to install packages by identifier, and to extract them again if their
+/// staging directory was removed. The problems that don't stop the installation, like an archive
+/// that can't be verified, are reported to ``reporter``.
+pub fn install(
+    profile: &mut Profile,
+    request: InstallRequest,
+    repository: Option<&LocalRepository>,
+    strategies: &StrategySet,
+    reporter: &dyn Reporter,
+) -> Result<Vec<String>, InstallError> {
+    let mut lock_file = profile.lock_file.clone();
+    let mut changed = Vec::new();
+    match request {
+        InstallRequest::Repository {
+            identifier,
+            version,
+        } => {
+            let repository = repository
+                .ok_or_else(|| InstallError::MissingRepositoryError(identifier.clone()))?;
+            let mut dependencies = locked_dependencies(&profile.lock_file, &identifier);
+            dependencies.push(PackageDependency::new(
+                &identifier,
+                version
+                    .as_ref()
+                    .map(exact_requirement)
+                    .unwrap_or(VersionReq::STAR),
+            ));
+            let resolved = resolve(&dependencies, repository)?;
+            for (identifier, source) in resolved.iter_dependency_source() {
+                // packages installed from a path are kept as they are
+                if matches!(source, LockSource::Path { .. }) {
+                    continue;
+                };
+                if lock_file.dependency_source(identifier).as_ref() != Some(source) {
+                    lock_file.set_dependency_source(identifier.clone(), source.clone());
+                    changed.push(identifier.clone());
+                };
+            }
+        }
+        InstallRequest::Path(path) => {
+            let identifier = path_package_identifier(&path)?;
+            let checksum = if path.is_dir() {
+                None
+            } else {
+                Some(
+                    sha256_file(&path)
+                        .map_err(|err| InstallError::FileIOError(path.clone(), err))?,
+                )
+            };
+            lock_file
+                .set_dependency_source(identifier.clone(), LockSource::Path { path, checksum });
+            changed.push(identifier);
+        }
+    };
+    changed.sort();
+    apply(
+        profile, lock_file, &changed, repository, strategies, reporter,
+    )?;
+    Ok(changed)
+}
+
+/// Remove the package with the given identifier from ``profile``, and delete its files from the
+/// game directory. Packages that depend on it are kept.
+pub fn remove(
+    profile: &mut Profile,
+    identifier: &str,
+    repository: Option<&LocalRepository>,
+    strategies: &StrategySet,
+    reporter: &dyn Reporter,
+) -> Result<(), InstallError> {
+    let mut lock_file = profile.lock_file.clone();
+    if lock_file.remove_dependency_source(identifier).is_none() {
+        return Err(InstallError::NotInstalledError(identifier.to_string()));
+    };
+    apply(profile, lock_file, &[], repository, strategies, reporter)
+}
+
+/// Deploy again the packages of ``profile``, after a change of its load order. The configuration
+/// of the profile is saved along with the deployment, so the saved load order always match the
+/// deployed files.
+pub fn redeploy(
+    profile: &mut Profile,
+    repository: Option<&LocalRepository>,
+    strategies: &StrategySet,
+    reporter: &dyn Reporter,
+) -> Result<(), InstallError> {
+    let lock_file = profile.lock_file.clone();
+    apply(profile, lock_file, &[], repository, strategies, reporter)
+}
+
+/// the packages of ``lock_file``, as dependencies on their exact locked version, except the one
+/// with identifier ``except``
+fn locked_dependencies(lock_file: &LockFile, except: &str) -> Vec<PackageDependency> {
+    lock_file
+        .iter_dependency_source()
+        .filter(|(identifier, _)| identifier.as_str() != except)
+        .map(|(identifier, source)| match source {
+            LockSource::IdVersion { version, .. } => {
+                PackageDependency::new(identifier, exact_requirement(version))
+            }
+            LockSource::Path { path, .. } => PackageDependency {
+                source: DependencySource::Path(path.clone()),
+                ..PackageDependency::new(identifier, VersionReq::STAR)
+            },
+        })
+        .collect()
+}
+
+/// read the identifier of the mod project or package archive at ``path``
+fn path_package_identifier(path: &Path) -> Result<String, InstallError> {
+    let information = if path.is_dir() {
+        load_package_from_project(path)
+            .map_err(|err| InstallError::LoadPackageError(path.to_path_buf(), err))?
+            .information
+    } else {
+        PackageReader::open(path)
+            .map_err(|err| InstallError::ReadPackageError(path.to_path_buf(), err))?
+            .into_package()
+            .information
+    };
+    information
+        .identifier
+        .ok_or_else(|| InstallError::MissingIdentifierError(path.to_path_buf()))
+}
+
+/// check the signature of the archive of ``reader`` according to the signature policy of
+/// ``profile``. Accepted archives that aren't trusted are reported to ``reporter``.
+fn check_signature<R: Read + Seek>(
+    profile: &Profile,
+    reader: &PackageReader<R>,
+    archive_path: &Path,
+    reporter: &dyn Reporter,
+) -> Result<(), InstallError> {
+    if profile.signature_policy == SignaturePolicy::Off {
+        return Ok(());
+    };
+    let status = reader.check_signature(&TrustedKeys::load(profile)?);
+    match (status, profile.signature_policy) {
+        (SignatureStatus::Trusted(_), _) => Ok(()),
+        (status @ SignatureStatus::Invalid, _) | (status, SignaturePolicy::Strict) => Err(
+            InstallError::UntrustedPackageError(archive_path.to_path_buf(), status),
+        ),
+        (status, _) => {
+            reporter.report(Event::Warning {
+                message: format!("the package archive {:?} {}", archive_path, status),
+            });
+            Ok(())
+        }
+    }
+}
+
+/// warn when the archive of ``reader`` doesn't have a [`CHECKSUMS_PATH`] file, as its files
+/// can't be verified when extracted. With the strict signature policy, such an archive is
+/// already refused by [`check_signature`], as the signature cover the checksums.
+fn check_checksums<R: Read + Seek>(
+    reader: &PackageReader<R>,
+    archive_path: &Path,
+    reporter: &dyn Reporter,
+) {
+    if reader.checksums().is_none() {
+        reporter.report(Event::Warning {
+            message: format!(
+                "the package archive {:?} doesn't have a {} file, its files can't be verified",
+                archive_path, CHECKSUMS_PATH
+            ),
+        });
+    };
+}
+
+/// extract the archive of ``reader`` into ``destination``, along with its
+/// [`JSON_CONFIG_PATH`] file, so the staged package can be deployed without the archive
+fn extract_archive<R: Read + Seek>(
+    reader: &mut PackageReader<R>,
+    destination: &Path,
+    archive_path: &Path,
+) -> Result<(), InstallError> {
+    reader
+        .extract(destination)
+        .map_err(|err| InstallError::ReadPackageError(archive_path.to_path_buf(), err))?;
+    let config_path = destination.join(JSON_CONFIG_PATH);
+    let config_content = get_project_config_json(&reader.package().information).map_err(|err| {
+        InstallError::EncodeJsonError(reader.package().information.identifier.clone(), err)
+    })?;
+    fs::write(&config_path, config_content)
+        .map_err(|err| InstallError::FileIOError(config_path, err))
+}
+
+/// extract the package from ``source`` into the ``destination`` directory
+fn stage(
+    profile: &Profile,
+    source: &LockSource,
+    destination: &Path,
+    repository: Option<&LocalRepository>,
+    strategies: &StrategySet,
+    reporter: &dyn Reporter,
+) -> Result<(), InstallError> {
+    match source {
+        LockSource::IdVersion {
+            identifier,
+            version,
+            checksum,
+        } => {
+            let repository = repository
+                .ok_or_else(|| InstallError::MissingRepositoryError(identifier.clone()))?;
+            let entry = repository.find(identifier, version).ok_or_else(|| {
+                InstallError::PackageNotFoundError(identifier.clone(), version.clone())
+            })?;
+            if checksum
+                .as_ref()
+                .is_some_and(|checksum| checksum != &entry.checksum)
+            {
+                return Err(InstallError::ChecksumMismatchError(
+                    repository.archive_path(entry),
+                ));
+            };
+            let archive_path = repository.archive_path(entry);
+            let mut reader = repository.open_archive(entry)?;
+            check_signature(profile, &reader, &archive_path, reporter)?;
+            check_checksums(&reader, &archive_path, reporter);
+            extract_archive(&mut reader, destination, &archive_path)
+        }
+        LockSource::Path { path, checksum } => {
+            let path = profile.resolve_path(path);
+            if let Some(checksum) = checksum {
+                let actual = sha256_file(&path)
+                    .map_err(|err| InstallError::FileIOError(path.clone(), err))?;
+                if &actual != checksum {
+                    return Err(InstallError::ChecksumMismatchError(path));
+                };
+            };
+            if path.is_dir() {
+                // a project can't be signed, only trusted because the user chose it, which the
+                // strict policy doesn't allow
+                if profile.signature_policy == SignaturePolicy::Strict {
+                    return Err(InstallError::UnsignedProjectError(path));
+                };
+                // package the project, so the ignored files are handled like in a published mod.
+                // It is packaged locally, so its signature isn't checked.
+                let mut archive_path = destination.as_os_str().to_owned();
+                archive_path.push(".zip");
+                let archive_path = PathBuf::from(archive_path);
+                let mut archive = File::create(&archive_path)
+                    .map_err(|err| InstallError::FileIOError(archive_path.clone(), err))?;
+                create_package(&path, &mut archive, &PackageOptions::new(strategies))
+                    .map_err(|err| InstallError::CreatePackageError(path.clone(), err))?;
+                drop(archive);
+                let result = PackageReader::open(&archive_path)
+                    .map_err(|err| InstallError::ReadPackageError(archive_path.clone(), err))
+                    .and_then(|mut reader| {
+                        extract_archive(&mut reader, destination, &archive_path)
+                    });
+                let _ = fs::remove_file(&archive_path);
+                result
+            } else {
+                let mut reader = PackageReader::open(&path)
+                    .map_err(|err| InstallError::ReadPackageError(path.clone(), err))?;
+                check_signature(profile, &reader, &path, reporter)?;
+                check_checksums(&reader, &path, reporter);
+                extract_archive(&mut reader, destination, &path)
+            }
+        }
+    }
+}
+
+/// make ``lock_file`` the lock file of ``profile``, staging the packages in ``restage`` and the
+/// ones that changed, and deploying the result. Everything is rolled back on error.
+fn apply(
+    profile: &mut Profile,
+    lock_file: LockFile,
+    restage: &[String],
+    repository: Option<&LocalRepository>,
+    strategies: &StrategySet,
+    reporter: &dyn Reporter,
+) -> Result<(), InstallError> {
+    let mut journal = Journal::new(&profile.path.join(JOURNAL_DIRECTORY))?;
+    let pending_dir = profile.staging_dir.join(PENDING_DIRECTORY);
+    let result = match apply_with_journal(
+        profile,
+        &lock_file,
+        restage,
+        repository,
+        strategies,
+        reporter,
+        &mut journal,
+    ) {
+        Ok(()) => {
+            journal.commit()?;
+            profile.lock_file = lock_file;
+            Ok(())
+        }
+        Err(err) => match journal.rollback() {
+            Ok(()) => Err(err),
+            Err(rollback_err) => Err(InstallError::RollbackError(Box::new(err), rollback_err)),
+        },
+    };
+    let _ = fs::remove_dir_all(&pending_dir);
+    result
+}
+
+fn apply_with_journal(
+    profile: &Profile,
+    lock_file: &LockFile,
+    restage: &[String],
+    repository: Option<&LocalRepository>,
+    strategies: &StrategySet,
+    reporter: &dyn Reporter,
+    journal: &mut Journal,
+) -> Result<(), InstallError> {
+    let previous = DeploymentManifest::load(profile)?;
+    let pending_dir = profile.staging_dir.join(PENDING_DIRECTORY);
+    if pending_dir.exists() {
+        fs::remove_dir_all(&pending_dir)
+            .map_err(|err| InstallError::FileIOError(pending_dir.clone(), err))?;
+    };
+    fs::create_dir_all(&pending_dir)
+        .map_err(|err| InstallError::FileIOError(pending_dir.clone(), err))?;
+
+    let sources: BTreeMap<&String, &LockSource> = lock_file.iter_dependency_source().collect();
+    let mut staged_packages = Vec::new();
+    for (identifier, source) in sources {
+        if safe_relative_path(identifier)
+            .map(|path| path.components().count() != 1)
+            .unwrap_or(true)
+            || identifier.starts_with('.')
+        {
+            return Err(InstallError::InvalidIdentifierError(identifier.clone()));
+        };
+        let staged_dir = profile.staging_dir.join(identifier);
+        let restaged = restage.contains(identifier)
+            || previous.packages.get(identifier) != Some(source)
+            || !staged_dir.join(JSON_CONFIG_PATH).is_file();
+        if restaged {
+            let pending_package_dir = pending_dir.join(identifier);
+            stage(
+                profile,
+                source,
+                &pending_package_dir,
+                repository,
+                strategies,
+                reporter,
+            )?;
+            journal.move_directory(&pending_package_dir, &staged_dir)?;
+        };
+        staged_packages.push(StagedPackage {
+            identifier: identifier.clone(),
+            source: source.clone(),
+            information: read_staged_information(&staged_dir)?,
+            staged_dir,
+            restaged,
+        });
+    }
+    sort_by_load_order(&mut staged_packages, &profile.load_order);
+
+    for identifier in previous.packages.keys() {
+        let staged_dir = profile.staging_dir.join(identifier);
+        if lock_file.dependency_source(identifier).is_none() && staged_dir.is_dir() {
+            journal.remove_directory(&staged_dir)?;
+        };
+    }
+
+    deploy(profile, &staged_packages, strategies, journal)?;
+
+    let mut lock_content = Vec::new();
+    lock_file
+        .write_writer(&mut lock_content)
+        .map_err(|err| ProfileError::LockFileError(profile.name.clone(), err))?;
+    journal.write_file(&profile.path.join(PROFILE_LOCK_PATH), &lock_content)?;
+    journal.write_file(
+        &profile.path.join(PROFILE_CONFIG_PATH),
+        &profile.config_content()?,
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::deploy::{verify_deployment, DeploymentIssue};
+    use crate::install::{install, redeploy, remove, InstallError, InstallRequest};
+    use crate::install_strategy::StrategySet;
+    use crate::package_writer::{create_package, PackageOptions};
+    use crate::profile::ProfileStore;
+    use crate::report::tests::RecordReporter;
+    use crate::report::{Event, SilentReporter};
+    use crate::repository::LocalRepository;
+    use crate::signature::{generate_key, SignaturePolicy, TrustedKeys};
+    use crate::test_utils::test_mod_path;
+    use std::fs;
+    use std::fs::File;
+    use std::io::Write;
+    use zip::write::{FileOptions, ZipWriter};
+
+    #[test]
+    fn test_install_and_remove() {
+        let test_mod = test_mod_path();
+        let temporary_dir = tempfile::tempdir().unwrap();
+        let work_dir = temporary_dir.path();
+
+        let archive_path = work_dir.join("test_mod.zip");
+        create_package(
+            &test_mod,
+            &mut File::create(&archive_path).unwrap(),
+            &PackageOptions::new(&StrategySet::default()),
+        )
+        .unwrap();
+        let mut repository = LocalRepository::create(&work_dir.join("repository")).unwrap();
+        repository.add_archive(&archive_path).unwrap();
+
+        let game_dir = work_dir.join("game");
+        fs::create_dir_all(&game_dir).unwrap();
+        fs::write(game_dir.join("another_file.txt"), "vanilla").unwrap();
+
+        let mut store = ProfileStore::open(&work_dir.join("home")).unwrap();
+        let mut profile = store.create("modded", &game_dir, None).unwrap();
+
+        let installed = install(
+            &mut profile,
+            InstallRequest::Repository {
+                identifier: "test_mod".into(),
+                version: None,
+            },
+            Some(&repository),
+            &StrategySet::default(),
+            &SilentReporter,
+        )
+        .unwrap();
+        assert_eq!(installed, vec!["test_mod".to_string()]);
+        assert!(game_dir.join("subfolder").join("file.arbitrary").is_file());
+        assert_ne!(
+            fs::read_to_string(game_dir.join("another_file.txt")).unwrap(),
+            "vanilla"
+        );
+        assert!(store
+            .load("modded")
+            .unwrap()
+            .lock_file
+            .dependency_source("test_mod")
+            .is_some());
+        assert!(verify_deployment(&profile).unwrap().is_empty());
+        profile.load_order = vec!["test_mod".to_string()];
+        redeploy(
+            &mut profile,
+            Some(&repository),
+            &StrategySet::default(),
+            &SilentReporter,
+        )
+        .unwrap();
+        assert_eq!(store.load("modded").unwrap().load_order, profile.load_order);
+
+        fs::write(game_dir.join("another_file.txt"), "tampered").unwrap();
+        assert_eq!(
+            verify_deployment(&profile).unwrap(),
+            vec![DeploymentIssue::Modified("another_file.txt".to_string())]
+        );
+
+        remove(
+            &mut profile,
+            "test_mod",
+            Some(&repository),
+            &StrategySet::default(),
+            &SilentReporter,
+        )
+        .unwrap();
+        assert!(!game_dir.join("subfolder").exists());
+        assert_eq!(
+            fs::read_to_string(game_dir.join("another_file.txt")).unwrap(),
+            "vanilla"
+        );
+        assert!(profile.lock_file.dependency_source("test_mod").is_none());
+        assert!(matches!(
+            remove(
+                &mut profile,
+                "test_mod",
+                Some(&repository),
+                &StrategySet::default(),
+                &SilentReporter
+            ),
+            Err(InstallError::NotInstalledError(_))
+        ));
+    }
+
+    #[test]
+    fn test_install_rollback() {
+        let test_mod = test_mod_path();
+        let temporary_dir = tempfile::tempdir().unwrap();
+        let work_dir = temporary_dir.path();
+        let game_dir = work_dir.join("game");
+        fs::create_dir_all(&game_dir).unwrap();
+        // a file where the mod need a directory make the deployment fail halfway
+        fs::write(game_dir.join("subfolder"), "vanilla").unwrap();
+
+        let mut store = ProfileStore::open(&work_dir.join("home")).unwrap();
+        let mut profile = store.create("modded", &game_dir, None).unwrap();
+        profile.load_order = vec!["test_mod".to_string()];
+        assert!(install(
+            &mut profile,
+            InstallRequest::Path(test_mod),
+            None,
+            &StrategySet::default(),
+            &SilentReporter
+        )
+        .is_err());
+
+        assert!(profile.lock_file.dependency_source("test_mod").is_none());
+        assert!(store
+            .load("modded")
+            .unwrap()
+            .lock_file
+            .dependency_source("test_mod")
+            .is_none());
+        assert!(store.load("modded").unwrap().load_order.is_empty());
+        let mut game_files = fs::read_dir(&game_dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect::<Vec<_>>();
+        game_files.sort();
+        assert_eq!(game_files, vec!["subfolder"]);
+        assert!(!profile.staging_dir.join("test_mod").exists());
+    }
+
+    #[test]
+    fn test_install_signature_policy() {
+        let test_mod = test_mod_path();
+        let temporary_dir = tempfile::tempdir().unwrap();
+        let work_dir = temporary_dir.path();
+        let strategies = StrategySet::default();
+
+        let unsigned_path = work_dir.join("unsigned.zip");
+        create_package(
+            &test_mod,
+            &mut File::create(&unsigned_path).unwrap(),
+            &PackageOptions::new(&strategies),
+        )
+        .unwrap();
+        let key = generate_key();
+        let signed_path = work_dir.join("signed.zip");
+        let mut options = PackageOptions::new(&strategies);
+        options.signing_key = Some(&key);
+        create_package(
+            &test_mod,
+            &mut File::create(&signed_path).unwrap(),
+            &options,
+        )
+        .unwrap();
+
+        let game_dir = work_dir.join("game");
+        fs::create_dir_all(&game_dir).unwrap();
+        let mut store = ProfileStore::open(&work_dir.join("home")).unwrap();
+        let mut profile = store.create("modded", &game_dir, None).unwrap();
+        profile.signature_policy = SignaturePolicy::Strict;
+
+        assert!(matches!(
+            install(
+                &mut profile,
+                InstallRequest::Path(test_mod.clone()),
+                None,
+                &strategies,
+                &SilentReporter,
+            ),
+            Err(InstallError::UnsignedProjectError(_))
+        ));
+        for archive_path in &[&unsigned_path, &signed_path] {
+            assert!(matches!(
+                install(
+                    &mut profile,
+                    InstallRequest::Path(archive_path.to_path_buf()),
+                    None,
+                    &strategies,
+                    &SilentReporter
+                ),
+                Err(InstallError::UntrustedPackageError(_, _))
+            ));
+        }
+
+        let mut trusted_keys = TrustedKeys::default();
+        trusted_keys
+            .add("modder", &hex::encode(key.verifying_key().to_bytes()))
+            .unwrap();
+        trusted_keys.save(&profile).unwrap();
+        install(
+            &mut profile,
+            InstallRequest::Path(signed_path),
+            None,
+            &strategies,
+            &SilentReporter,
+        )
+        .unwrap();
+        assert!(game_dir.join("subfolder").join("file.arbitrary").is_file());
+
+        profile.signature_policy = SignaturePolicy::Warn;
+        let reporter = RecordReporter::default();
+        install(
+            &mut profile,
+            InstallRequest::Path(unsigned_path),
+            None,
+            &strategies,
+            &reporter,
+        )
+        .unwrap();
+        assert!(matches!(
+            reporter.events().as_slice(),
+            [Event::Warning { message }] if message.contains("unsigned.zip")
+        ));
+    }
+
+    #[test]
+    fn test_install_without_checksums() {
+        let temporary_dir = tempfile::tempdir().unwrap();
+        let work_dir = temporary_dir.path();
+        let archive_path = work_dir.join("bare.zip");
+        let mut zip = ZipWriter::new(File::create(&archive_path).unwrap());
+        zip.start_file("config.json", FileOptions::default())
+            .unwrap();
+        zip.write_all(b"{\"identifier\": \"bare\", \"version\": \"1.0.0\"}")
+            .unwrap();
+        zip.start_file("bare.txt", FileOptions::default()).unwrap();
+        zip.write_all(b"bare").unwrap();
+        zip.finish().unwrap();
+        drop(zip);
+
+        let game_dir = work_dir.join("game");
+        fs::create_dir_all(&game_dir).unwrap();
+        let mut store = ProfileStore::open(&work_dir.join("home")).unwrap();
+        let mut profile = store.create("modded", &game_dir, None).unwrap();
+        profile.signature_policy = SignaturePolicy::Off;
+        let reporter = RecordReporter::default();
+        install(
+            &mut profile,
+            InstallRequest::Path(archive_path),
+            None,
+            &StrategySet::default(),
+            &reporter,
+        )
+        .unwrap();
+        assert!(game_dir.join("bare.txt").is_file());
+        assert!(matches!(
+            reporter.events().as_slice(),
+            [Event::Warning { message }] if message.contains("checksums.json")
+        ));
+    }
+}
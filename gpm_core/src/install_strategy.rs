@@ -0,0 +1,232 @@
+//! Map the files of a package onto the game directory, following the `install_strategies` of
+//! the package.
+//!
+//! The strategies known for a game are grouped in a [`StrategySet`]. Each file of a package is
+//! installed by the first of its strategies that handle it, and files no strategy handle aren't
+//! installed. A package without strategy use [`DEFAULT_STRATEGY`], that copy everything as is.
+
+use crate::display::list::format_str_id_list;
+
+/// the strategy used by packages that don't specify any
+pub const DEFAULT_STRATEGY: &str = "root";
+
+#[derive(thiserror::Error, Debug)]
+pub enum InstallStrategyError {
+    #[error("the install strategy {0:?} doesn't exist for {1}. The known strategies are {2}")]
+    UnknownStrategyError(String, String, String), //strategy, game, formatted known strategies
+}
+
+/// A way to install the files of a package in the game directory
+pub trait InstallStrategy {
+    /// the name used in the `install_strategies` of packages
+    fn name(&self) -> &str;
+
+    fn description(&self) -> &str;
+
+    /// the path, relative to the game directory, where the file at ``relative_path`` in the
+    /// package ``identifier`` should be installed, or None if this strategy doesn't handle it.
+    /// Paths use `/` separators.
+    fn target(&self, identifier: &str, relative_path: &str) -> Option<String>;
+}
+
+/// Install the files in the ``source`` folder of a package into the ``destination`` folder of
+/// the game directory. ``{identifier}`` in ``destination`` is replaced by the identifier of the
+/// package.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrefixStrategy {
+    name: String,
+    description: String,
+    source: String,
+    destination: String,
+}
+
+impl PrefixStrategy {
+    /// ``source`` and ``destination`` are folders using `/` separators. An empty one is the root
+    /// of the package or of the game directory.
+    pub fn new(name: &str, description: &str, source: &str, destination: &str) -> Self {
+        fn as_prefix(folder: &str) -> String {
+            let folder = folder.trim_matches('/');
+            if folder.is_empty() {
+                String::new()
+            } else {
+                format!("{}/", folder)
+            }
+        }
+        Self {
+            name: name.to_string(),
+            description: description.to_string(),
+            source: as_prefix(source),
+            destination: as_prefix(destination),
+        }
+    }
+}
+
+impl InstallStrategy for PrefixStrategy {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+
+    fn target(&self, identifier: &str, relative_path: &str) -> Option<String> {
+        let rest = relative_path.strip_prefix(&self.source)?;
+        Some(format!(
+            "{}{}",
+            self.destination.replace("{identifier}", identifier),
+            rest
+        ))
+    }
+}
+
+/// The install strategies known for a game
+pub struct StrategySet {
+    game: String,
+    strategies: Vec<Box<dyn InstallStrategy>>,
+}
+
+impl StrategySet {
+    /// a set for ``game`` containing only the [`DEFAULT_STRATEGY`]
+    pub fn new(game: &str) -> Self {
+        let mut set = Self {
+            game: game.to_string(),
+            strategies: Vec::new(),
+        };
+        set.add(PrefixStrategy::new(
+            DEFAULT_STRATEGY,
+            "copy the whole package to the game directory",
+            "",
+            "",
+        ));
+        set
+    }
+
+    /// the strategies for Cyberpunk 2077 and its usual modding frameworks
+    pub fn cyberpunk_2077() -> Self {
+        let mut set = Self::new("Cyberpunk 2077");
+        set.add(PrefixStrategy::new(
+            "archive",
+            "copy the archive/pc/mod folder to the game directory",
+            "archive/pc/mod",
+            "archive/pc/mod",
+        ));
+        set.add(PrefixStrategy::new(
+            "redscript",
+            "merge the r6/scripts folder into the one of the game",
+            "r6/scripts",
+            "r6/scripts",
+        ));
+        set.add(PrefixStrategy::new(
+            "cet",
+            "install the package as a Cyber Engine Tweaks mod folder",
+            "",
+            "bin/x64/plugins/cyber_engine_tweaks/mods/{identifier}",
+        ));
+        set
+    }
+
+    pub fn game(&self) -> &str {
+        &self.game
+    }
+
+    /// add a strategy to this set, replacing the one with the same name
+    pub fn add<S: InstallStrategy + 'static>(&mut self, strategy: S) {
+        self.strategies
+            .retain(|existing| existing.name() != strategy.name());
+        self.strategies.push(Box::new(strategy));
+    }
+
+    pub fn get(&self, name: &str) -> Option<&dyn InstallStrategy> {
+        self.strategies
+            .iter()
+            .find(|strategy| strategy.name() == name)
+            .map(|strategy| strategy.as_ref())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &dyn InstallStrategy> {
+        self.strategies.iter().map(|strategy| strategy.as_ref())
+    }
+
+    fn get_or_error(&self, name: &str) -> Result<&dyn InstallStrategy, InstallStrategyError> {
+        self.get(name).ok_or_else(|| {
+            InstallStrategyError::UnknownStrategyError(
+                name.to_string(),
+                self.game.clone(),
+                format_str_id_list(&self.iter().map(|s| s.name()).collect::<Vec<_>>()),
+            )
+        })
+    }
+
+    /// return an error if one of the strategies named ``names`` isn't in this set
+    pub fn check<S: AsRef<str>>(&self, names: &[S]) -> Result<(), InstallStrategyError> {
+        for name in names {
+            self.get_or_error(name.as_ref())?;
+        }
+        Ok(())
+    }
+
+    /// the path, relative to the game directory, where the file at ``relative_path`` in the
+    /// package ``identifier`` with the strategies ``names`` should be installed, or None if it
+    /// shouldn't be installed.
+    pub fn target<S: AsRef<str>>(
+        &self,
+        identifier: &str,
+        names: &[S],
+        relative_path: &str,
+    ) -> Result<Option<String>, InstallStrategyError> {
+        if names.is_empty() {
+            return Ok(self
+                .get_or_error(DEFAULT_STRATEGY)?
+                .target(identifier, relative_path));
+        };
+        for name in names {
+            if let Some(target) = self
+                .get_or_error(name.as_ref())?
+                .target(identifier, relative_path)
+            {
+                return Ok(Some(target));
+            };
+        }
+        Ok(None)
+    }
+}
+
+impl Default for StrategySet {
+    fn default() -> Self {
+        Self::cyberpunk_2077()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::install_strategy::{InstallStrategyError, StrategySet};
+
+    #[test]
+    fn test_strategy_target() {
+        let set = StrategySet::cyberpunk_2077();
+        let none: &[&str] = &[];
+        assert_eq!(
+            set.target("my_mod", none, "subfolder/file.txt").unwrap(),
+            Some("subfolder/file.txt".to_string())
+        );
+        let strategies = ["archive", "cet"];
+        assert_eq!(
+            set.target("my_mod", &strategies, "archive/pc/mod/my_mod.archive")
+                .unwrap(),
+            Some("archive/pc/mod/my_mod.archive".to_string())
+        );
+        assert_eq!(
+            set.target("my_mod", &strategies, "init.lua").unwrap(),
+            Some("bin/x64/plugins/cyber_engine_tweaks/mods/my_mod/init.lua".to_string())
+        );
+        assert_eq!(
+            set.target("my_mod", &["redscript"], "readme.md").unwrap(),
+            None
+        );
+        assert!(matches!(
+            set.check(&["archive", "unknown"]),
+            Err(InstallStrategyError::UnknownStrategyError(_, _, _))
+        ));
+    }
+}
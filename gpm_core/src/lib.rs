@@ -1,11 +1,35 @@
+pub mod archive;
+pub mod checksum;
+pub mod conflict;
+pub mod deploy;
 pub mod display;
+pub mod import;
+pub mod info;
+pub mod install;
+pub mod install_strategy;
+pub mod license;
 pub mod lockfile;
 pub mod package;
+pub mod package_reader;
 pub mod package_writer;
+pub mod profile;
+pub mod report;
+pub mod repository;
+pub mod resolver;
+pub mod signature;
 pub mod store_project;
+pub mod validate;
+
+#[cfg(test)]
+mod test_utils;
+
+pub use semver;
 
 pub mod constants {
     pub const TOML_CONFIG_PATH: &str = "config.toml";
     pub const JSON_CONFIG_PATH: &str = "config.json";
     pub const IGNORE_PATH: &str = ".modignore";
+    pub const REPOSITORY_INDEX_PATH: &str = "index.toml";
+    pub const CHECKSUMS_PATH: &str = "checksums.json";
+    pub const SIGNATURE_PATH: &str = "signature.json";
 }
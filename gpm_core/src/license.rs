@@ -0,0 +1,303 @@
+//! Parse the licenses of packages as SPDX license expressions, and check that the packages of a
+//! profile can be used, and shared, together.
+//!
+//! What is checked is configured by a [`LicensePolicy`], stored in the
+//! [`PROFILE_LICENSE_POLICY_PATH`] file of a profile. Licenses that aren't in the SPDX list can
+//! be written as `LicenseRef-<name>`, like `LicenseRef-NoRedistribution`.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::package::PackageInformation;
+use crate::profile::Profile;
+
+use serde::{Deserialize, Serialize};
+use spdx::{Expression, LicenseItem, LicenseReq};
+
+/// the file, in the profile folder, containing the [`LicensePolicy`] of the profile
+pub const PROFILE_LICENSE_POLICY_PATH: &str = "license_policy.toml";
+
+#[derive(thiserror::Error, Debug)]
+pub enum LicenseError {
+    #[error("io error with the file {0}")]
+    FileIOError(PathBuf, #[source] io::Error),
+    #[error("error while parsing the license policy file {0}")]
+    TomlDecodeError(PathBuf, #[source] toml::de::Error),
+    #[error("{0:?} isn't a SPDX license expression (like MIT or GPL-3.0-or-later): {1}")]
+    NotSpdxError(String, String), //license, reason
+}
+
+/// parse ``license`` as a SPDX license expression
+pub fn parse_license(license: &str) -> Result<Expression, LicenseError> {
+    Expression::parse(license)
+        .map_err(|err| LicenseError::NotSpdxError(license.to_string(), err.reason.to_string()))
+}
+
+/// the name of a license, without its `+` or exception, as written in a [`LicensePolicy`]
+fn license_name(requirement: &LicenseReq) -> String {
+    match &requirement.license {
+        LicenseItem::Spdx { id, .. } => id.name.to_string(),
+        other => other.to_string(),
+    }
+}
+
+fn contains_license(licenses: &[String], requirement: &LicenseReq) -> bool {
+    let name = license_name(requirement);
+    licenses
+        .iter()
+        .any(|license| license.eq_ignore_ascii_case(&name))
+}
+
+/// What the license report of a profile check
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct LicensePolicy {
+    /// the licenses that don't allow redistributing the mod, as SPDX identifiers or
+    /// `LicenseRef-<name>`. A package can't be part of a redistributed modpack if its license
+    /// expression can't be satisfied without one of them.
+    #[serde(default)]
+    pub no_redistribution: Vec<String>,
+    /// the pairs of licenses whose packages can't be used together
+    #[serde(default)]
+    pub incompatible: Vec<(String, String)>,
+}
+
+impl Default for LicensePolicy {
+    fn default() -> Self {
+        Self {
+            no_redistribution: vec![
+                "LicenseRef-NoRedistribution".to_string(),
+                "LicenseRef-AllRightsReserved".to_string(),
+            ],
+            incompatible: Vec::new(),
+        }
+    }
+}
+
+impl LicensePolicy {
+    /// load the policy of ``profile``. A profile without policy file use the default policy.
+    pub fn load(profile: &Profile) -> Result<Self, LicenseError> {
+        Self::load_file(&profile.path.join(PROFILE_LICENSE_POLICY_PATH))
+    }
+
+    /// load the policy in the file at ``path``, or the default policy if it doesn't exist
+    pub fn load_file(path: &Path) -> Result<Self, LicenseError> {
+        match fs::read(path) {
+            Ok(content) => toml::from_slice(&content)
+                .map_err(|err| LicenseError::TomlDecodeError(path.to_path_buf(), err)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(LicenseError::FileIOError(path.to_path_buf(), err)),
+        }
+    }
+
+    /// return true if a package licensed under ``expression`` can be redistributed
+    pub fn allow_redistribution(&self, expression: &Expression) -> bool {
+        expression.evaluate(|requirement| !contains_license(&self.no_redistribution, requirement))
+    }
+}
+
+/// return true if a package licensed under ``expression`` can't be used without ``license``
+fn require_license(expression: &Expression, license: &str) -> bool {
+    !expression.evaluate(|requirement| !contains_license(&[license.to_string()], requirement))
+}
+
+/// A problem found by [`license_report`]
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum LicenseIssue {
+    /// the package doesn't have a license
+    Missing { identifier: String },
+    /// the license of the package isn't a SPDX expression, and can't be checked
+    NotSpdx { identifier: String, license: String },
+    /// the package can't be redistributed, but the profile is
+    NoRedistribution { identifier: String, license: String },
+    /// the two packages have licenses the policy mark as incompatible
+    Incompatible {
+        first: String,
+        first_license: String,
+        second: String,
+        second_license: String,
+    },
+}
+
+impl fmt::Display for LicenseIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Missing { identifier } => write!(f, "{} doesn't have a license", identifier),
+            Self::NotSpdx {
+                identifier,
+                license,
+            } => write!(
+                f,
+                "the license {:?} of {} isn't a SPDX license expression, it can't be checked",
+                license, identifier
+            ),
+            Self::NoRedistribution {
+                identifier,
+                license,
+            } => write!(
+                f,
+                "{} can't be redistributed, its license is {}",
+                identifier, license
+            ),
+            Self::Incompatible {
+                first,
+                first_license,
+                second,
+                second_license,
+            } => write!(
+                f,
+                "{} ({}) and {} ({}) have incompatible licenses",
+                first, first_license, second, second_license
+            ),
+        }
+    }
+}
+
+/// The licenses of a set of packages
+#[derive(Serialize, Debug, Clone, PartialEq, Default)]
+pub struct LicenseReport {
+    /// the identifiers of the packages, by license. Packages without license aren't listed.
+    pub licenses: BTreeMap<String, Vec<String>>,
+    pub issues: Vec<LicenseIssue>,
+}
+
+/// aggregate the licenses of ``packages``, and check them against ``policy``. If
+/// ``redistributed`` is true, the packages are shared together, like in a modpack, so they must
+/// allow redistribution.
+pub fn license_report<'a, I: IntoIterator<Item = &'a PackageInformation>>(
+    packages: I,
+    policy: &LicensePolicy,
+    redistributed: bool,
+) -> LicenseReport {
+    let mut report = LicenseReport::default();
+    let mut parsed: Vec<(String, String, Expression)> = Vec::new();
+    for information in packages {
+        let identifier = information.identifier.clone().unwrap_or_default();
+        let license = match &information.license {
+            Some(license) => license.clone(),
+            None => {
+                report.issues.push(LicenseIssue::Missing { identifier });
+                continue;
+            }
+        };
+        report
+            .licenses
+            .entry(license.clone())
+            .or_default()
+            .push(identifier.clone());
+        match parse_license(&license) {
+            Ok(expression) => {
+                if redistributed && !policy.allow_redistribution(&expression) {
+                    report.issues.push(LicenseIssue::NoRedistribution {
+                        identifier: identifier.clone(),
+                        license: license.clone(),
+                    });
+                };
+                parsed.push((identifier, license, expression));
+            }
+            Err(_) => report.issues.push(LicenseIssue::NotSpdx {
+                identifier,
+                license,
+            }),
+        };
+    }
+
+    for (first_license_name, second_license_name) in &policy.incompatible {
+        for (first, first_license, first_expression) in &parsed {
+            if !require_license(first_expression, first_license_name) {
+                continue;
+            };
+            for (second, second_license, second_expression) in &parsed {
+                if first != second && require_license(second_expression, second_license_name) {
+                    report.issues.push(LicenseIssue::Incompatible {
+                        first: first.clone(),
+                        first_license: first_license.clone(),
+                        second: second.clone(),
+                        second_license: second_license.clone(),
+                    });
+                };
+            }
+        }
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::license::{license_report, parse_license, LicenseIssue, LicensePolicy};
+    use crate::package::PackageInformation;
+    use semver::Version;
+
+    fn package(identifier: &str, license: &str) -> PackageInformation {
+        PackageInformation::new(
+            "modder",
+            identifier,
+            Version::new(1, 0, 0),
+            identifier,
+            "a test mod",
+            license,
+        )
+    }
+
+    #[test]
+    fn test_parse_license() {
+        assert!(parse_license("MIT").is_ok());
+        assert!(parse_license("GPL-3.0-or-later OR LicenseRef-Custom").is_ok());
+        assert!(parse_license("some version of AGPL").is_err());
+    }
+
+    #[test]
+    fn test_license_report() {
+        let mut policy = LicensePolicy::default();
+        policy
+            .incompatible
+            .push(("GPL-2.0-only".to_string(), "Apache-2.0".to_string()));
+        let mut missing = package("missing", "");
+        missing.license = None;
+        let packages = vec![
+            package("free", "MIT"),
+            package("dual", "MIT OR LicenseRef-NoRedistribution"),
+            package("closed", "LicenseRef-NoRedistribution"),
+            package("text", "some version of AGPL"),
+            package("gpl", "GPL-2.0-only"),
+            package("apache", "Apache-2.0"),
+            missing,
+        ];
+
+        let report = license_report(&packages, &policy, false);
+        assert_eq!(report.licenses["MIT"], vec!["free"]);
+        assert_eq!(report.licenses.len(), 6);
+        assert_eq!(
+            report.issues,
+            vec![
+                LicenseIssue::NotSpdx {
+                    identifier: "text".to_string(),
+                    license: "some version of AGPL".to_string()
+                },
+                LicenseIssue::Missing {
+                    identifier: "missing".to_string()
+                },
+                LicenseIssue::Incompatible {
+                    first: "gpl".to_string(),
+                    first_license: "GPL-2.0-only".to_string(),
+                    second: "apache".to_string(),
+                    second_license: "Apache-2.0".to_string()
+                },
+            ]
+        );
+
+        // the dual licensed package can be redistributed under MIT
+        let report = license_report(&packages, &policy, true);
+        assert_eq!(
+            report.issues[0],
+            LicenseIssue::NoRedistribution {
+                identifier: "closed".to_string(),
+                license: "LicenseRef-NoRedistribution".to_string()
+            }
+        );
+        assert_eq!(report.issues.len(), 4);
+    }
+}
@@ -1,4 +1,5 @@
 use anyhow::Context;
+use semver::Version;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs::File;
@@ -13,15 +14,26 @@ pub enum LockSource {
     ///
     /// Even if the parent contain the id, we still store it there, as it may be usefull that id
     /// differ from it's parent.
-    IdVersion { identifier: String, version: String },
+    IdVersion {
+        identifier: String,
+        version: Version,
+        /// the hex-encoded SHA-256 hash of the package archive
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        checksum: Option<String>,
+    },
     /// Use a specific path on the local filesystem. If relative, it'll be based around the
     /// profile folder.
-    Path { path: PathBuf },
+    Path {
+        path: PathBuf,
+        /// the hex-encoded SHA-256 hash of the package archive, if the path is an archive
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        checksum: Option<String>,
+    },
 }
 
 /// contain a fixed set of mod dependency, with each dependency having a specific version. Mod are
 /// identified by their id. They are unique.
-#[derive(Serialize, Deserialize, Default, Debug)]
+#[derive(Serialize, Deserialize, Default, Debug, Clone, PartialEq)]
 pub struct LockFile {
     pub dependencies: HashMap<String, LockSource>,
 }
@@ -82,28 +94,30 @@ impl LockFile {
     pub fn load_file(path: &Path) -> anyhow::Result<Self> {
         let mut file =
             File::open(path).with_context(|| format!("can't open the lock file at {:?}", &path))?;
-        Ok(Self::load_reader(&mut file)
-            .with_context(|| format!("can't load the TOML lock file at {:?}", &path))?)
+        Self::load_reader(&mut file)
+            .with_context(|| format!("can't load the TOML lock file at {:?}", &path))
     }
 
     /// write this [`LockFile`] to the given file
     pub fn write_file(&self, path: &Path) -> anyhow::Result<()> {
-        let mut file = File::create(&path)
+        let mut file = File::create(path)
             .with_context(|| format!("can't create the lock file at {:?}", &path))?;
-        Ok(Self::write_writer(&self, &mut file)
-            .with_context(|| format!("can't write the TOML lock file at {:?}", &path))?)
+        Self::write_writer(self, &mut file)
+            .with_context(|| format!("can't write the TOML lock file at {:?}", &path))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::lockfile::{LockFile, LockSource};
+    use semver::Version;
 
     #[test]
     fn test_lock_file() {
         let package1_source = LockSource::IdVersion {
             identifier: "package1_bis".into(),
-            version: "1.0.0".into(),
+            version: Version::new(1, 0, 0),
+            checksum: None,
         };
         let mut lock_file = LockFile::new();
         assert!(lock_file.dependency_source("package1").is_none());
@@ -1,5 +1,9 @@
-/// Code below is used to represent a package that can be downloaded, installed,
-/// or created by the user and published to the store.
+//! Code below is used to represent a package that can be downloaded, installed,
+//! or created by the user and published to the store.
+
+use crate::store_project::{init_project, InitProjectError};
+use semver::{Version, VersionReq};
+use std::path::{Path, PathBuf};
 
 pub struct PackageInformationExtraData {
     pub key: String,
@@ -15,10 +19,45 @@ impl PackageInformationExtraData {
     }
 }
 
+/// Where a dependency should be fetched from
+#[derive(Debug, Clone, PartialEq)]
+pub enum DependencySource {
+    /// look for the dependency identifier in the configured package repositories
+    Repository,
+    /// use the mod project or archive at the given path. If relative, it is based around the
+    /// project folder.
+    Path(PathBuf),
+    /// download the archive at the given URL
+    Url(String),
+}
+
+/// A mod required (or optionally used) by a package
+#[derive(Debug, Clone, PartialEq)]
+pub struct PackageDependency {
+    pub identifier: String,
+    /// the versions of the dependency that are compatible with this package
+    pub version: VersionReq,
+    /// if true, the package works without this dependency, but will use it if it's installed
+    pub optional: bool,
+    pub source: DependencySource,
+}
+
+impl PackageDependency {
+    /// create a new required dependency, looked for in the package repositories
+    pub fn new(identifier: &str, version: VersionReq) -> PackageDependency {
+        PackageDependency {
+            identifier: identifier.to_owned(),
+            version,
+            optional: false,
+            source: DependencySource::Repository,
+        }
+    }
+}
+
 pub struct RequiredPublishInformation {
     pub creator: String,
     pub identifier: String,
-    pub version: String,
+    pub version: Version,
     pub display_name: String,
     pub description: String,
     pub license: String,
@@ -31,16 +70,23 @@ pub struct PackageInformation {
     // those
     pub creator: Option<String>,
     pub identifier: Option<String>,
-    pub version: Option<String>,
+    pub version: Option<Version>,
     pub display_name: Option<String>,
     pub description: Option<String>,
     pub license: Option<String>,
 
     // optional values
     pub website_url: Option<String>,
-    pub dependencies: Vec<String>,
+    pub dependencies: Vec<PackageDependency>,
     pub tags: Vec<String>,
     pub install_strategies: Vec<String>,
+    /// gitignore-style patterns of the files stored without compression in the package archive,
+    /// in addition to the file types of [`crate::package_writer::STORED_EXTENSIONS`]
+    pub uncompressed: Vec<String>,
+    /// gitignore-style patterns of the files to package, even if an ignore file exclude them
+    pub include: Vec<String>,
+    /// gitignore-style patterns of the files not to package, in addition to the ignore files
+    pub exclude: Vec<String>,
     pub extra_data: Vec<PackageInformationExtraData>,
 }
 
@@ -48,7 +94,7 @@ impl PackageInformation {
     pub fn new(
         creator: &str,
         identifier: &str,
-        version: &str,
+        version: Version,
         display_name: &str,
         description: &str,
         license: &str,
@@ -56,7 +102,7 @@ impl PackageInformation {
         PackageInformation {
             creator: Some(creator.to_owned()),
             identifier: Some(identifier.to_owned()),
-            version: Some(version.to_owned()),
+            version: Some(version),
             display_name: Some(display_name.to_owned()),
             description: Some(description.to_owned()),
             license: Some(license.to_owned()),
@@ -65,6 +111,9 @@ impl PackageInformation {
             dependencies: Vec::new(),
             tags: Vec::new(),
             install_strategies: Vec::new(),
+            uncompressed: Vec::new(),
+            include: Vec::new(),
+            exclude: Vec::new(),
             extra_data: Vec::new(),
         }
     }
@@ -118,8 +167,13 @@ impl Package {
         }
     }
 
-    pub fn init_project_directory() -> Result<(), anyhow::Error> {
-        Ok(())
+    /// create a new mod project in ``project_path`` for this package. See [`init_project`].
+    pub fn init_project_directory(
+        &self,
+        project_path: &Path,
+        force: bool,
+    ) -> Result<(), InitProjectError> {
+        init_project(project_path, &self.information, force)
     }
 
     pub fn publish() -> Result<(), anyhow::Error> {
@@ -130,13 +184,14 @@ impl Package {
 #[cfg(test)]
 mod tests {
     use crate::package::PackageInformation;
+    use semver::Version;
 
     #[test]
     fn test_publish_field() {
         let mut package_information = PackageInformation::new(
             "a creator",
             "an_identifier",
-            "1.2.3",
+            Version::new(1, 2, 3),
             "A Display Name",
             "the description of this is a description",
             "some version of AGPL",
@@ -145,7 +200,7 @@ mod tests {
         assert!(package_information.missing_publish_field().is_empty());
         let required_publish_information =
             package_information.required_publish_information().unwrap();
-        assert_eq!(&required_publish_information.version, "1.2.3");
+        assert_eq!(required_publish_information.version, Version::new(1, 2, 3));
 
         package_information.version = None;
         package_information.display_name = None;
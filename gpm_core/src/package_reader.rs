@@ -0,0 +1,517 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io;
+use std::io::{BufReader, Read, Seek};
+use std::path::{Component, Path, PathBuf};
+
+use crate::archive::{ArchiveError, ArchiveFormat, ArchiveReader, EntryKind};
+use crate::checksum::HashingWriter;
+use crate::constants::{CHECKSUMS_PATH, JSON_CONFIG_PATH, SIGNATURE_PATH};
+use crate::package::{Package, PackageInformation};
+use crate::signature::{signed_message, PackageSignature, SignatureStatus, TrustedKeys};
+use crate::store_project::load_package_from_json;
+
+#[derive(thiserror::Error, Debug)]
+pub enum ReadPackageError {
+    #[error("io error with the file {0}")]
+    FileIOError(PathBuf, #[source] io::Error),
+    #[error("error while reading the archive")]
+    ArchiveError(#[from] ArchiveError),
+    #[error(
+        "the archive doesn't contain a {} file, it is probably not a mod package",
+        JSON_CONFIG_PATH
+    )]
+    MissingConfigError,
+    #[error("can't parse the {} file of the archive", JSON_CONFIG_PATH)]
+    DecodeJsonError(#[source] serde_json::error::Error),
+    #[error("the archive entry {0:?} would be extracted outside of the target directory")]
+    UnsafePathError(String),
+    #[error("the archive entry {0:?} is a link, which isn't allowed in a package")]
+    SymlinkError(String),
+    #[error("the archive entry {0:?} is a special file, which isn't allowed in a package")]
+    SpecialFileError(String),
+    #[error("the archive contains the entry {0:?} more than once")]
+    DuplicateEntryError(String),
+    #[error("can't parse the {} file of the archive", CHECKSUMS_PATH)]
+    DecodeChecksumsError(#[source] serde_json::error::Error),
+    #[error("can't parse the {} file of the archive", SIGNATURE_PATH)]
+    DecodeSignatureError(#[source] serde_json::error::Error),
+    #[error("the archive entry {0:?} doesn't match its checksum, the archive may have been modified or corrupted")]
+    ChecksumMismatchError(String),
+    #[error("the archive entry {0:?} doesn't have a checksum")]
+    MissingChecksumError(String),
+    #[error(
+        "the file {0:?} listed in the checksums is missing from the archive, it may be incomplete"
+    )]
+    MissingFileError(String),
+}
+
+/// An entry of a package archive, as listed by [`PackageReader::entries`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct PackageEntry {
+    /// the path of the entry, relative to the root of the package
+    pub path: PathBuf,
+    /// the uncompressed size of the entry, in bytes
+    pub size: u64,
+    pub is_dir: bool,
+}
+
+/// Read a package archive, as produced by [`crate::package_writer::create_package`], in any
+/// [`ArchiveFormat`].
+///
+/// Archives of existing mods, without the files added by gpm, can be read with
+/// [`Self::with_information`].
+pub struct PackageReader<R: Read + Seek> {
+    archive: ArchiveReader<R>,
+    package: Package,
+    config_content: Vec<u8>,
+    checksums: Option<BTreeMap<String, String>>,
+    checksums_content: Option<Vec<u8>>,
+    signature: Option<PackageSignature>,
+    /// true for archives not created by gpm, whose every entry is part of the package
+    legacy: bool,
+}
+
+impl PackageReader<BufReader<File>> {
+    /// open the package archive at the given path
+    pub fn open(path: &Path) -> Result<Self, ReadPackageError> {
+        let file = File::open(path)
+            .map_err(|err| ReadPackageError::FileIOError(path.to_path_buf(), err))?;
+        Self::new(BufReader::new(file))
+    }
+}
+
+impl<R: Read + Seek> PackageReader<R> {
+    /// read the package archive from the given stream, and parse its embedded
+    /// [`JSON_CONFIG_PATH`] file.
+    pub fn new(reader: R) -> Result<Self, ReadPackageError> {
+        let mut archive = ArchiveReader::new(reader)?;
+        let mut files = archive.read_files(&[JSON_CONFIG_PATH, CHECKSUMS_PATH, SIGNATURE_PATH])?;
+
+        let config_content = files
+            .remove(JSON_CONFIG_PATH)
+            .ok_or(ReadPackageError::MissingConfigError)?;
+        let package =
+            load_package_from_json(&config_content).map_err(ReadPackageError::DecodeJsonError)?;
+
+        // archives created before checksums were introduced don't have this file
+        let checksums_content = files.remove(CHECKSUMS_PATH);
+        let checksums = checksums_content
+            .as_ref()
+            .map(|content| serde_json::from_slice(content))
+            .transpose()
+            .map_err(ReadPackageError::DecodeChecksumsError)?;
+        let signature = files
+            .remove(SIGNATURE_PATH)
+            .map(|content| serde_json::from_slice(&content))
+            .transpose()
+            .map_err(ReadPackageError::DecodeSignatureError)?;
+
+        Ok(Self {
+            archive,
+            package,
+            config_content,
+            checksums,
+            checksums_content,
+            signature,
+            legacy: false,
+        })
+    }
+
+    /// read an archive of an existing mod, not created by gpm, from the given stream. The
+    /// package is described by ``information``, and every entry of the archive, including a
+    /// [`JSON_CONFIG_PATH`] file, is part of it. The archive has neither checksums nor
+    /// signature.
+    pub fn with_information(
+        reader: R,
+        information: PackageInformation,
+    ) -> Result<Self, ReadPackageError> {
+        Ok(Self {
+            archive: ArchiveReader::new(reader)?,
+            package: Package::new(information),
+            config_content: Vec::new(),
+            checksums: None,
+            checksums_content: None,
+            signature: None,
+            legacy: true,
+        })
+    }
+
+    /// the format of the archive
+    pub fn format(&self) -> ArchiveFormat {
+        self.archive.format()
+    }
+
+    /// the package described by the [`JSON_CONFIG_PATH`] file of this archive
+    pub fn package(&self) -> &Package {
+        &self.package
+    }
+
+    /// the SHA-256 hash of each file of the archive, by entry name, as stored in its
+    /// [`CHECKSUMS_PATH`] file. None for archives without this file.
+    pub fn checksums(&self) -> Option<&BTreeMap<String, String>> {
+        self.checksums.as_ref()
+    }
+
+    /// the signature of the archive, if it is signed
+    pub fn signature(&self) -> Option<&PackageSignature> {
+        self.signature.as_ref()
+    }
+
+    /// check the signature of this archive against ``trusted_keys``. A signature is invalid if
+    /// the archive doesn't have checksums.
+    pub fn check_signature(&self, trusted_keys: &TrustedKeys) -> SignatureStatus {
+        match (&self.signature, &self.checksums_content) {
+            (None, _) => SignatureStatus::Unsigned,
+            (Some(_), None) => SignatureStatus::Invalid,
+            (Some(signature), Some(checksums_content)) => trusted_keys.check(
+                Some(signature),
+                &signed_message(&self.config_content, checksums_content),
+            ),
+        }
+    }
+
+    /// consume this reader, returning the package described by the archive
+    pub fn into_package(self) -> Package {
+        self.package
+    }
+
+    /// list the content of the archive, except the [`JSON_CONFIG_PATH`], [`CHECKSUMS_PATH`] and
+    /// [`SIGNATURE_PATH`] files.
+    ///
+    /// return an error if one of the entry would be unsafe to extract, or if two entries have the
+    /// same path, as one would hide the other. The entries are in the order of the archive.
+    pub fn entries(&mut self) -> Result<Vec<PackageEntry>, ReadPackageError> {
+        Ok(self
+            .named_entries()?
+            .into_iter()
+            .map(|(_, entry)| entry)
+            .collect())
+    }
+
+    /// same as [`Self::entries`], along with the name of each entry in the archive
+    fn named_entries(&mut self) -> Result<Vec<(String, PackageEntry)>, ReadPackageError> {
+        let mut entries = Vec::new();
+        let mut paths = BTreeMap::new();
+        for entry in self.archive.entries()? {
+            // older archives, and some other tools, add an entry for the root directory
+            if matches!(entry.name.as_str(), "" | "/" | "./")
+                || (!self.legacy
+                    && (entry.name == JSON_CONFIG_PATH
+                        || entry.name == CHECKSUMS_PATH
+                        || entry.name == SIGNATURE_PATH))
+            {
+                continue;
+            };
+            match entry.kind {
+                EntryKind::Link => return Err(ReadPackageError::SymlinkError(entry.name)),
+                EntryKind::Other => return Err(ReadPackageError::SpecialFileError(entry.name)),
+                EntryKind::File | EntryKind::Directory => (),
+            };
+            let path = safe_relative_path(&entry.name)
+                .ok_or_else(|| ReadPackageError::UnsafePathError(entry.name.clone()))?;
+            let is_dir = entry.kind == EntryKind::Directory;
+            // the same directory can be listed twice without harm, unlike a file
+            match paths.insert(path.clone(), is_dir) {
+                Some(true) if is_dir => continue,
+                Some(_) => return Err(ReadPackageError::DuplicateEntryError(entry.name)),
+                None => (),
+            };
+            entries.push((
+                entry.name,
+                PackageEntry {
+                    path,
+                    size: entry.size,
+                    is_dir,
+                },
+            ));
+        }
+        Ok(entries)
+    }
+
+    /// extract the content of the archive (except the files excluded by [`Self::entries`]) into
+    /// ``target_dir``, creating it if needed.
+    ///
+    /// Every entry is checked before anything is written, so an archive containing an absolute
+    /// path, a path escaping ``target_dir`` or a link is refused as a whole. If the archive has
+    /// checksums, each extracted file is checked against its checksum.
+    pub fn extract(&mut self, target_dir: &Path) -> Result<(), ReadPackageError> {
+        let entries: BTreeMap<String, PackageEntry> = self.named_entries()?.into_iter().collect();
+        if let Some(checksums) = &self.checksums {
+            for name in checksums.keys() {
+                if !entries.contains_key(name) {
+                    return Err(ReadPackageError::MissingFileError(name.clone()));
+                };
+            }
+        };
+
+        std::fs::create_dir_all(target_dir)
+            .map_err(|err| ReadPackageError::FileIOError(target_dir.to_path_buf(), err))?;
+
+        let checksums = &self.checksums;
+        self.archive.visit(|archive_entry, content| {
+            let entry = match entries.get(&archive_entry.name) {
+                Some(entry) => entry,
+                None => return Ok(()),
+            };
+            let destination = target_dir.join(&entry.path);
+            if entry.is_dir {
+                return std::fs::create_dir_all(&destination)
+                    .map_err(|err| ReadPackageError::FileIOError(destination.clone(), err));
+            };
+            if let Some(parent) = destination.parent() {
+                std::fs::create_dir_all(parent)
+                    .map_err(|err| ReadPackageError::FileIOError(parent.to_path_buf(), err))?;
+            };
+            let expected_checksum = match checksums {
+                Some(checksums) => Some(checksums.get(&archive_entry.name).ok_or_else(|| {
+                    ReadPackageError::MissingChecksumError(archive_entry.name.clone())
+                })?),
+                None => None,
+            };
+            let mut destination_file = HashingWriter::new(
+                File::create(&destination)
+                    .map_err(|err| ReadPackageError::FileIOError(destination.clone(), err))?,
+            );
+            io::copy(content, &mut destination_file)
+                .map_err(|err| ReadPackageError::FileIOError(destination.clone(), err))?;
+            let (_, checksum) = destination_file.finish();
+            if expected_checksum.is_some_and(|expected| expected != &checksum) {
+                return Err(ReadPackageError::ChecksumMismatchError(
+                    archive_entry.name.clone(),
+                ));
+            };
+            Ok(())
+        })
+    }
+}
+
+/// convert the name of an archive entry into a relative path, or return None if it is absolute,
+/// contain a parent directory component or otherwise could point outside of the extraction
+/// directory. Both `/` and `\` are considered separators.
+pub fn safe_relative_path(name: &str) -> Option<PathBuf> {
+    if name.contains('\0') {
+        return None;
+    };
+    let normalized = name.replace('\\', "/");
+    if normalized.starts_with('/') {
+        return None;
+    };
+    let mut result = PathBuf::new();
+    for component in Path::new(&normalized).components() {
+        match component {
+            Component::Normal(part) => {
+                // reject windows drive letter and alternate data stream
+                if part.to_string_lossy().contains(':') {
+                    return None;
+                };
+                result.push(part)
+            }
+            Component::CurDir => (),
+            Component::ParentDir | Component::RootDir | Component::Prefix(_) => return None,
+        }
+    }
+    if result.as_os_str().is_empty() {
+        None
+    } else {
+        Some(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::archive::ArchiveFormat;
+    use crate::checksum::sha256_bytes;
+    use crate::install_strategy::StrategySet;
+    use crate::package::PackageInformation;
+    use crate::package_reader::{safe_relative_path, PackageReader, ReadPackageError};
+    use crate::package_writer::{create_package, PackageOptions};
+    use crate::test_utils::test_mod_path;
+    use semver::Version;
+    use std::io::{Cursor, Write};
+    use std::path::{Path, PathBuf};
+    use zip::write::{FileOptions, ZipWriter};
+
+    #[test]
+    fn test_read_package() {
+        let mut buffer = Cursor::new(Vec::new());
+        create_package(
+            &test_mod_path(),
+            &mut buffer,
+            &PackageOptions::new(&StrategySet::default()),
+        )
+        .unwrap();
+
+        let mut reader = PackageReader::new(buffer).unwrap();
+        assert_eq!(
+            reader.package().information.identifier.as_deref(),
+            Some("test_mod")
+        );
+        let entries = reader.entries().unwrap();
+        assert!(entries
+            .iter()
+            .any(|entry| entry.path == Path::new("another_file.txt") && !entry.is_dir));
+        assert!(!entries
+            .iter()
+            .any(|entry| entry.path == Path::new("ignored.txt")));
+
+        let work_dir = tempfile::tempdir().unwrap();
+        let target = work_dir.path().join("extracted");
+        reader.extract(&target).unwrap();
+        assert!(target.join("subfolder").join("file.arbitrary").is_file());
+        assert!(!target.join("config.json").exists());
+    }
+
+    #[test]
+    fn test_reject_unsafe_path() {
+        for name in &[
+            "../evil.txt",
+            "/etc/evil",
+            "a/../../evil",
+            "C:\\evil",
+            "..\\evil",
+        ] {
+            let mut buffer = Cursor::new(Vec::new());
+            let mut zip = ZipWriter::new(&mut buffer);
+            zip.start_file("config.json", FileOptions::default())
+                .unwrap();
+            zip.write_all(b"{}").unwrap();
+            zip.start_file(*name, FileOptions::default()).unwrap();
+            zip.write_all(b"evil").unwrap();
+            zip.finish().unwrap();
+            drop(zip);
+
+            let mut reader = PackageReader::new(buffer).unwrap();
+            let target = tempfile::tempdir().unwrap();
+            assert!(matches!(
+                reader.extract(target.path()),
+                Err(ReadPackageError::UnsafePathError(_))
+            ));
+        }
+        assert_eq!(safe_relative_path("./a/b"), Some(PathBuf::from("a/b")));
+    }
+
+    #[test]
+    fn test_checksum_mismatch() {
+        let mut buffer = Cursor::new(Vec::new());
+        let mut zip = ZipWriter::new(&mut buffer);
+        zip.start_file("config.json", FileOptions::default())
+            .unwrap();
+        zip.write_all(b"{}").unwrap();
+        zip.start_file("checksums.json", FileOptions::default())
+            .unwrap();
+        zip.write_all(format!("{{\"file.txt\": \"{}\"}}", sha256_bytes(b"original")).as_bytes())
+            .unwrap();
+        zip.start_file("file.txt", FileOptions::default()).unwrap();
+        zip.write_all(b"tampered").unwrap();
+        zip.finish().unwrap();
+        drop(zip);
+
+        let mut reader = PackageReader::new(buffer).unwrap();
+        assert!(reader.checksums().unwrap().contains_key("file.txt"));
+        let target = tempfile::tempdir().unwrap();
+        assert!(matches!(
+            reader.extract(target.path()),
+            Err(ReadPackageError::ChecksumMismatchError(_))
+        ));
+    }
+
+    #[test]
+    fn test_entries_order_and_duplicates() {
+        let archive = |names: &[&str]| {
+            let mut buffer = Cursor::new(Vec::new());
+            let mut zip = ZipWriter::new(&mut buffer);
+            zip.start_file("config.json", FileOptions::default())
+                .unwrap();
+            zip.write_all(b"{}").unwrap();
+            for name in names {
+                zip.start_file(*name, FileOptions::default()).unwrap();
+                zip.write_all(name.as_bytes()).unwrap();
+            }
+            zip.finish().unwrap();
+            drop(zip);
+            PackageReader::new(buffer).unwrap()
+        };
+
+        let paths = archive(&["b.txt", "a.txt"])
+            .entries()
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.path)
+            .collect::<Vec<_>>();
+        assert_eq!(paths, vec![PathBuf::from("b.txt"), PathBuf::from("a.txt")]);
+        for names in [&["a.txt", "a.txt"][..], &["a.txt", "./a.txt"][..]] {
+            let mut reader = archive(names);
+            assert!(matches!(
+                reader.entries(),
+                Err(ReadPackageError::DuplicateEntryError(_))
+            ));
+            let target = tempfile::tempdir().unwrap();
+            assert!(matches!(
+                reader.extract(target.path()),
+                Err(ReadPackageError::DuplicateEntryError(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_missing_config() {
+        let mut buffer = Cursor::new(Vec::new());
+        ZipWriter::new(&mut buffer).finish().unwrap();
+        assert!(matches!(
+            PackageReader::new(buffer),
+            Err(ReadPackageError::MissingConfigError)
+        ));
+    }
+
+    #[test]
+    fn test_legacy_archive() {
+        let temporary_dir = tempfile::tempdir().unwrap();
+        let work_dir = temporary_dir.path();
+        let source = work_dir.join("source");
+        std::fs::create_dir_all(source.join("mods").join("legacy")).unwrap();
+        std::fs::write(source.join("mods").join("legacy").join("init.lua"), "init").unwrap();
+        // not the gpm configuration, but a file of the mod
+        std::fs::write(source.join("config.json"), "{\"option\": true}").unwrap();
+
+        let mut zip_buffer = Cursor::new(Vec::new());
+        let mut zip = ZipWriter::new(&mut zip_buffer);
+        zip.start_file("config.json", FileOptions::default())
+            .unwrap();
+        zip.write_all(b"{\"option\": true}").unwrap();
+        zip.start_file("mods/legacy/init.lua", FileOptions::default())
+            .unwrap();
+        zip.write_all(b"init").unwrap();
+        zip.finish().unwrap();
+        drop(zip);
+        let seven_z_buffer = sevenz_rust::compress(&source, Cursor::new(Vec::new())).unwrap();
+
+        for (format, buffer) in [
+            (ArchiveFormat::Zip, zip_buffer),
+            (ArchiveFormat::SevenZ, seven_z_buffer),
+        ] {
+            let information = PackageInformation::new(
+                "modder",
+                "legacy",
+                Version::new(1, 0, 0),
+                "Legacy",
+                "an existing mod",
+                "MIT",
+            );
+            let mut reader = PackageReader::with_information(buffer, information).unwrap();
+            assert_eq!(reader.format(), format);
+            assert_eq!(
+                reader.package().information.identifier.as_deref(),
+                Some("legacy")
+            );
+            let target = work_dir.join(format.name());
+            reader.extract(&target).unwrap();
+            assert_eq!(
+                std::fs::read_to_string(target.join("mods").join("legacy").join("init.lua"))
+                    .unwrap(),
+                "init"
+            );
+            assert!(target.join("config.json").is_file());
+        }
+    }
+}
@@ -1,22 +1,40 @@
-use std::fs::File;
+//! Create package archives from mod projects.
+//!
+//! Archives are reproducible: the same project always give the same bytes. Entries are written
+//! in a fixed order with normalized permissions, and all share the timestamp of
+//! [`PackageOptions::timestamp`]. The archive format is chosen with [`PackageOptions::format`].
+
+use std::collections::BTreeSet;
+use std::env;
+use std::fmt;
+use std::fs;
 use std::io;
-use std::io::{ErrorKind, Read, Seek, Write};
+use std::io::{ErrorKind, Seek, Write};
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
 
-use crate::constants::{IGNORE_PATH, JSON_CONFIG_PATH};
+use crate::archive::{ArchiveError, ArchiveFormat, SourceEntry, WriterSettings};
+use crate::constants::{
+    CHECKSUMS_PATH, IGNORE_PATH, JSON_CONFIG_PATH, SIGNATURE_PATH, TOML_CONFIG_PATH,
+};
 use crate::display::list::format_str_id_list;
+use crate::install_strategy::{InstallStrategyError, StrategySet};
+use crate::license::parse_license;
+use crate::package::PackageInformation;
+use crate::report::{Event, Reporter, SilentReporter};
+use crate::signature::{sign, signed_message};
 use crate::store_project::{
-    get_project_config_json, load_package_from_project, LoadPackageFromProjectError,
+    get_project_config_json, load_project_config, serialize_information,
+    LoadPackageFromProjectError,
 };
 
+use ed25519_dalek::SigningKey;
 use walkdir::WalkDir;
-use zip::{
-    write::{FileOptions, ZipWriter},
-    CompressionMethod,
-};
 
 use ignore;
-use ignore::gitignore::GitignoreBuilder;
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::Match;
+use serde::Serialize;
 
 #[derive(thiserror::Error, Debug)]
 pub enum CreatePackageError {
@@ -28,25 +46,641 @@ pub enum CreatePackageError {
     WalkDirError(#[from] walkdir::Error),
     #[error("error stripping a the path {0} with {1}")]
     StripPrefixError(PathBuf, PathBuf, #[source] std::path::StripPrefixError),
-    #[error("error while handling the zip file")]
-    ZipError(#[from] zip::result::ZipError),
-    #[error("error while writing to the zip file")]
-    ZipWriteError(#[source] io::Error),
+    #[error("error while writing the archive")]
+    ArchiveError(#[from] ArchiveError),
     #[error("can't generate the output json configuration file, but can parse the toml one. Probably internal error")]
     EncodeJsonError(#[source] serde_json::error::Error),
     #[error("error while handling the ignore file")] // this one should include path
     IgnoreFileError(#[from] ignore::Error),
     #[error("can't get all the required field for packaging the mod : {0}")]
     MissingPublishFieldError(String), //formatted missing field
+    #[error("the install strategies of the mod are invalid")]
+    InstallStrategyError(#[from] InstallStrategyError),
+    #[error(
+        "the {} environment variable should be a number of seconds, found {0:?}",
+        SOURCE_DATE_EPOCH_VAR
+    )]
+    InvalidSourceDateEpochError(String),
+    #[error("the link {0:?} leads to {1:?}, outside of the mod project")]
+    OutsideLinkError(PathBuf, PathBuf), //link, target
 }
 
-pub fn create_package<D: Write + Seek>(
+/// the environment variable giving the timestamp of reproducible builds, in seconds since the
+/// unix epoch
+pub const SOURCE_DATE_EPOCH_VAR: &str = "SOURCE_DATE_EPOCH";
+/// the extensions, in lowercase, of the file types that are already compressed. They are stored
+/// without compression in zip archives.
+pub const STORED_EXTENSIONS: &[&str] = &[
+    "7z", "archive", "bk2", "bz2", "gz", "jpeg", "jpg", "mp3", "mp4", "ogg", "opus", "png", "rar",
+    "webm", "webp", "wem", "xz", "zip", "zst",
+];
+
+/// How [`create_package`] build the archive
+pub struct PackageOptions<'a> {
+    /// the install strategies the package can use
+    pub strategies: &'a StrategySet,
+    /// the key to sign the package with, if any
+    pub signing_key: Option<&'a SigningKey>,
+    /// the modification time of every entry, in seconds since the unix epoch. None use the
+    /// earliest time a zip can store, 1980-01-01.
+    pub timestamp: Option<u64>,
+    /// the number of threads compressing files. The archive is the same whatever the number.
+    pub threads: usize,
+    /// the format of the archive. It must be one of [`ArchiveFormat::WRITABLE`].
+    pub format: ArchiveFormat,
+    /// where to report the files added to the archive and the warnings
+    pub reporter: &'a dyn Reporter,
+    /// how the files of the project are listed
+    pub walk: WalkOptions<'a>,
+}
+
+impl<'a> PackageOptions<'a> {
+    /// unsigned zip packages, using install strategies from ``strategies``, reporting nothing
+    pub fn new(strategies: &'a StrategySet) -> Self {
+        Self {
+            strategies,
+            signing_key: None,
+            timestamp: None,
+            threads: 1,
+            format: ArchiveFormat::default(),
+            reporter: &SilentReporter,
+            walk: WalkOptions::default(),
+        }
+    }
+}
+
+/// What to do with the links of a mod project leading outside of its directory
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LinkPolicy {
+    /// refuse to package the project
+    Error,
+    /// ignore the link
+    Skip,
+    /// package the target of the link, with a warning
+    #[default]
+    Follow,
+}
+
+impl LinkPolicy {
+    pub const NAMES: &'static [&'static str] = &["error", "skip", "follow"];
+}
+
+impl FromStr for LinkPolicy {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "error" => Ok(Self::Error),
+            "skip" => Ok(Self::Skip),
+            "follow" => Ok(Self::Follow),
+            _ => Err(format!(
+                "unknown link policy {:?}, expected error, skip or follow",
+                name
+            )),
+        }
+    }
+}
+
+/// How [`project_entries`] list the files of a mod project
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WalkOptions<'a> {
+    /// gitignore-style patterns of more files not to package. They take precedence over the
+    /// ignore rules of the project.
+    pub exclude: &'a [String],
+    /// the file the archive is written to. It is ignored if it is inside the project.
+    pub output: Option<&'a Path>,
+    /// what to do with the links leading outside of the project
+    pub links: LinkPolicy,
+}
+
+/// the timestamp given by the [`SOURCE_DATE_EPOCH_VAR`] environment variable, if set
+pub fn source_date_epoch() -> Result<Option<u64>, CreatePackageError> {
+    match env::var(SOURCE_DATE_EPOCH_VAR) {
+        Ok(value) => value
+            .trim()
+            .parse()
+            .map(Some)
+            .map_err(|_| CreatePackageError::InvalidSourceDateEpochError(value)),
+        Err(env::VarError::NotPresent) => Ok(None),
+        Err(env::VarError::NotUnicode(value)) => Err(
+            CreatePackageError::InvalidSourceDateEpochError(value.to_string_lossy().to_string()),
+        ),
+    }
+}
+
+/// the name of the archive entry for ``relative_path``, using `/` separators on every platform
+pub(crate) fn entry_name(relative_path: &Path) -> String {
+    relative_path
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// return true if the file at ``relative_path`` should be stored without compression
+fn is_stored(relative_path: &Path, uncompressed: &Gitignore) -> bool {
+    let extension = relative_path
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase());
+    extension.is_some_and(|extension| STORED_EXTENSIONS.contains(&extension.as_str()))
+        || uncompressed
+            .matched_path_or_any_parents(relative_path, false)
+            .is_ignore()
+}
+
+/// the gitignore-style patterns of the files never packaged, unless an `include` pattern of the
+/// package match them: version control data, editor files and previously built packages
+pub const DEFAULT_IGNORES: &[&str] = &[
+    ".git/",
+    ".hg/",
+    ".svn/",
+    ".idea/",
+    ".vscode/",
+    "*.swp",
+    "*.swo",
+    "*~",
+    ".DS_Store",
+    "Thumbs.db",
+    "desktop.ini",
+    "/*.zip",
+    "/*.tar.zst",
+    "/*.tar.gz",
+    "/*.7z",
+];
+
+/// Where an [`IgnoreRule`] come from
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(tag = "kind", content = "path", rename_all = "snake_case")]
+pub enum IgnoreSource {
+    /// the [`DEFAULT_IGNORES`]
+    Default,
+    /// an [`IGNORE_PATH`] file of the project, relative to the project directory. The rules of
+    /// a file only apply to its directory, and override the ones of its parent directories.
+    IgnoreFile(PathBuf),
+    /// the `include` and `exclude` patterns of the package
+    Config,
+    /// the [`WalkOptions::exclude`] patterns
+    Options,
+    /// the [`WalkOptions::output`] file, written while the project is packaged
+    Output,
+    /// a link to one of its parent directories, the pattern being the directory. It would be
+    /// followed forever.
+    SymlinkLoop,
+    /// a link leading outside of the project, skipped because of [`LinkPolicy::Skip`]. The
+    /// pattern is the target of the link.
+    OutsideLink,
+}
+
+impl fmt::Display for IgnoreSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Default => write!(f, "the default ignores"),
+            Self::IgnoreFile(path) => write!(f, "{}", path.to_string_lossy()),
+            Self::Config => write!(f, "{}", TOML_CONFIG_PATH),
+            Self::Options => write!(f, "the package options"),
+            Self::Output => write!(f, "the output archive"),
+            Self::SymlinkLoop => write!(f, "a link loop"),
+            Self::OutsideLink => write!(f, "a link outside of the project"),
+        }
+    }
+}
+
+/// A pattern matching a [`ProjectEntry`]
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct IgnoreRule {
+    /// the pattern, as written by the user
+    pub pattern: String,
+    pub source: IgnoreSource,
+    /// true for the rules keeping the entries ignored by a previous rule, like the `!pattern`
+    /// lines of ignore files and the `include` patterns
+    pub whitelist: bool,
+}
+
+impl IgnoreRule {
+    /// a rule not written by the user
+    fn builtin(source: IgnoreSource, path: &Path) -> Self {
+        let pattern = match path.to_string_lossy() {
+            pattern if pattern.is_empty() => ".".to_string(),
+            pattern => pattern.to_string(),
+        };
+        Self {
+            pattern,
+            source,
+            whitelist: false,
+        }
+    }
+}
+
+impl fmt::Display for IgnoreRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.source {
+            IgnoreSource::Output => write!(f, "{}", self.source),
+            IgnoreSource::SymlinkLoop => {
+                write!(f, "a link to its parent directory {:?}", self.pattern)
+            }
+            IgnoreSource::OutsideLink => {
+                write!(f, "a link to {:?}, outside of the project", self.pattern)
+            }
+            _ => write!(f, "{:?} of {}", self.pattern, self.source),
+        }
+    }
+}
+
+/// A set of ignore rules, applying to a directory of the project
+struct IgnoreLayer {
+    source: IgnoreSource,
+    /// the directory the rules apply to, relative to the project directory
+    directory: PathBuf,
+    gitignore: Gitignore,
+    /// true if the entries matched by the rules are packaged, like with the `include` patterns
+    include: bool,
+}
+
+impl IgnoreLayer {
+    fn from_lines<S: AsRef<str>>(
+        source: IgnoreSource,
+        input_dir: &Path,
+        lines: &[S],
+        include: bool,
+    ) -> Result<Self, ignore::Error> {
+        let mut builder = GitignoreBuilder::new(input_dir);
+        for line in lines {
+            builder.add_line(None, line.as_ref())?;
+        }
+        Ok(Self {
+            source,
+            directory: PathBuf::new(),
+            gitignore: builder.build()?,
+            include,
+        })
+    }
+
+    /// the layer of the [`IGNORE_PATH`] file in ``directory``, if there is one
+    fn from_ignore_file(input_dir: &Path, directory: &Path) -> Result<Option<Self>, ignore::Error> {
+        let mut builder = GitignoreBuilder::new(input_dir.join(directory));
+        if let Some(err) = builder.add(input_dir.join(directory).join(IGNORE_PATH)) {
+            return match err.io_error() {
+                Some(io_err) if io_err.kind() == ErrorKind::NotFound => Ok(None),
+                _ => Err(err),
+            };
+        };
+        Ok(Some(Self {
+            source: IgnoreSource::IgnoreFile(directory.join(IGNORE_PATH)),
+            directory: directory.to_path_buf(),
+            gitignore: builder.build()?,
+            include: false,
+        }))
+    }
+
+    /// the rule of this layer deciding whether the entry at ``relative_path`` is ignored
+    fn matched(&self, relative_path: &Path, is_dir: bool) -> Option<IgnoreRule> {
+        let path = match relative_path.strip_prefix(&self.directory) {
+            Ok(path) if !path.as_os_str().is_empty() => path,
+            _ => return None,
+        };
+        match self.gitignore.matched_path_or_any_parents(path, is_dir) {
+            Match::None => None,
+            Match::Ignore(glob) => Some(IgnoreRule {
+                pattern: glob.original().to_string(),
+                source: self.source.clone(),
+                whitelist: self.include,
+            }),
+            Match::Whitelist(glob) => Some(IgnoreRule {
+                pattern: glob.original().to_string(),
+                source: self.source.clone(),
+                whitelist: true,
+            }),
+        }
+    }
+}
+
+/// The ignore rules of a mod project, from every [`IgnoreSource`], merged in a single matcher.
+/// The last matching rule wins, the sources being ordered as in [`IgnoreSource`].
+struct ProjectIgnore {
+    defaults: IgnoreLayer,
+    ignore_files: Vec<IgnoreLayer>,
+    overrides: Vec<IgnoreLayer>,
+    /// the `include` patterns of the package
+    include: Vec<String>,
+}
+
+impl ProjectIgnore {
+    fn new(
+        input_dir: &Path,
+        information: &PackageInformation,
+        exclude: &[String],
+    ) -> Result<Self, ignore::Error> {
+        Ok(Self {
+            defaults: IgnoreLayer::from_lines(
+                IgnoreSource::Default,
+                input_dir,
+                DEFAULT_IGNORES,
+                false,
+            )?,
+            ignore_files: Vec::new(),
+            overrides: vec![
+                IgnoreLayer::from_lines(
+                    IgnoreSource::Config,
+                    input_dir,
+                    &information.exclude,
+                    false,
+                )?,
+                IgnoreLayer::from_lines(
+                    IgnoreSource::Config,
+                    input_dir,
+                    &information.include,
+                    true,
+                )?,
+                IgnoreLayer::from_lines(IgnoreSource::Options, input_dir, exclude, false)?,
+            ],
+            include: information.include.clone(),
+        })
+    }
+
+    /// true if an `include` pattern could match an entry inside ``directory``, so its content
+    /// has to be walked even if it is ignored. Patterns with wildcards are assumed to match.
+    fn may_include_below(&self, directory: &Path) -> bool {
+        self.include.iter().any(|pattern| {
+            let pattern = pattern.trim().trim_end_matches('/');
+            if pattern.is_empty() || pattern.starts_with('#') {
+                return false;
+            };
+            // like in gitignore, a pattern without a slash match at any depth
+            if !pattern.contains('/') {
+                return true;
+            };
+            let parts = pattern
+                .trim_start_matches('/')
+                .split('/')
+                .collect::<Vec<_>>();
+            let components = directory.components().collect::<Vec<_>>();
+            if components.len() >= parts.len() && !parts.contains(&"**") {
+                return false;
+            };
+            for (part, component) in parts.iter().zip(&components) {
+                if *part == "**" {
+                    return true;
+                };
+                let is_glob = part.contains(['*', '?', '[']);
+                if !is_glob && component.as_os_str() != *part {
+                    return false;
+                };
+            }
+            true
+        })
+    }
+
+    /// the last rule matching the entry at ``relative_path``
+    fn matched(&self, relative_path: &Path, is_dir: bool) -> Option<IgnoreRule> {
+        std::iter::once(&self.defaults)
+            .chain(&self.ignore_files)
+            .chain(&self.overrides)
+            .rev()
+            .find_map(|layer| layer.matched(relative_path, is_dir))
+    }
+}
+
+/// A file or directory of a mod project, as listed by [`project_entries`]
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ProjectEntry {
+    /// the path of the entry, relative to the project directory
+    pub relative_path: PathBuf,
+    #[serde(skip)]
+    pub path: PathBuf,
+    pub is_file: bool,
+    /// the size of the file, in bytes, or 0 for directories
+    pub size: u64,
+    /// true if the entry is ignored, and so isn't packaged. The content of ignored directories
+    /// isn't listed.
+    pub ignored: bool,
+    /// the last rule matching the entry, or one of its parent directories
+    pub rule: Option<IgnoreRule>,
+    /// the target of the link, for the links leading outside of the project that are followed
+    pub outside_link: Option<PathBuf>,
+}
+
+/// the canonical path of ``output``, which may not exist yet
+fn canonical_output(output: &Path) -> Option<PathBuf> {
+    match fs::canonicalize(output) {
+        Ok(output) => Some(output),
+        Err(_) => {
+            let parent = match output.parent() {
+                Some(parent) if !parent.as_os_str().is_empty() => parent,
+                _ => Path::new("."),
+            };
+            Some(fs::canonicalize(parent).ok()?.join(output.file_name()?))
+        }
+    }
+}
+
+/// true if the entry at ``path`` is ``output``, a canonical path. The canonical paths are
+/// compared, as the output may be reached through a link or a path with `..` components.
+fn is_output(path: &Path, output: &Path) -> bool {
+    // only the entries with the same name are resolved
+    path.file_name() == output.file_name()
+        && fs::canonicalize(path).is_ok_and(|path| path == output)
+}
+
+/// list the files and directories of the mod project in ``input_dir``, in the order they are
+/// packaged. The files generated by [`create_package`] aren't listed.
+///
+/// The entries are matched against the [`DEFAULT_IGNORES`], the [`IGNORE_PATH`] files of the
+/// project, the `exclude` and `include` patterns of ``information`` and then the exclude
+/// patterns of ``options``. The output file of ``options`` and the link loops are always
+/// ignored, the links leading outside of the project are handled following ``options``.
+///
+/// An ignored directory is still walked if an `include` pattern could match its content, and
+/// is packaged if it contains an included entry.
+pub fn project_entries(
     input_dir: &Path,
-    destination: &mut D,
-) -> Result<(), CreatePackageError> {
+    information: &PackageInformation,
+    options: &WalkOptions,
+) -> Result<Vec<ProjectEntry>, CreatePackageError> {
+    let root = fs::canonicalize(input_dir)
+        .map_err(|err| CreatePackageError::FileIOError(input_dir.to_path_buf(), err))?;
+    let output = options.output.and_then(canonical_output);
+    let relative = |path: &Path| -> Result<PathBuf, CreatePackageError> {
+        path.strip_prefix(input_dir)
+            .map(Path::to_path_buf)
+            .map_err(|err| {
+                CreatePackageError::StripPrefixError(
+                    path.to_path_buf(),
+                    input_dir.to_path_buf(),
+                    err,
+                )
+            })
+    };
+
+    let mut ignore = ProjectIgnore::new(input_dir, information, options.exclude)?;
+    ignore
+        .ignore_files
+        .extend(IgnoreLayer::from_ignore_file(input_dir, Path::new(""))?);
+
+    let mut walkdir = WalkDir::new(input_dir)
+        .follow_links(true)
+        .sort_by(|first, second| first.file_name().cmp(second.file_name()))
+        .into_iter();
+
+    let mut entries = Vec::new();
+    while let Some(entry) = walkdir.next() {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(err) => match (err.path(), err.loop_ancestor()) {
+                (Some(path), Some(ancestor)) => {
+                    let ancestor = relative(ancestor).unwrap_or_else(|_| ancestor.to_path_buf());
+                    entries.push(ProjectEntry {
+                        relative_path: relative(path)?,
+                        path: path.to_path_buf(),
+                        is_file: false,
+                        size: 0,
+                        ignored: true,
+                        rule: Some(IgnoreRule::builtin(IgnoreSource::SymlinkLoop, &ancestor)),
+                        outside_link: None,
+                    });
+                    continue;
+                }
+                _ => return Err(err.into()),
+            },
+        };
+
+        let content_abs_path = entry.path();
+        let content_rel_path = &relative(content_abs_path)?;
+
+        // the root directory itself isn't part of the archive
+        if content_rel_path.as_os_str().is_empty()
+            || content_rel_path == Path::new(JSON_CONFIG_PATH)
+            || content_rel_path == Path::new(CHECKSUMS_PATH)
+            || content_rel_path == Path::new(SIGNATURE_PATH)
+        {
+            continue;
+        };
+
+        let is_file = entry.file_type().is_file();
+        let mut rule = if output
+            .as_ref()
+            .is_some_and(|output| is_output(content_abs_path, output))
+        {
+            Some(IgnoreRule::builtin(IgnoreSource::Output, content_rel_path))
+        } else {
+            ignore.matched(content_rel_path, !is_file)
+        };
+        let mut ignored = rule.as_ref().is_some_and(|rule| !rule.whitelist);
+        let mut outside_link = None;
+        if !ignored && entry.path_is_symlink() {
+            let target = fs::canonicalize(content_abs_path).map_err(|err| {
+                CreatePackageError::FileIOError(content_abs_path.to_path_buf(), err)
+            })?;
+            if !target.starts_with(&root) {
+                match options.links {
+                    LinkPolicy::Error => {
+                        return Err(CreatePackageError::OutsideLinkError(
+                            content_rel_path.to_path_buf(),
+                            target,
+                        ))
+                    }
+                    LinkPolicy::Skip => {
+                        rule = Some(IgnoreRule::builtin(IgnoreSource::OutsideLink, &target));
+                        ignored = true;
+                    }
+                    LinkPolicy::Follow => outside_link = Some(target),
+                };
+            };
+        };
+        if !is_file {
+            let skipped_link = rule
+                .as_ref()
+                .is_some_and(|rule| rule.source == IgnoreSource::OutsideLink);
+            if ignored && (skipped_link || !ignore.may_include_below(content_rel_path)) {
+                walkdir.skip_current_dir();
+            } else {
+                ignore
+                    .ignore_files
+                    .extend(IgnoreLayer::from_ignore_file(input_dir, content_rel_path)?);
+            };
+        };
+        entries.push(ProjectEntry {
+            relative_path: content_rel_path.to_path_buf(),
+            path: content_abs_path.to_path_buf(),
+            is_file,
+            size: if is_file { entry.metadata()?.len() } else { 0 },
+            ignored,
+            rule,
+            outside_link,
+        });
+    }
+
+    // the directories holding included entries are packaged, and only the ignored entries that
+    // aren't in an ignored directory are listed
+    let packaged_parents = entries
+        .iter()
+        .filter(|entry| !entry.ignored)
+        .flat_map(|entry| entry.relative_path.ancestors().skip(1))
+        .map(Path::to_path_buf)
+        .collect::<BTreeSet<_>>();
+    for entry in &mut entries {
+        if packaged_parents.contains(&entry.relative_path) {
+            entry.ignored = false;
+        };
+    }
+    let ignored_directories = entries
+        .iter()
+        .filter(|entry| entry.ignored && !entry.is_file)
+        .map(|entry| entry.relative_path.clone())
+        .collect::<BTreeSet<_>>();
+    entries.retain(|entry| {
+        !entry
+            .relative_path
+            .ancestors()
+            .skip(1)
+            .any(|parent| ignored_directories.contains(parent))
+    });
+    Ok(entries)
+}
+
+/// An entry of a mod project, and how [`create_package`] handle it
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct PlannedEntry {
+    #[serde(flatten)]
+    pub entry: ProjectEntry,
+    /// false for the files stored without compression
+    pub compress: bool,
+}
+
+/// What [`create_package`] put in the archive of a mod project, as listed by [`plan_package`]
+#[derive(Serialize)]
+pub struct PackagePlan {
+    #[serde(serialize_with = "serialize_information")]
+    pub information: PackageInformation,
+    /// every entry of the project, including the ignored ones, in the order they are packaged
+    pub entries: Vec<PlannedEntry>,
+}
+
+impl PackagePlan {
+    /// the total uncompressed size of the files that are packaged
+    pub fn total_size(&self) -> u64 {
+        self.entries
+            .iter()
+            .filter(|planned| !planned.entry.ignored)
+            .map(|planned| planned.entry.size)
+            .sum()
+    }
+}
+
+/// check that the mod project in ``input_dir`` can be packaged, and list the entries of its
+/// archive, without writing anything. The warnings are reported to the reporter of ``options``.
+pub fn plan_package(
+    input_dir: &Path,
+    options: &PackageOptions,
+) -> Result<PackagePlan, CreatePackageError> {
     // load the package
-    let package = load_package_from_project(&input_dir)
+    let config = load_project_config(input_dir)
         .map_err(|err| CreatePackageError::LoadPackageError(input_dir.to_path_buf(), err))?;
+    for unknown_key in &config.unknown_keys {
+        options.reporter.report(Event::Warning {
+            message: unknown_key.to_string(),
+        });
+    }
+    let package = config.package;
 
     let missing_publish_field = package.information.missing_publish_field();
     if !missing_publish_field.is_empty() {
@@ -55,101 +689,499 @@ pub fn create_package<D: Write + Seek>(
         ));
     };
 
-    //load the ignore file
-    let ignore_path = input_dir.join(IGNORE_PATH);
-
-    let mut builder = GitignoreBuilder::new(&input_dir);
-    let ignore = match builder.add(&ignore_path) {
-        None => Some(builder.build()?),
-        Some(err) => match err.io_error() {
-            Some(io_err) => match io_err.kind() {
-                ErrorKind::NotFound => {
-                    println!("{:?} not found, ignoring it.", ignore_path);
-                    None
-                }
-                _ => return Err(CreatePackageError::from(err)),
-            },
-            None => return Err(CreatePackageError::from(err)),
-        },
-    };
-
-    // write the zip file
-    let mut zip = ZipWriter::new(destination);
-
-    let walkdir = WalkDir::new(&input_dir).follow_links(true);
-
-    let zip_options = FileOptions::default().compression_method(CompressionMethod::Deflated);
-
-    let mut embedded_content = Vec::new();
-    for entry in walkdir {
-        let entry = entry?;
-
-        let content_abs_path = entry.path();
-        let content_rel_path = content_abs_path.strip_prefix(&input_dir).map_err(|err| {
-            CreatePackageError::StripPrefixError(
-                content_abs_path.to_path_buf(),
-                input_dir.to_path_buf(),
-                err,
-            )
-        })?;
+    options
+        .strategies
+        .check(&package.information.install_strategies)?;
 
-        if content_rel_path == PathBuf::from(JSON_CONFIG_PATH) {
-            continue;
+    if let Some(license) = &package.information.license {
+        if let Err(err) = parse_license(license) {
+            options.reporter.report(Event::Warning {
+                message: format!("the license {}", err),
+            });
         };
+    };
 
-        let is_file = entry.file_type().is_file();
+    let mut uncompressed_builder = GitignoreBuilder::new(input_dir);
+    for pattern in &package.information.uncompressed {
+        uncompressed_builder.add_line(None, pattern)?;
+    }
+    let uncompressed = uncompressed_builder.build()?;
 
-        if let Some(ignore) = &ignore {
-            if ignore
-                .matched_path_or_any_parents(&content_rel_path, !is_file)
-                .is_ignore()
-            {
-                println!("ignored {:?}", content_rel_path);
-                continue;
-            }
+    let entries = project_entries(input_dir, &package.information, &options.walk)?;
+    for entry in &entries {
+        if let (Some(target), false) = (&entry.outside_link, entry.ignored) {
+            options.reporter.report(Event::Warning {
+                message: format!(
+                    "the link {:?} leads to {:?}, outside of the mod project. It is packaged",
+                    entry.relative_path, target
+                ),
+            });
         };
+    }
+    let entries = entries
+        .into_iter()
+        .map(|entry| PlannedEntry {
+            compress: !entry.is_file
+                || options.format != ArchiveFormat::Zip
+                || !is_stored(&entry.relative_path, &uncompressed),
+            entry,
+        })
+        .collect();
+    Ok(PackagePlan {
+        information: package.information,
+        entries,
+    })
+}
 
-        if is_file {
-            println!("adding the file {:?} to the archive", content_rel_path);
-            zip.start_file(content_rel_path.to_string_lossy(), zip_options)?;
-            let mut embedded_file = File::open(content_abs_path).map_err(|err| {
-                CreatePackageError::FileIOError(content_abs_path.to_path_buf(), err)
-            })?;
-            embedded_file
-                .read_to_end(&mut embedded_content)
-                .map_err(|err| {
-                    CreatePackageError::FileIOError(content_abs_path.to_path_buf(), err)
-                })?;
-            zip.write_all(&embedded_content)
-                .map_err(CreatePackageError::ZipWriteError)?;
-            embedded_content.clear();
+/// create a package archive of the mod project in ``input_dir``. Its install strategies must be
+/// part of the strategies of ``options``.
+///
+/// Files are streamed into the archive, so they don't need to fit in memory. In zip archives,
+/// files with one of the [`STORED_EXTENSIONS`] or matching the `uncompressed` patterns of the
+/// package are stored without compression.
+pub fn create_package<D: Write + Seek>(
+    input_dir: &Path,
+    destination: &mut D,
+    options: &PackageOptions,
+) -> Result<(), CreatePackageError> {
+    let plan = plan_package(input_dir, options)?;
+
+    // list the content of the archive, with the event reported once each entry is written
+    let mut entries = Vec::new();
+    let mut added_events = Vec::new();
+    let (mut files, mut directories, mut ignored) = (0, 0, 0);
+    for PlannedEntry { entry, compress } in plan.entries {
+        if entry.ignored {
+            ignored += 1;
+            options.reporter.report(Event::FileIgnored {
+                path: entry.relative_path,
+            });
+        } else if entry.is_file {
+            files += 1;
+            entries.push(SourceEntry::File {
+                name: entry_name(&entry.relative_path),
+                path: entry.path,
+                compress,
+            });
+            added_events.push(Event::FileAdded {
+                path: entry.relative_path,
+                compressed: compress,
+            });
         } else {
-            println!("adding the directory {:?} to the archive", content_rel_path);
-            zip.add_directory(content_rel_path.to_string_lossy(), zip_options)?;
+            directories += 1;
+            entries.push(SourceEntry::Directory {
+                name: entry_name(&entry.relative_path),
+            });
+            added_events.push(Event::DirectoryAdded {
+                path: entry.relative_path,
+            });
         }
     }
-    let config_json: Vec<u8> = get_project_config_json(&package.information)
-        .map_err(CreatePackageError::EncodeJsonError)?;
-    zip.start_file(JSON_CONFIG_PATH, zip_options)?;
-    zip.write_all(&config_json)
-        .map_err(CreatePackageError::ZipWriteError)?;
+
+    // write the archive
+    let mut writer = options.format.writer(
+        destination,
+        WriterSettings {
+            timestamp: options.timestamp,
+            threads: options.threads,
+        },
+    )?;
+    let checksums = writer.write_entries(&entries, &mut |index| {
+        options.reporter.report(added_events[index].clone())
+    })?;
+    let config_json: Vec<u8> =
+        get_project_config_json(&plan.information).map_err(CreatePackageError::EncodeJsonError)?;
+    writer.write_data(JSON_CONFIG_PATH, &config_json)?;
+    let checksums_json =
+        serde_json::to_vec_pretty(&checksums).map_err(CreatePackageError::EncodeJsonError)?;
+    writer.write_data(CHECKSUMS_PATH, &checksums_json)?;
+    if let Some(signing_key) = options.signing_key {
+        let signature = sign(signing_key, &signed_message(&config_json, &checksums_json));
+        let signature_json =
+            serde_json::to_vec_pretty(&signature).map_err(CreatePackageError::EncodeJsonError)?;
+        writer.write_data(SIGNATURE_PATH, &signature_json)?;
+    };
+    writer.finish()?;
+    options.reporter.report(Event::Finished {
+        files,
+        directories,
+        ignored,
+    });
     Ok(())
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::package_writer::create_package;
-    use std::io::Cursor;
-    use std::path::PathBuf;
+    use crate::archive::ArchiveFormat;
+    use crate::constants::IGNORE_PATH;
+    use crate::install_strategy::StrategySet;
+    use crate::package::PackageInformation;
+    use crate::package_reader::PackageReader;
+    use crate::package_writer::{
+        create_package, entry_name, project_entries, IgnoreSource, PackageOptions, WalkOptions,
+    };
+    use crate::report::tests::RecordReporter;
+    use crate::report::Event;
+    use crate::test_utils::test_mod_path;
+    use semver::Version;
+    use std::fs;
+    use std::io::{Cursor, Read};
+    use std::path::{Path, PathBuf};
+    use zip::{CompressionMethod, ZipArchive};
 
     #[test]
     fn test_create_package() {
-        let test_mod = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
-            .parent()
-            .unwrap()
-            .join("test_data")
-            .join("test_mod");
+        let test_mod = test_mod_path();
         let mut buffer = Cursor::new(vec![0u8; 1_000_000]); //1Mo should be enought
-        create_package(&test_mod, &mut buffer).unwrap();
+        let strategies = StrategySet::default();
+        let reporter = RecordReporter::default();
+        let mut options = PackageOptions::new(&strategies);
+        options.reporter = &reporter;
+        create_package(&test_mod, &mut buffer, &options).unwrap();
+
+        let events = reporter.events();
+        assert!(events.contains(&Event::FileIgnored {
+            path: PathBuf::from("ignored.txt")
+        }));
+        assert!(events.contains(&Event::FileAdded {
+            path: PathBuf::from("subfolder/file.arbitrary"),
+            compressed: true
+        }));
+        assert!(events.contains(&Event::DirectoryAdded {
+            path: PathBuf::from("subfolder")
+        }));
+        assert_eq!(
+            events.last(),
+            Some(&Event::Finished {
+                files: 4,
+                directories: 1,
+                ignored: 2
+            })
+        );
+    }
+
+    #[test]
+    fn test_reproducible_package() {
+        let test_mod = test_mod_path();
+        let strategies = StrategySet::default();
+        let mut options = PackageOptions::new(&strategies);
+        options.timestamp = Some(1_600_000_000);
+        let mut first = Cursor::new(Vec::new());
+        create_package(&test_mod, &mut first, &options).unwrap();
+        let mut second = Cursor::new(Vec::new());
+        create_package(&test_mod, &mut second, &options).unwrap();
+        assert_eq!(first.get_ref(), second.get_ref());
+
+        let mut archive = ZipArchive::new(first).unwrap();
+        let names = (0..archive.len())
+            .map(|index| archive.by_index(index).unwrap().name().to_string())
+            .collect::<Vec<_>>();
+        let mut sorted_names = names.clone();
+        sorted_names[..names.len() - 2].sort();
+        assert_eq!(names, sorted_names);
+        let entry = archive.by_name("another_file.txt").unwrap();
+        assert_eq!(entry.unix_mode().unwrap() & 0o777, 0o644);
+        let time = entry.last_modified();
+        assert_eq!(
+            (
+                time.year(),
+                time.month(),
+                time.day(),
+                time.hour(),
+                time.minute()
+            ),
+            (2020, 9, 13, 12, 26)
+        );
+    }
+
+    #[test]
+    fn test_package_compression() {
+        let temporary_dir = tempfile::tempdir().unwrap();
+        let project = temporary_dir.path();
+        fs::create_dir_all(project.join("raw")).unwrap();
+        fs::write(
+            project.join("config.toml"),
+            concat!(
+                "identifier = \"compressed\"\n",
+                "display_name = \"Compressed\"\n",
+                "creator = \"modder\"\n",
+                "description = \"a mod\"\n",
+                "version = \"1.0.0\"\n",
+                "license = \"MIT\"\n",
+                "uncompressed = [\"raw/\"]\n",
+            ),
+        )
+        .unwrap();
+        for index in 0..8 {
+            fs::write(
+                project.join(format!("file_{}.txt", index)),
+                "some text ".repeat(1000 * index),
+            )
+            .unwrap();
+        }
+        fs::write(project.join("textures.ARCHIVE"), "packed ".repeat(1000)).unwrap();
+        fs::write(project.join("raw").join("data.bin"), "raw ".repeat(1000)).unwrap();
+
+        let strategies = StrategySet::default();
+        let mut options = PackageOptions::new(&strategies);
+        let mut serial = Cursor::new(Vec::new());
+        create_package(project, &mut serial, &options).unwrap();
+        options.threads = 4;
+        let mut parallel = Cursor::new(Vec::new());
+        create_package(project, &mut parallel, &options).unwrap();
+        assert_eq!(serial.get_ref(), parallel.get_ref());
+
+        let mut archive = ZipArchive::new(parallel).unwrap();
+        for (name, method) in &[
+            ("file_7.txt", CompressionMethod::Deflated),
+            ("textures.ARCHIVE", CompressionMethod::Stored),
+            ("raw/data.bin", CompressionMethod::Stored),
+        ] {
+            assert_eq!(archive.by_name(name).unwrap().compression(), *method);
+        }
+        let mut content = String::new();
+        archive
+            .by_name("file_7.txt")
+            .unwrap()
+            .read_to_string(&mut content)
+            .unwrap();
+        assert_eq!(content, "some text ".repeat(7000));
+
+        // the packaging patterns stay in the project
+        let mut config = String::new();
+        archive
+            .by_name("config.json")
+            .unwrap()
+            .read_to_string(&mut config)
+            .unwrap();
+        assert!(config.contains("\"identifier\": \"compressed\""));
+        assert!(!config.contains("uncompressed"));
+    }
+
+    #[test]
+    fn test_tar_package() {
+        let test_mod = test_mod_path();
+        let strategies = StrategySet::default();
+        for format in &[ArchiveFormat::TarZst, ArchiveFormat::TarGz] {
+            let mut options = PackageOptions::new(&strategies);
+            options.format = *format;
+            let mut buffer = Cursor::new(Vec::new());
+            create_package(&test_mod, &mut buffer, &options).unwrap();
+
+            let mut reader = PackageReader::new(buffer).unwrap();
+            assert_eq!(reader.format(), *format);
+            assert!(reader.checksums().unwrap().contains_key("another_file.txt"));
+            let target = tempfile::tempdir().unwrap();
+            let target = target.path();
+            reader.extract(target).unwrap();
+            assert!(target.join("subfolder").join("file.arbitrary").is_file());
+            assert!(!target.join("checksums.json").exists());
+        }
+    }
+
+    #[test]
+    fn test_project_entries_ignores() {
+        let work_dir = tempfile::tempdir().unwrap();
+        let project = work_dir.path();
+        for dir in [".git", "textures", "scripts/generated", "docs/manual"] {
+            fs::create_dir_all(project.join(dir)).unwrap();
+        }
+        for file in [
+            ".git/HEAD",
+            "old.zip",
+            "notes.txt",
+            "notes.txt.swp",
+            "draft.psd",
+            "textures/a.png",
+            "textures/b.png",
+            "scripts/main.reds",
+            "scripts/generated/out.reds",
+            "scripts/generated/keep.reds",
+            "docs/draft.txt",
+            "docs/manual/old.txt",
+            "docs/manual/readme.txt",
+        ] {
+            fs::write(project.join(file), "content").unwrap();
+        }
+        fs::write(project.join(IGNORE_PATH), "*.png\nnotes.txt\ndocs/\n").unwrap();
+        fs::write(
+            project.join("scripts").join(IGNORE_PATH),
+            "generated/\n!generated/keep.reds\n",
+        )
+        .unwrap();
+        let mut information =
+            PackageInformation::new("modder", "test", Version::new(1, 0, 0), "", "", "MIT");
+        information.include.push("textures/b.png".to_string());
+        information
+            .include
+            .push("docs/manual/readme.txt".to_string());
+        information.exclude.push("*.psd".to_string());
+
+        let entries = project_entries(
+            project,
+            &information,
+            &WalkOptions {
+                exclude: &["scripts/main.reds".to_string()],
+                ..WalkOptions::default()
+            },
+        )
+        .unwrap();
+        let packaged: Vec<String> = entries
+            .iter()
+            .filter(|entry| !entry.ignored)
+            .map(|entry| entry_name(&entry.relative_path))
+            .collect();
+        assert_eq!(
+            packaged,
+            vec![
+                ".modignore",
+                "docs",
+                "docs/manual",
+                "docs/manual/readme.txt",
+                "scripts",
+                "scripts/.modignore",
+                "textures",
+                "textures/b.png"
+            ]
+        );
+        let rule = |name: &str| {
+            entries
+                .iter()
+                .find(|entry| entry_name(&entry.relative_path) == name)
+                .and_then(|entry| entry.rule.clone())
+                .map(|rule| (rule.pattern, rule.source))
+        };
+        assert_eq!(
+            rule(".git"),
+            Some((".git/".to_string(), IgnoreSource::Default))
+        );
+        assert_eq!(
+            rule("draft.psd"),
+            Some(("*.psd".to_string(), IgnoreSource::Config))
+        );
+        assert_eq!(
+            rule("scripts/generated"),
+            Some((
+                "generated/".to_string(),
+                IgnoreSource::IgnoreFile(PathBuf::from("scripts").join(IGNORE_PATH))
+            ))
+        );
+        assert_eq!(
+            rule("scripts/main.reds"),
+            Some(("scripts/main.reds".to_string(), IgnoreSource::Options))
+        );
+        assert_eq!(
+            rule("textures/b.png"),
+            Some(("textures/b.png".to_string(), IgnoreSource::Config))
+        );
+        // the ignored directory is walked for the included file, and packaged with it
+        assert_eq!(
+            rule("docs/manual/old.txt"),
+            Some((
+                "docs/".to_string(),
+                IgnoreSource::IgnoreFile(PathBuf::from(IGNORE_PATH))
+            ))
+        );
+        assert!(entries
+            .iter()
+            .any(|entry| entry_name(&entry.relative_path) == "docs/draft.txt" && entry.ignored));
+        // the content of ignored directories isn't listed
+        assert_eq!(rule(".git/HEAD"), None);
+        assert_eq!(rule("scripts/generated/keep.reds"), None);
+    }
+
+    #[test]
+    fn test_project_entries_output() {
+        let test_dir = tempfile::tempdir().unwrap();
+        let project = test_dir.path().join("project");
+        fs::create_dir_all(project.join("out")).unwrap();
+        fs::write(project.join("mod.txt"), "content").unwrap();
+        fs::write(project.join("out").join("pkg.zip"), "being written").unwrap();
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(project.join("out"), project.join("link")).unwrap();
+        let information =
+            PackageInformation::new("modder", "test", Version::new(1, 0, 0), "", "", "MIT");
+        let output = project.join("out").join("..").join("out").join("pkg.zip");
+        let options = WalkOptions {
+            output: Some(&output),
+            ..WalkOptions::default()
+        };
+
+        let entries = project_entries(&project, &information, &options).unwrap();
+        let source = |name: &str| {
+            entries
+                .iter()
+                .find(|entry| entry_name(&entry.relative_path) == name)
+                .and_then(|entry| entry.rule.clone())
+                .map(|rule| rule.source)
+        };
+        assert_eq!(source("mod.txt"), None);
+        assert_eq!(source("out/pkg.zip"), Some(IgnoreSource::Output));
+        // the same file, reached through a link to its directory
+        #[cfg(unix)]
+        assert_eq!(source("link/pkg.zip"), Some(IgnoreSource::Output));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_project_entries_links() {
+        use crate::package_writer::{CreatePackageError, LinkPolicy};
+        use std::os::unix::fs::symlink;
+
+        let test_dir = tempfile::tempdir().unwrap();
+        let project = test_dir.path().join("project");
+        let outside = test_dir.path().join("outside");
+        fs::create_dir_all(&project).unwrap();
+        fs::create_dir_all(&outside).unwrap();
+        fs::write(project.join("mod.txt"), "content").unwrap();
+        fs::write(project.join("mod.zip"), "being written").unwrap();
+        fs::write(outside.join("secret.txt"), "content").unwrap();
+        symlink(&project, project.join("loop")).unwrap();
+        symlink(&outside, project.join("shared")).unwrap();
+        let information =
+            PackageInformation::new("modder", "test", Version::new(1, 0, 0), "", "", "MIT");
+        let output = project.join("mod.zip");
+        let mut options = WalkOptions {
+            output: Some(&output),
+            links: LinkPolicy::Skip,
+            ..WalkOptions::default()
+        };
+
+        let entries = project_entries(&project, &information, &options).unwrap();
+        let source = |name: &str| {
+            entries
+                .iter()
+                .find(|entry| entry_name(&entry.relative_path) == name)
+                .and_then(|entry| entry.rule.clone())
+                .map(|rule| rule.source)
+        };
+        assert_eq!(source("mod.txt"), None);
+        assert_eq!(source("mod.zip"), Some(IgnoreSource::Output));
+        assert_eq!(source("loop"), Some(IgnoreSource::SymlinkLoop));
+        assert_eq!(source("shared"), Some(IgnoreSource::OutsideLink));
+        assert!(entries
+            .iter()
+            .all(|entry| entry.ignored == (entry_name(&entry.relative_path) != "mod.txt")));
+
+        options.links = LinkPolicy::Follow;
+        let entries = project_entries(&project, &information, &options).unwrap();
+        let shared = entries
+            .iter()
+            .find(|entry| entry.relative_path == Path::new("shared"))
+            .unwrap();
+        assert!(!shared.ignored);
+        assert_eq!(
+            shared.outside_link,
+            Some(fs::canonicalize(&outside).unwrap())
+        );
+        assert!(entries
+            .iter()
+            .any(|entry| entry.relative_path == Path::new("shared/secret.txt")));
+
+        options.links = LinkPolicy::Error;
+        assert!(matches!(
+            project_entries(&project, &information, &options),
+            Err(CreatePackageError::OutsideLinkError(..))
+        ));
     }
 }
@@ -0,0 +1,441 @@
+//! Profiles are named sets of installed mods, each targeting a game directory.
+//!
+//! They are stored in a gpm home directory, with the following layout:
+//! ```text
+//! <home>/profiles.toml              the name of the active profile
+//! <home>/profiles/<name>/profile.toml  the configuration of the profile
+//! <home>/profiles/<name>/lock.toml     the LockFile of the profile
+//! <home>/profiles/<name>/trusted_keys.toml  the keys trusted to sign packages
+//! <home>/profiles/<name>/staging/      the default staging directory of the profile
+//! ```
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::deploy::{DeployError, DeploymentManifest, JOURNAL_DIRECTORY};
+use crate::lockfile::LockFile;
+use crate::signature::SignaturePolicy;
+
+use serde::{Deserialize, Serialize};
+
+pub const PROFILES_STATE_PATH: &str = "profiles.toml";
+pub const PROFILES_DIRECTORY: &str = "profiles";
+pub const PROFILE_CONFIG_PATH: &str = "profile.toml";
+pub const PROFILE_LOCK_PATH: &str = "lock.toml";
+pub const PROFILE_STAGING_DIRECTORY: &str = "staging";
+/// the environment variable that can be used to override the default gpm home directory
+pub const GPM_HOME_ENV: &str = "GPM_HOME";
+
+#[derive(thiserror::Error, Debug)]
+pub enum ProfileError {
+    #[error("io error with the file {0}")]
+    FileIOError(PathBuf, #[source] io::Error),
+    #[error("error while parsing the toml file {0}")]
+    TomlDecodeError(PathBuf, #[source] toml::de::Error),
+    #[error("can't encode the toml file {0}. Probably internal error")]
+    TomlEncodeError(PathBuf, #[source] toml::ser::Error),
+    #[error("error with the lock file of the profile {0}")]
+    LockFileError(String, #[source] anyhow::Error),
+    #[error("the profile name {0:?} is invalid, it should only contain ascii letters, digits, `-` and `_`")]
+    InvalidNameError(String),
+    #[error("the profile {0} already exist")]
+    AlreadyExistError(String),
+    #[error("the profile {0} doesn't exist")]
+    NotFoundError(String),
+    #[error("there is no active profile, select one with `gpm profile switch`")]
+    NoActiveProfileError,
+    #[error("can't read what is deployed by the profile {0}")]
+    DeployError(String, #[source] Box<DeployError>),
+    #[error("mods of the profile {0} are deployed in its game directory, remove them with `gpm remove` first")]
+    DeployedError(String),
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct StoredProfilesState {
+    #[serde(default)]
+    active: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoredProfile {
+    game_dir: PathBuf,
+    #[serde(default)]
+    staging_dir: Option<PathBuf>,
+    #[serde(default)]
+    repository: Option<PathBuf>,
+    #[serde(default)]
+    signature_policy: SignaturePolicy,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    load_order: Vec<String>,
+}
+
+/// A named set of installed mods, deployed in a game directory
+#[derive(Debug)]
+pub struct Profile {
+    pub name: String,
+    /// the folder containing the profile files. Relative [`crate::lockfile::LockSource::Path`] are
+    /// based around it.
+    pub path: PathBuf,
+    /// the directory of the game the mods are installed in
+    pub game_dir: PathBuf,
+    /// the directory where the mods are extracted before being deployed
+    pub staging_dir: PathBuf,
+    /// the [`crate::repository::LocalRepository`] to install the mods from, if any
+    pub repository: Option<PathBuf>,
+    /// the identifiers of the packages to load after the others, in this order. See
+    /// [`crate::conflict`].
+    pub load_order: Vec<String>,
+    /// how the signature of the installed package archives is checked, against the keys of
+    /// [`crate::signature::TrustedKeys`]
+    pub signature_policy: SignaturePolicy,
+    pub lock_file: LockFile,
+}
+
+impl Profile {
+    /// load the profile stored in ``path``
+    pub fn load(name: &str, path: &Path) -> Result<Self, ProfileError> {
+        let config_path = path.join(PROFILE_CONFIG_PATH);
+        let stored: StoredProfile = read_toml(&config_path)?;
+        let lock_path = path.join(PROFILE_LOCK_PATH);
+        let lock_file = LockFile::load_file(&lock_path)
+            .map_err(|err| ProfileError::LockFileError(name.to_string(), err))?;
+        Ok(Self {
+            name: name.to_string(),
+            path: path.to_path_buf(),
+            game_dir: stored.game_dir,
+            staging_dir: stored
+                .staging_dir
+                .unwrap_or_else(|| path.join(PROFILE_STAGING_DIRECTORY)),
+            repository: stored.repository,
+            load_order: stored.load_order,
+            signature_policy: stored.signature_policy,
+            lock_file,
+        })
+    }
+
+    /// write the configuration and the [`LockFile`] of this profile
+    pub fn save(&self) -> Result<(), ProfileError> {
+        fs::create_dir_all(&self.path)
+            .map_err(|err| ProfileError::FileIOError(self.path.clone(), err))?;
+        write_toml(&self.path.join(PROFILE_CONFIG_PATH), &self.stored())?;
+        self.lock_file
+            .write_file(&self.path.join(PROFILE_LOCK_PATH))
+            .map_err(|err| ProfileError::LockFileError(self.name.clone(), err))
+    }
+
+    /// the content of the [`PROFILE_CONFIG_PATH`] file of this profile, for the deployment to
+    /// write it along with the files it depends on
+    pub(crate) fn config_content(&self) -> Result<Vec<u8>, ProfileError> {
+        let config_path = self.path.join(PROFILE_CONFIG_PATH);
+        toml::to_vec(&self.stored()).map_err(|err| ProfileError::TomlEncodeError(config_path, err))
+    }
+
+    fn stored(&self) -> StoredProfile {
+        let default_staging_dir = self.path.join(PROFILE_STAGING_DIRECTORY);
+        StoredProfile {
+            game_dir: self.game_dir.clone(),
+            staging_dir: if self.staging_dir == default_staging_dir {
+                None
+            } else {
+                Some(self.staging_dir.clone())
+            },
+            repository: self.repository.clone(),
+            signature_policy: self.signature_policy,
+            load_order: self.load_order.clone(),
+        }
+    }
+
+    /// return the absolute version of a path stored in the [`LockFile`], based around the
+    /// profile folder if relative.
+    pub fn resolve_path(&self, path: &Path) -> PathBuf {
+        self.path.join(path)
+    }
+}
+
+/// The set of profiles stored in a gpm home directory
+#[derive(Debug)]
+pub struct ProfileStore {
+    home: PathBuf,
+    active: Option<String>,
+}
+
+impl ProfileStore {
+    /// return the gpm home directory: the content of the [`GPM_HOME_ENV`] environment variable if
+    /// defined, or the `.gpm` folder of the user home directory otherwise.
+    pub fn default_home() -> Option<PathBuf> {
+        if let Some(home) = std::env::var_os(GPM_HOME_ENV) {
+            return Some(PathBuf::from(home));
+        };
+        std::env::var_os("HOME")
+            .or_else(|| std::env::var_os("USERPROFILE"))
+            .map(|home| PathBuf::from(home).join(".gpm"))
+    }
+
+    /// open the profiles of the given gpm home directory. It doesn't need to exist yet.
+    pub fn open(home: &Path) -> Result<Self, ProfileError> {
+        let state_path = home.join(PROFILES_STATE_PATH);
+        let state = if state_path.exists() {
+            read_toml(&state_path)?
+        } else {
+            StoredProfilesState::default()
+        };
+        Ok(Self {
+            home: home.to_path_buf(),
+            active: state.active,
+        })
+    }
+
+    fn profile_path(&self, name: &str) -> PathBuf {
+        self.home.join(PROFILES_DIRECTORY).join(name)
+    }
+
+    fn save_state(&self) -> Result<(), ProfileError> {
+        fs::create_dir_all(&self.home)
+            .map_err(|err| ProfileError::FileIOError(self.home.clone(), err))?;
+        write_toml(
+            &self.home.join(PROFILES_STATE_PATH),
+            &StoredProfilesState {
+                active: self.active.clone(),
+            },
+        )
+    }
+
+    /// return true if a profile with this name exist
+    pub fn exists(&self, name: &str) -> bool {
+        is_valid_profile_name(name) && self.profile_path(name).join(PROFILE_CONFIG_PATH).is_file()
+    }
+
+    /// return the name of every profile, sorted alphabetically
+    pub fn list(&self) -> Result<Vec<String>, ProfileError> {
+        let profiles_dir = self.home.join(PROFILES_DIRECTORY);
+        let read_dir = match fs::read_dir(&profiles_dir) {
+            Ok(read_dir) => read_dir,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(ProfileError::FileIOError(profiles_dir, err)),
+        };
+        let mut names = Vec::new();
+        for entry in read_dir {
+            let entry =
+                entry.map_err(|err| ProfileError::FileIOError(profiles_dir.clone(), err))?;
+            let name = entry.file_name().to_string_lossy().to_string();
+            if self.exists(&name) {
+                names.push(name);
+            };
+        }
+        names.sort();
+        Ok(names)
+    }
+
+    /// create a new profile with an empty [`LockFile`]. If ``staging_dir`` is None, the staging
+    /// directory is located in the profile folder.
+    ///
+    /// The first profile created become the active one.
+    pub fn create(
+        &mut self,
+        name: &str,
+        game_dir: &Path,
+        staging_dir: Option<&Path>,
+    ) -> Result<Profile, ProfileError> {
+        if !is_valid_profile_name(name) {
+            return Err(ProfileError::InvalidNameError(name.to_string()));
+        };
+        if self.exists(name) {
+            return Err(ProfileError::AlreadyExistError(name.to_string()));
+        };
+        let path = self.profile_path(name);
+        let profile = Profile {
+            name: name.to_string(),
+            staging_dir: staging_dir
+                .map(Path::to_path_buf)
+                .unwrap_or_else(|| path.join(PROFILE_STAGING_DIRECTORY)),
+            path,
+            game_dir: game_dir.to_path_buf(),
+            repository: None,
+            load_order: Vec::new(),
+            signature_policy: SignaturePolicy::default(),
+            lock_file: LockFile::new(),
+        };
+        profile.save()?;
+        if self.active.is_none() {
+            self.active = Some(name.to_string());
+            self.save_state()?;
+        };
+        Ok(profile)
+    }
+
+    /// load the profile with the given name
+    pub fn load(&self, name: &str) -> Result<Profile, ProfileError> {
+        if !self.exists(name) {
+            return Err(ProfileError::NotFoundError(name.to_string()));
+        };
+        Profile::load(name, &self.profile_path(name))
+    }
+
+    /// delete the profile with the given name, with its lock file and its staging directory. If
+    /// it was the active one, there is no more active profile.
+    ///
+    /// A profile with mods deployed in its game directory, or with an interrupted deployment, is
+    /// refused, as the backups of the game files it replaced would be lost.
+    pub fn delete(&mut self, name: &str) -> Result<(), ProfileError> {
+        let profile = self.load(name)?;
+        let manifest = DeploymentManifest::load(&profile)
+            .map_err(|err| ProfileError::DeployError(name.to_string(), Box::new(err)))?;
+        if !manifest.packages.is_empty() || profile.path.join(JOURNAL_DIRECTORY).exists() {
+            return Err(ProfileError::DeployedError(name.to_string()));
+        };
+        // a staging directory outside of the profile folder isn't removed with it
+        if !profile.staging_dir.starts_with(&profile.path) {
+            match fs::remove_dir_all(&profile.staging_dir) {
+                Err(err) if err.kind() != io::ErrorKind::NotFound => {
+                    return Err(ProfileError::FileIOError(profile.staging_dir, err))
+                }
+                _ => (),
+            };
+        };
+        fs::remove_dir_all(&profile.path)
+            .map_err(|err| ProfileError::FileIOError(profile.path, err))?;
+        if self.active.as_deref() == Some(name) {
+            self.active = None;
+            self.save_state()?;
+        };
+        Ok(())
+    }
+
+    /// make the profile with the given name the active one
+    pub fn switch(&mut self, name: &str) -> Result<(), ProfileError> {
+        if !self.exists(name) {
+            return Err(ProfileError::NotFoundError(name.to_string()));
+        };
+        self.active = Some(name.to_string());
+        self.save_state()
+    }
+
+    /// the name of the active profile, if any
+    pub fn active(&self) -> Option<&str> {
+        self.active.as_deref()
+    }
+
+    /// load the active profile
+    pub fn load_active(&self) -> Result<Profile, ProfileError> {
+        match &self.active {
+            Some(name) => self.load(name),
+            None => Err(ProfileError::NoActiveProfileError),
+        }
+    }
+}
+
+/// return true if ``name`` can be used as a profile name. It should be non-empty and only contain
+/// ascii letters, digits, `-` and `_`, so it can be used as a folder name.
+pub fn is_valid_profile_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+fn read_toml<T: serde::de::DeserializeOwned>(path: &Path) -> Result<T, ProfileError> {
+    let content =
+        fs::read(path).map_err(|err| ProfileError::FileIOError(path.to_path_buf(), err))?;
+    toml::from_slice(&content).map_err(|err| ProfileError::TomlDecodeError(path.to_path_buf(), err))
+}
+
+fn write_toml<T: Serialize>(path: &Path, value: &T) -> Result<(), ProfileError> {
+    let content = toml::to_vec(value)
+        .map_err(|err| ProfileError::TomlEncodeError(path.to_path_buf(), err))?;
+    fs::write(path, content).map_err(|err| ProfileError::FileIOError(path.to_path_buf(), err))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::deploy::{DeploymentManifest, DEPLOYMENT_MANIFEST_PATH};
+    use crate::lockfile::LockSource;
+    use crate::profile::{ProfileError, ProfileStore};
+    use std::fs;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_profile_store() {
+        let work_dir = tempfile::tempdir().unwrap();
+        let home = work_dir.path().join("home");
+
+        let mut store = ProfileStore::open(&home).unwrap();
+        assert!(store.list().unwrap().is_empty());
+        assert!(matches!(
+            store.load_active(),
+            Err(ProfileError::NoActiveProfileError)
+        ));
+
+        let mut modded = store.create("modded", &home.join("game"), None).unwrap();
+        store.create("vanilla", &home.join("game"), None).unwrap();
+        assert!(matches!(
+            store.create("vanilla", &home.join("game"), None),
+            Err(ProfileError::AlreadyExistError(_))
+        ));
+        assert!(matches!(
+            store.create("../escape", &home.join("game"), None),
+            Err(ProfileError::InvalidNameError(_))
+        ));
+        assert_eq!(store.list().unwrap(), vec!["modded", "vanilla"]);
+        assert_eq!(store.active(), Some("modded"));
+
+        modded.lock_file.set_dependency_source(
+            "local".into(),
+            LockSource::Path {
+                path: PathBuf::from("local"),
+                checksum: None,
+            },
+        );
+        modded.save().unwrap();
+
+        store.switch("vanilla").unwrap();
+        let store = ProfileStore::open(&home).unwrap();
+        assert_eq!(store.active(), Some("vanilla"));
+        let modded = store.load("modded").unwrap();
+        assert!(modded.lock_file.dependency_source("local").is_some());
+        assert_eq!(modded.staging_dir, modded.path.join("staging"));
+
+        let mut store = store;
+        let staging_dir = work_dir.path().join("external_staging");
+        store
+            .create("external", &home.join("game"), Some(&staging_dir))
+            .unwrap();
+        fs::create_dir_all(staging_dir.join("package")).unwrap();
+        store.delete("external").unwrap();
+        assert!(!staging_dir.exists());
+
+        let deployed = store.create("deployed", &home.join("game"), None).unwrap();
+        let mut manifest = DeploymentManifest::default();
+        manifest.packages.insert(
+            "local".into(),
+            LockSource::Path {
+                path: PathBuf::from("local"),
+                checksum: None,
+            },
+        );
+        fs::write(
+            deployed.path.join(DEPLOYMENT_MANIFEST_PATH),
+            toml::to_vec(&manifest).unwrap(),
+        )
+        .unwrap();
+        assert!(matches!(
+            store.delete("deployed"),
+            Err(ProfileError::DeployedError(_))
+        ));
+        assert!(deployed.path.join(DEPLOYMENT_MANIFEST_PATH).is_file());
+        fs::write(
+            deployed.path.join(DEPLOYMENT_MANIFEST_PATH),
+            toml::to_vec(&DeploymentManifest::default()).unwrap(),
+        )
+        .unwrap();
+        store.delete("deployed").unwrap();
+
+        store.delete("vanilla").unwrap();
+        assert_eq!(store.active(), None);
+        assert_eq!(store.list().unwrap(), vec!["modded"]);
+        assert!(matches!(
+            store.switch("vanilla"),
+            Err(ProfileError::NotFoundError(_))
+        ));
+    }
+}
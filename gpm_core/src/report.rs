@@ -0,0 +1,67 @@
+//! Report the progress of long operations, like [`crate::package_writer::create_package`], to
+//! the frontend, instead of printing it.
+//!
+//! A frontend implements [`Reporter`] to render the [`Event`]s: the CLI print them as text or
+//! as JSON, a GUI could show them in a progress window.
+
+use std::path::PathBuf;
+
+use serde::Serialize;
+
+/// Something that happened during an operation
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event {
+    /// a file was added to the archive. ``compressed`` is false when it is stored as is.
+    FileAdded { path: PathBuf, compressed: bool },
+    /// a directory was added to the archive
+    DirectoryAdded { path: PathBuf },
+    /// a file or directory was excluded from the archive by the ignore file
+    FileIgnored { path: PathBuf },
+    /// a problem that doesn't stop the operation
+    Warning { message: String },
+    /// the operation succeeded
+    Finished {
+        files: usize,
+        directories: usize,
+        ignored: usize,
+    },
+}
+
+/// Receive the [`Event`]s of an operation, in the order they happen. Reporters can be shared
+/// between threads.
+pub trait Reporter: Send + Sync {
+    fn report(&self, event: Event);
+}
+
+/// A [`Reporter`] discarding every event
+pub struct SilentReporter;
+
+impl Reporter for SilentReporter {
+    fn report(&self, _event: Event) {}
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use crate::report::{Event, Reporter};
+    use std::sync::Mutex;
+
+    /// a [`Reporter`] keeping the events, to check them
+    #[derive(Default)]
+    pub struct RecordReporter {
+        pub events: Mutex<Vec<Event>>,
+    }
+
+    impl RecordReporter {
+        /// the events reported until now
+        pub fn events(&self) -> Vec<Event> {
+            self.events.lock().unwrap().clone()
+        }
+    }
+
+    impl Reporter for RecordReporter {
+        fn report(&self, event: Event) {
+            self.events.lock().unwrap().push(event);
+        }
+    }
+}
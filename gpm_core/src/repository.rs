@@ -0,0 +1,351 @@
+//! A local package repository: a directory of package archives, with an index file listing them.
+//!
+//! It can be shared between users with a network drive, and doesn't need any server.
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+use crate::checksum::sha256_file;
+use crate::constants::REPOSITORY_INDEX_PATH;
+use crate::package::PackageDependency;
+use crate::package_reader::{PackageReader, ReadPackageError};
+use crate::resolver::{IndexedPackage, PackageIndex};
+use crate::store_project::{deserialize_dependencies, StoredDependency};
+
+use semver::Version;
+use serde::{Deserialize, Serialize};
+
+/// A package archive stored in a [`LocalRepository`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct RepositoryEntry {
+    pub identifier: String,
+    pub version: Version,
+    /// the path of the archive, relative to the repository directory
+    pub file: PathBuf,
+    /// the hex-encoded SHA-256 hash of the archive
+    pub checksum: String,
+    pub dependencies: Vec<PackageDependency>,
+    pub tags: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoredRepositoryEntry {
+    identifier: String,
+    version: Version,
+    file: PathBuf,
+    checksum: String,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default, deserialize_with = "deserialize_dependencies")]
+    dependencies: BTreeMap<String, StoredDependency>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct StoredRepositoryIndex {
+    #[serde(default)]
+    packages: Vec<StoredRepositoryEntry>,
+}
+
+impl From<StoredRepositoryEntry> for RepositoryEntry {
+    fn from(stored: StoredRepositoryEntry) -> Self {
+        Self {
+            identifier: stored.identifier,
+            version: stored.version,
+            file: stored.file,
+            checksum: stored.checksum,
+            dependencies: stored
+                .dependencies
+                .into_iter()
+                .map(|(identifier, dependency)| dependency.into_package_dependency(identifier))
+                .collect(),
+            tags: stored.tags,
+        }
+    }
+}
+
+impl From<&RepositoryEntry> for StoredRepositoryEntry {
+    fn from(entry: &RepositoryEntry) -> Self {
+        Self {
+            identifier: entry.identifier.clone(),
+            version: entry.version.clone(),
+            file: entry.file.clone(),
+            checksum: entry.checksum.clone(),
+            tags: entry.tags.clone(),
+            dependencies: entry
+                .dependencies
+                .iter()
+                .map(|dependency| (dependency.identifier.clone(), dependency.into()))
+                .collect(),
+        }
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum RepositoryError {
+    #[error("io error with the file {0}")]
+    FileIOError(PathBuf, #[source] io::Error),
+    #[error("the repository directory {0} doesn't exist")]
+    MissingRepositoryError(PathBuf),
+    #[error("error while parsing the repository index {0}")]
+    TomlDecodeError(PathBuf, #[source] toml::de::Error),
+    #[error("can't encode the repository index. Probably internal error")]
+    TomlEncodeError(#[source] toml::ser::Error),
+    #[error("error while reading the package archive {0}")]
+    ReadPackageError(PathBuf, #[source] ReadPackageError),
+    #[error("the package archive {0} doesn't have an identifier or a version")]
+    MissingIdentifierError(PathBuf),
+    #[error("the package archive {0} has the identifier {1:?}, that can't be used as a file name")]
+    InvalidIdentifierError(PathBuf, String),
+    #[error("the package {0} {1} is already in the repository")]
+    AlreadyExistError(String, Version),
+    #[error("the archive {0} doesn't match the checksum of the repository index, it may have been modified or corrupted")]
+    ChecksumMismatchError(PathBuf),
+}
+
+/// A directory of package archives, along with a [`REPOSITORY_INDEX_PATH`] file that list them
+#[derive(Debug)]
+pub struct LocalRepository {
+    path: PathBuf,
+    entries: Vec<RepositoryEntry>,
+}
+
+impl LocalRepository {
+    /// load the repository in the given directory. A repository without an index file is
+    /// considered empty.
+    pub fn open(path: &Path) -> Result<Self, RepositoryError> {
+        if !path.is_dir() {
+            return Err(RepositoryError::MissingRepositoryError(path.to_path_buf()));
+        };
+        let index_path = path.join(REPOSITORY_INDEX_PATH);
+        let stored_index = match std::fs::read(&index_path) {
+            Ok(content) => toml::from_slice::<StoredRepositoryIndex>(&content)
+                .map_err(|err| RepositoryError::TomlDecodeError(index_path.clone(), err))?,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => StoredRepositoryIndex::default(),
+            Err(err) => return Err(RepositoryError::FileIOError(index_path, err)),
+        };
+        Ok(Self {
+            path: path.to_path_buf(),
+            entries: stored_index.packages.into_iter().map(Into::into).collect(),
+        })
+    }
+
+    /// create a new, empty repository in the given directory (creating it if needed), or open
+    /// the existing one.
+    pub fn create(path: &Path) -> Result<Self, RepositoryError> {
+        std::fs::create_dir_all(path)
+            .map_err(|err| RepositoryError::FileIOError(path.to_path_buf(), err))?;
+        let repository = Self::open(path)?;
+        repository.write_index()?;
+        Ok(repository)
+    }
+
+    /// the directory of this repository
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// every package stored in this repository
+    pub fn entries(&self) -> &[RepositoryEntry] {
+        &self.entries
+    }
+
+    /// return the entry with the given identifier and version, if it exist
+    pub fn find(&self, identifier: &str, version: &Version) -> Option<&RepositoryEntry> {
+        self.entries
+            .iter()
+            .find(|entry| entry.identifier == identifier && &entry.version == version)
+    }
+
+    /// return every entry with the given tag
+    pub fn find_by_tag(&self, tag: &str) -> Vec<&RepositoryEntry> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.tags.iter().any(|entry_tag| entry_tag == tag))
+            .collect()
+    }
+
+    /// the absolute path of the archive of ``entry``
+    pub fn archive_path(&self, entry: &RepositoryEntry) -> PathBuf {
+        self.path.join(&entry.file)
+    }
+
+    /// open the archive of ``entry``, after checking it match the checksum of the index
+    pub fn open_archive(
+        &self,
+        entry: &RepositoryEntry,
+    ) -> Result<PackageReader<io::BufReader<File>>, RepositoryError> {
+        let archive_path = self.archive_path(entry);
+        let checksum = sha256_file(&archive_path)
+            .map_err(|err| RepositoryError::FileIOError(archive_path.clone(), err))?;
+        if checksum != entry.checksum {
+            return Err(RepositoryError::ChecksumMismatchError(archive_path));
+        };
+        PackageReader::open(&archive_path)
+            .map_err(|err| RepositoryError::ReadPackageError(archive_path, err))
+    }
+
+    /// copy the package archive at ``archive_path`` in this repository, and add it to the index.
+    ///
+    /// Fail if a package with the same identifier and version is already present, or if the
+    /// identifier isn't valid, as it is part of the name of the copied archive.
+    pub fn add_archive(
+        &mut self,
+        archive_path: &Path,
+    ) -> Result<&RepositoryEntry, RepositoryError> {
+        let reader = PackageReader::open(archive_path)
+            .map_err(|err| RepositoryError::ReadPackageError(archive_path.to_path_buf(), err))?;
+        let format = reader.format();
+        let information = reader.into_package().information;
+        let (identifier, version) = match (information.identifier, information.version) {
+            (Some(identifier), Some(version)) => (identifier, version),
+            _ => {
+                return Err(RepositoryError::MissingIdentifierError(
+                    archive_path.to_path_buf(),
+                ))
+            }
+        };
+        if Path::new(&identifier)
+            .components()
+            .map(|component| matches!(component, Component::Normal(_)))
+            .ne([true])
+        {
+            return Err(RepositoryError::InvalidIdentifierError(
+                archive_path.to_path_buf(),
+                identifier,
+            ));
+        };
+        if self.find(&identifier, &version).is_some() {
+            return Err(RepositoryError::AlreadyExistError(identifier, version));
+        };
+
+        let checksum = sha256_file(archive_path)
+            .map_err(|err| RepositoryError::FileIOError(archive_path.to_path_buf(), err))?;
+        let file = PathBuf::from(format!("{}-{}.{}", identifier, version, format.name()));
+        let destination = self.path.join(&file);
+        std::fs::copy(archive_path, &destination)
+            .map_err(|err| RepositoryError::FileIOError(destination.clone(), err))?;
+
+        self.entries.push(RepositoryEntry {
+            identifier,
+            version,
+            file,
+            checksum,
+            dependencies: information.dependencies,
+            tags: information.tags,
+        });
+        if let Err(err) = self.write_index() {
+            self.entries.pop();
+            let _ = std::fs::remove_file(&destination);
+            return Err(err);
+        };
+        Ok(self.entries.last().unwrap()) //unwrap: just pushed
+    }
+
+    /// write the index file, replacing it atomically
+    fn write_index(&self) -> Result<(), RepositoryError> {
+        let stored_index = StoredRepositoryIndex {
+            packages: self.entries.iter().map(Into::into).collect(),
+        };
+        let content = toml::to_vec(&stored_index).map_err(RepositoryError::TomlEncodeError)?;
+        let index_path = self.path.join(REPOSITORY_INDEX_PATH);
+        let temporary_path = index_path.with_extension("toml.tmp");
+        std::fs::write(&temporary_path, content)
+            .map_err(|err| RepositoryError::FileIOError(temporary_path.clone(), err))?;
+        std::fs::rename(&temporary_path, &index_path)
+            .map_err(|err| RepositoryError::FileIOError(index_path, err))
+    }
+}
+
+impl PackageIndex for LocalRepository {
+    fn versions(&self, identifier: &str) -> Vec<IndexedPackage> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.identifier == identifier)
+            .map(|entry| IndexedPackage {
+                identifier: entry.identifier.clone(),
+                version: entry.version.clone(),
+                dependencies: entry.dependencies.clone(),
+                checksum: Some(entry.checksum.clone()),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::install_strategy::StrategySet;
+    use crate::package_writer::{create_package, PackageOptions};
+    use crate::repository::{LocalRepository, RepositoryError};
+    use crate::resolver::PackageIndex;
+    use crate::test_utils::test_mod_path;
+    use semver::Version;
+    use std::fs::{self, File};
+
+    #[test]
+    fn test_local_repository() {
+        let work_dir = tempfile::tempdir().unwrap();
+        let archive_path = work_dir.path().join("test_mod.zip");
+        create_package(
+            &test_mod_path(),
+            &mut File::create(&archive_path).unwrap(),
+            &PackageOptions::new(&StrategySet::default()),
+        )
+        .unwrap();
+
+        let repository_path = work_dir.path().join("repository");
+        let mut repository = LocalRepository::create(&repository_path).unwrap();
+        let entry = repository.add_archive(&archive_path).unwrap().clone();
+        assert_eq!(entry.identifier, "test_mod");
+        assert!(matches!(
+            repository.add_archive(&archive_path),
+            Err(RepositoryError::AlreadyExistError(_, _))
+        ));
+
+        let repository = LocalRepository::open(&repository_path).unwrap();
+        assert_eq!(repository.entries().len(), 1);
+        assert_eq!(repository.entries()[0], entry);
+        assert_eq!(repository.versions("test_mod").len(), 1);
+        assert!(repository
+            .find("test_mod", &Version::new(0, 0, 0))
+            .is_some());
+        repository.open_archive(&entry).unwrap();
+
+        fs::write(repository.archive_path(&entry), b"tampered").unwrap();
+        assert!(matches!(
+            repository.open_archive(&entry),
+            Err(RepositoryError::ChecksumMismatchError(_))
+        ));
+    }
+
+    #[test]
+    fn test_add_traversal_identifier() {
+        let work_dir = tempfile::tempdir().unwrap();
+        let project = work_dir.path().join("project");
+        fs::create_dir(&project).unwrap();
+        fs::write(
+            project.join("config.toml"),
+            "identifier = \"../../x\"\nversion = \"1.0.0\"\ndisplay_name = \"X\"\n\
+             creator = \"modder\"\ndescription = \"x\"\nlicense = \"MIT\"\n",
+        )
+        .unwrap();
+        let archive_path = work_dir.path().join("x.zip");
+        create_package(
+            &project,
+            &mut File::create(&archive_path).unwrap(),
+            &PackageOptions::new(&StrategySet::default()),
+        )
+        .unwrap();
+
+        let repository_path = work_dir.path().join("a").join("repository");
+        let mut repository = LocalRepository::create(&repository_path).unwrap();
+        assert!(matches!(
+            repository.add_archive(&archive_path),
+            Err(RepositoryError::InvalidIdentifierError(_, identifier)) if identifier == "../../x"
+        ));
+        assert!(repository.entries().is_empty());
+        assert!(!work_dir.path().join("x-1.0.0.zip").exists());
+        assert_eq!(fs::read_dir(&repository_path).unwrap().count(), 1);
+    }
+}
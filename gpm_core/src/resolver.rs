@@ -0,0 +1,479 @@
+//! Compute the set of packages (and their version) needed by a package, as a [`LockFile`].
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fmt;
+use std::path::PathBuf;
+
+use crate::lockfile::{LockFile, LockSource};
+use crate::package::{DependencySource, PackageDependency};
+
+use semver::{Comparator, Op, Version, VersionReq};
+
+/// A specific version of a package, as known by a [`PackageIndex`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndexedPackage {
+    pub identifier: String,
+    pub version: Version,
+    pub dependencies: Vec<PackageDependency>,
+    /// the hex-encoded SHA-256 hash of the package archive, if known. It is recorded in the
+    /// [`LockFile`].
+    pub checksum: Option<String>,
+}
+
+/// A source of available packages, used by [`resolve`]
+pub trait PackageIndex {
+    /// return every known version of the package with the given identifier, in any order.
+    fn versions(&self, identifier: &str) -> Vec<IndexedPackage>;
+}
+
+/// A [`PackageIndex`] stored in memory
+#[derive(Debug, Default)]
+pub struct MemoryIndex {
+    packages: HashMap<String, Vec<IndexedPackage>>,
+}
+
+impl MemoryIndex {
+    /// create a new [`MemoryIndex`] with no packages.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// add a package to this index, replacing the one with the same identifier and version if any.
+    pub fn add(&mut self, package: IndexedPackage) {
+        let versions = self.packages.entry(package.identifier.clone()).or_default();
+        versions.retain(|existing| existing.version != package.version);
+        versions.push(package);
+    }
+}
+
+impl PackageIndex for MemoryIndex {
+    fn versions(&self, identifier: &str) -> Vec<IndexedPackage> {
+        self.packages.get(identifier).cloned().unwrap_or_default()
+    }
+}
+
+/// A requirement on a package, with the list of packages that lead to it
+#[derive(Debug, Clone, PartialEq)]
+pub struct RequirementChain {
+    /// the packages that transitively require this one, starting from the root package's direct
+    /// dependency. Empty if it is required by the root package.
+    pub required_by: Vec<(String, Version)>,
+    pub dependency: PackageDependency,
+}
+
+impl fmt::Display for RequirementChain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.required_by.is_empty() {
+            write!(f, "the root package")?;
+        } else {
+            let chain = self
+                .required_by
+                .iter()
+                .map(|(identifier, version)| format!("{} {}", identifier, version))
+                .collect::<Vec<_>>()
+                .join(" -> ");
+            write!(f, "{}", chain)?;
+        };
+        write!(
+            f,
+            " {} {} {}",
+            if self.dependency.optional {
+                "optionally requires"
+            } else {
+                "requires"
+            },
+            self.dependency.identifier,
+            self.dependency.version
+        )
+    }
+}
+
+fn format_chains(chains: &[RequirementChain]) -> String {
+    chains
+        .iter()
+        .map(|chain| format!("  - {}", chain))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum ResolveError {
+    #[error("no package named {} is available ({0})", .0.dependency.identifier)]
+    PackageNotFoundError(RequirementChain),
+    #[error("no version of {0} satisfies all the requirements:\n{}", format_chains(.1))]
+    ConflictError(String, Vec<RequirementChain>),
+    #[error("downloading a dependency from an URL isn't supported yet ({0})")]
+    UnsupportedSourceError(RequirementChain),
+    #[error("the dependencies are too complex to be resolved, gave up after {0} requirements")]
+    TooComplexError(usize),
+}
+
+#[derive(Clone)]
+enum Selected {
+    Version(Version),
+    Path(PathBuf),
+}
+
+#[derive(Clone, Default)]
+struct ResolveState {
+    selected: BTreeMap<String, Selected>,
+    /// every requirement encountered to reach this state
+    requirements: Vec<RequirementChain>,
+}
+
+impl ResolveState {
+    fn requirements_of(&self, identifier: &str) -> Vec<RequirementChain> {
+        self.requirements
+            .iter()
+            .filter(|requirement| requirement.dependency.identifier == identifier)
+            .cloned()
+            .collect()
+    }
+
+    fn conflict(&self, identifier: &str) -> ResolveError {
+        ResolveError::ConflictError(identifier.to_string(), self.requirements_of(identifier))
+    }
+}
+
+/// Select a version for each package transitively required by ``dependencies``, so that every
+/// version requirement is satisfied, and return them as a [`LockFile`].
+///
+/// The most recent compatible version of each package is preferred. Optional dependencies are not
+/// installed, but their version requirement is enforced if the package is required by something
+/// else. Dependencies with a [`DependencySource::Path`] source are locked as a
+/// [`LockSource::Path`], without looking at their own dependencies.
+pub fn resolve(
+    dependencies: &[PackageDependency],
+    index: &dyn PackageIndex,
+) -> Result<LockFile, ResolveError> {
+    let pending = dependencies
+        .iter()
+        .map(|dependency| RequirementChain {
+            required_by: Vec::new(),
+            dependency: dependency.clone(),
+        })
+        .collect();
+    let state = solve(index, pending, MAX_RESOLVE_STEPS)?;
+
+    let mut lock_file = LockFile::new();
+    for (identifier, selected) in state.selected {
+        let source = match selected {
+            Selected::Version(version) => LockSource::IdVersion {
+                checksum: index
+                    .versions(&identifier)
+                    .into_iter()
+                    .find(|package| package.version == version)
+                    .and_then(|package| package.checksum),
+                identifier: identifier.clone(),
+                version,
+            },
+            Selected::Path(path) => LockSource::Path {
+                path,
+                checksum: None,
+            },
+        };
+        lock_file.set_dependency_source(identifier, source);
+    }
+    Ok(lock_file)
+}
+
+/// the number of requirements [`resolve`] handles before giving up, the backtracking being
+/// exponential in the worst case
+const MAX_RESOLVE_STEPS: usize = 100_000;
+
+/// What to do after handling a requirement in [`solve`]
+enum Step {
+    /// handle the next pending requirement
+    Next,
+    /// the requirement can't be satisfied in this state
+    Failed(ResolveError),
+    /// try each of these states, the first one first
+    Branch(Vec<(ResolveState, VecDeque<RequirementChain>)>),
+}
+
+/// find a state satisfying ``pending`` with a depth-first search, backtracking to the next
+/// candidate version when a requirement can't be satisfied. The error is the first one found, or
+/// [`ResolveError::TooComplexError`] after ``max_steps`` requirements.
+fn solve(
+    index: &dyn PackageIndex,
+    pending: VecDeque<RequirementChain>,
+    max_steps: usize,
+) -> Result<ResolveState, ResolveError> {
+    let mut stack = vec![(ResolveState::default(), pending)];
+    let mut first_error = None;
+    let mut steps = 0;
+    'states: while let Some((mut state, mut pending)) = stack.pop() {
+        while let Some(requirement) = pending.pop_front() {
+            steps += 1;
+            if steps > max_steps {
+                return Err(ResolveError::TooComplexError(max_steps));
+            };
+            match solve_requirement(index, &mut state, &pending, requirement) {
+                Step::Next => (),
+                Step::Failed(err) => {
+                    first_error.get_or_insert(err);
+                    continue 'states;
+                }
+                Step::Branch(candidates) => {
+                    stack.extend(candidates.into_iter().rev());
+                    continue 'states;
+                }
+            };
+        }
+        return Ok(state);
+    }
+    Err(first_error.expect("the search only stops without a solution after a failure"))
+}
+
+/// handle ``requirement`` in ``state``, ``pending`` being the requirements handled after it
+fn solve_requirement(
+    index: &dyn PackageIndex,
+    state: &mut ResolveState,
+    pending: &VecDeque<RequirementChain>,
+    requirement: RequirementChain,
+) -> Step {
+    let dependency = requirement.dependency.clone();
+    let identifier = dependency.identifier.as_str();
+    state.requirements.push(requirement.clone());
+
+    match (state.selected.get(identifier), &dependency.source) {
+        (_, DependencySource::Url(_)) if !dependency.optional => {
+            return Step::Failed(ResolveError::UnsupportedSourceError(requirement))
+        }
+        (Some(Selected::Version(version)), DependencySource::Repository) => {
+            return if dependency.version.matches(version) {
+                Step::Next
+            } else {
+                Step::Failed(state.conflict(identifier))
+            };
+        }
+        (Some(Selected::Path(selected_path)), DependencySource::Path(path))
+            if selected_path == path =>
+        {
+            return Step::Next
+        }
+        (Some(_), _) if dependency.optional => return Step::Next,
+        (Some(_), _) => return Step::Failed(state.conflict(identifier)),
+        (None, _) if dependency.optional => return Step::Next,
+        (None, DependencySource::Path(path)) => {
+            state
+                .selected
+                .insert(identifier.to_string(), Selected::Path(path.clone()));
+            return Step::Next;
+        }
+        // non-optional URL dependencies are refused above
+        (None, DependencySource::Repository) | (None, DependencySource::Url(_)) => (),
+    };
+
+    let mut candidates = index.versions(identifier);
+    if candidates.is_empty() {
+        return Step::Failed(ResolveError::PackageNotFoundError(requirement));
+    };
+    let requirements = state.requirements_of(identifier);
+    candidates.retain(|candidate| {
+        requirements
+            .iter()
+            .all(|requirement| requirement.dependency.version.matches(&candidate.version))
+    });
+    if candidates.is_empty() {
+        return Step::Failed(state.conflict(identifier));
+    };
+    candidates.sort_by(|first, second| second.version.cmp(&first.version));
+
+    Step::Branch(
+        candidates
+            .into_iter()
+            .map(|candidate| {
+                let mut candidate_state = state.clone();
+                candidate_state.selected.insert(
+                    identifier.to_string(),
+                    Selected::Version(candidate.version.clone()),
+                );
+                let mut candidate_pending = pending.clone();
+                let mut required_by = requirement.required_by.clone();
+                required_by.push((identifier.to_string(), candidate.version.clone()));
+                for candidate_dependency in candidate.dependencies {
+                    candidate_pending.push_back(RequirementChain {
+                        required_by: required_by.clone(),
+                        dependency: candidate_dependency,
+                    });
+                }
+                (candidate_state, candidate_pending)
+            })
+            .collect(),
+    )
+}
+
+/// return a [`VersionReq`] only matching ``version``
+pub fn exact_requirement(version: &Version) -> VersionReq {
+    VersionReq {
+        comparators: vec![Comparator {
+            op: Op::Exact,
+            major: version.major,
+            minor: Some(version.minor),
+            patch: Some(version.patch),
+            pre: version.pre.clone(),
+        }],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::lockfile::LockSource;
+    use crate::package::{DependencySource, PackageDependency};
+    use crate::resolver::{
+        resolve, solve, IndexedPackage, MemoryIndex, RequirementChain, ResolveError,
+    };
+    use semver::{Version, VersionReq};
+
+    fn dependency(identifier: &str, version: &str) -> PackageDependency {
+        PackageDependency::new(identifier, VersionReq::parse(version).unwrap())
+    }
+
+    fn package(identifier: &str, version: &str, dependencies: &[(&str, &str)]) -> IndexedPackage {
+        IndexedPackage {
+            identifier: identifier.into(),
+            version: Version::parse(version).unwrap(),
+            dependencies: dependencies
+                .iter()
+                .map(|(identifier, version)| dependency(identifier, version))
+                .collect(),
+            checksum: None,
+        }
+    }
+
+    fn locked_version(source: Option<LockSource>) -> String {
+        match source {
+            Some(LockSource::IdVersion { version, .. }) => version.to_string(),
+            _ => panic!("the dependency isn't locked to a version"),
+        }
+    }
+
+    #[test]
+    fn test_resolve() {
+        let mut index = MemoryIndex::new();
+        index.add(package("cet_core", "1.8.0", &[]));
+        index.add(package("cet_core", "1.9.2", &[]));
+        index.add(package("cet_core", "2.0.0", &[]));
+        index.add(package("ui_lib", "1.0.0", &[("cet_core", ">=1.9")]));
+        index.add(package("ui_lib", "1.1.0", &[("cet_core", ">=2")]));
+        index.add(package("my_mod", "0.1.0", &[("ui_lib", "1")]));
+
+        // the most recent ui_lib require cet_core 2, so it need to backtrack to ui_lib 1.0.0
+        let lock_file = resolve(
+            &[
+                dependency("my_mod", "*"),
+                dependency("cet_core", ">=1.9, <2"),
+            ],
+            &index,
+        )
+        .unwrap();
+        assert_eq!(lock_file.dependencies.len(), 3);
+        assert_eq!(
+            locked_version(lock_file.dependency_source("my_mod")),
+            "0.1.0"
+        );
+        assert_eq!(
+            locked_version(lock_file.dependency_source("ui_lib")),
+            "1.0.0"
+        );
+        assert_eq!(
+            locked_version(lock_file.dependency_source("cet_core")),
+            "1.9.2"
+        );
+
+        let lock_file = resolve(&[dependency("my_mod", "*")], &index).unwrap();
+        assert_eq!(
+            locked_version(lock_file.dependency_source("ui_lib")),
+            "1.1.0"
+        );
+        assert_eq!(
+            locked_version(lock_file.dependency_source("cet_core")),
+            "2.0.0"
+        );
+    }
+
+    #[test]
+    fn test_resolve_optional_and_path() {
+        let mut index = MemoryIndex::new();
+        index.add(package("cet_core", "1.0.0", &[]));
+        index.add(package("cet_core", "2.0.0", &[]));
+
+        let mut optional = dependency("cet_core", "1");
+        optional.optional = true;
+        let mut local = dependency("local", "*");
+        local.source = DependencySource::Path("../local".into());
+
+        let lock_file = resolve(&[optional.clone(), local], &index).unwrap();
+        assert!(lock_file.dependency_source("cet_core").is_none());
+        assert_eq!(
+            lock_file.dependency_source("local"),
+            Some(LockSource::Path {
+                path: "../local".into(),
+                checksum: None,
+            })
+        );
+
+        let lock_file = resolve(&[optional, dependency("cet_core", "*")], &index).unwrap();
+        assert_eq!(
+            locked_version(lock_file.dependency_source("cet_core")),
+            "1.0.0"
+        );
+    }
+
+    #[test]
+    fn test_resolve_error() {
+        let mut index = MemoryIndex::new();
+        index.add(package("cet_core", "1.0.0", &[]));
+        index.add(package("ui_lib", "1.0.0", &[("cet_core", ">=2")]));
+
+        match resolve(&[dependency("ui_lib", "1")], &index) {
+            Err(ResolveError::ConflictError(identifier, chains)) => {
+                assert_eq!(identifier, "cet_core");
+                assert_eq!(chains.len(), 1);
+                assert_eq!(chains[0].to_string(), "ui_lib 1.0.0 requires cet_core >=2");
+            }
+            _ => panic!("the conflict wasn't detected"),
+        };
+
+        match resolve(&[dependency("missing", "1")], &index) {
+            Err(ResolveError::PackageNotFoundError(chain)) => {
+                assert!(chain.required_by.is_empty());
+                assert_eq!(chain.to_string(), "the root package requires missing ^1");
+            }
+            _ => panic!("the missing package wasn't detected"),
+        };
+    }
+
+    #[test]
+    fn test_resolve_limit() {
+        // every combination of versions is tried before finding that b 2 doesn't exist
+        let mut index = MemoryIndex::new();
+        let identifiers = (0..10)
+            .map(|index| format!("a{}", index))
+            .collect::<Vec<_>>();
+        for identifier in &identifiers {
+            index.add(package(identifier, "1.0.0", &[]));
+            index.add(package(identifier, "2.0.0", &[]));
+        }
+        index.add(package("b", "1.0.0", &[]));
+        let pending = identifiers
+            .iter()
+            .map(|identifier| dependency(identifier, "*"))
+            .chain(std::iter::once(dependency("b", "2")))
+            .map(|dependency| RequirementChain {
+                required_by: Vec::new(),
+                dependency,
+            })
+            .collect();
+
+        assert!(matches!(
+            solve(&index, pending, 1000),
+            Err(ResolveError::TooComplexError(1000))
+        ));
+        match resolve(&[dependency("a0", "*"), dependency("b", "2")], &index) {
+            Err(ResolveError::ConflictError(identifier, _)) => assert_eq!(identifier, "b"),
+            _ => panic!("the conflict wasn't detected"),
+        };
+    }
+}
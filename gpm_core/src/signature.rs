@@ -0,0 +1,347 @@
+//! Sign package archives with ed25519 keys, and check their signature against the keys trusted
+//! by a profile.
+//!
+//! The signature cover the [`JSON_CONFIG_PATH`] and [`CHECKSUMS_PATH`] files of the archive, and
+//! so, through the checksums, every file of the package. It is stored in the
+//! [`crate::constants::SIGNATURE_PATH`] file of the archive, along with the public key of the author.
+//!
+//! Keys are stored as hex-encoded text files: the secret key in a file, and the public key in
+//! the same file with a `.pub` extension added.
+
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use crate::checksum::sha256_bytes;
+use crate::constants::{CHECKSUMS_PATH, JSON_CONFIG_PATH};
+use crate::profile::Profile;
+
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use serde::{Deserialize, Serialize};
+
+/// the file, in the profile folder, listing the public keys trusted by the profile
+pub const PROFILE_TRUSTED_KEYS_PATH: &str = "trusted_keys.toml";
+/// the extension added to a secret key path to get the path of its public key
+pub const PUBLIC_KEY_EXTENSION: &str = "pub";
+
+#[derive(thiserror::Error, Debug)]
+pub enum SignatureError {
+    #[error("io error with the file {0}")]
+    FileIOError(PathBuf, #[source] io::Error),
+    #[error("the key in {0} isn't a valid hex-encoded ed25519 key")]
+    InvalidKeyError(PathBuf),
+    #[error("{0:?} isn't a valid hex-encoded ed25519 public key")]
+    InvalidPublicKeyError(String),
+    #[error("the key file {0} already exist")]
+    KeyAlreadyExistError(PathBuf),
+    #[error("error while parsing the trusted keys file {0}")]
+    TomlDecodeError(PathBuf, #[source] toml::de::Error),
+    #[error("can't encode the trusted keys file. Probably internal error")]
+    TomlEncodeError(#[source] toml::ser::Error),
+}
+
+/// The content of the [`crate::constants::SIGNATURE_PATH`] file of a package archive
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PackageSignature {
+    /// the hex-encoded public key of the signer
+    pub public_key: String,
+    /// the hex-encoded ed25519 signature of the message returned by [`signed_message`]
+    pub signature: String,
+}
+
+/// How a profile handle the signature of the packages it install
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum SignaturePolicy {
+    /// refuse packages that aren't signed by a trusted key, including mod project directories,
+    /// that can't be signed
+    Strict,
+    /// install packages that aren't signed by a trusted key, with a warning. Packages with an
+    /// invalid signature are still refused. Mod project directories are installed without
+    /// warning.
+    #[default]
+    Warn,
+    /// don't check signatures
+    Off,
+}
+
+impl fmt::Display for SignaturePolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Strict => "strict",
+            Self::Warn => "warn",
+            Self::Off => "off",
+        })
+    }
+}
+
+impl std::str::FromStr for SignaturePolicy {
+    type Err = String;
+
+    fn from_str(policy: &str) -> Result<Self, Self::Err> {
+        match policy {
+            "strict" => Ok(Self::Strict),
+            "warn" => Ok(Self::Warn),
+            "off" => Ok(Self::Off),
+            _ => Err(format!(
+                "unknown signature policy {:?}, expected strict, warn or off",
+                policy
+            )),
+        }
+    }
+}
+
+/// The result of checking the signature of a package
+#[derive(Debug, Clone, PartialEq)]
+pub enum SignatureStatus {
+    /// the package doesn't have a signature
+    Unsigned,
+    /// the signature doesn't match the content of the package, or is malformed
+    Invalid,
+    /// the signature is valid, but made with a key that isn't trusted. Contain the hex-encoded
+    /// public key.
+    Untrusted(String),
+    /// the signature is valid, and made with the trusted key of this name
+    Trusted(String),
+}
+
+impl fmt::Display for SignatureStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unsigned => write!(f, "isn't signed"),
+            Self::Invalid => write!(f, "has an invalid signature"),
+            Self::Untrusted(public_key) => {
+                write!(f, "is signed by the untrusted key {}", public_key)
+            }
+            Self::Trusted(name) => write!(f, "is signed by {}", name),
+        }
+    }
+}
+
+/// the message signed for a package, from the content of its [`JSON_CONFIG_PATH`] and
+/// [`CHECKSUMS_PATH`] files
+pub fn signed_message(config_content: &[u8], checksums_content: &[u8]) -> Vec<u8> {
+    format!(
+        "gpm package signature v1\n{} {}\n{} {}\n",
+        JSON_CONFIG_PATH,
+        sha256_bytes(config_content),
+        CHECKSUMS_PATH,
+        sha256_bytes(checksums_content)
+    )
+    .into_bytes()
+}
+
+/// generate a new random signing key
+pub fn generate_key() -> SigningKey {
+    SigningKey::generate(&mut rand_core::OsRng)
+}
+
+/// the path of the public key associated with the secret key at ``secret_path``
+pub fn public_key_path(secret_path: &Path) -> PathBuf {
+    let mut path = secret_path.as_os_str().to_owned();
+    path.push(".");
+    path.push(PUBLIC_KEY_EXTENSION);
+    PathBuf::from(path)
+}
+
+/// write ``key`` at ``secret_path``, and its public key next to it. Existing files aren't
+/// overwritten. On unix, the secret key is only readable by its owner.
+pub fn write_key(key: &SigningKey, secret_path: &Path) -> Result<(), SignatureError> {
+    let public_path = public_key_path(secret_path);
+    if public_path.exists() {
+        return Err(SignatureError::KeyAlreadyExistError(public_path));
+    };
+    create_key_file(secret_path, &hex::encode(key.to_bytes()), true)?;
+    let result = create_key_file(
+        &public_path,
+        &hex::encode(key.verifying_key().to_bytes()),
+        false,
+    );
+    if result.is_err() {
+        let _ = fs::remove_file(secret_path);
+    };
+    result
+}
+
+/// create the file at ``path`` with ``content``, failing if it already exist. A ``secret`` file
+/// is created with permissions restricted to its owner, so it is never readable by others.
+fn create_key_file(path: &Path, content: &str, secret: bool) -> Result<(), SignatureError> {
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        if secret {
+            options.mode(0o600);
+        };
+    }
+    #[cfg(not(unix))]
+    let _ = secret;
+    let mut file = options.open(path).map_err(|err| match err.kind() {
+        io::ErrorKind::AlreadyExists => SignatureError::KeyAlreadyExistError(path.to_path_buf()),
+        _ => SignatureError::FileIOError(path.to_path_buf(), err),
+    })?;
+    file.write_all(content.as_bytes())
+        .map_err(|err| SignatureError::FileIOError(path.to_path_buf(), err))
+}
+
+/// read the secret key written by [`write_key`] at ``path``
+pub fn load_signing_key(path: &Path) -> Result<SigningKey, SignatureError> {
+    let content = fs::read_to_string(path)
+        .map_err(|err| SignatureError::FileIOError(path.to_path_buf(), err))?;
+    let bytes = hex::decode(content.trim())
+        .ok()
+        .and_then(|bytes| <[u8; 32]>::try_from(bytes.as_slice()).ok())
+        .ok_or_else(|| SignatureError::InvalidKeyError(path.to_path_buf()))?;
+    Ok(SigningKey::from_bytes(&bytes))
+}
+
+/// parse a hex-encoded public key
+pub fn parse_public_key(public_key: &str) -> Result<VerifyingKey, SignatureError> {
+    hex::decode(public_key.trim())
+        .ok()
+        .and_then(|bytes| <[u8; 32]>::try_from(bytes.as_slice()).ok())
+        .and_then(|bytes| VerifyingKey::from_bytes(&bytes).ok())
+        .ok_or_else(|| SignatureError::InvalidPublicKeyError(public_key.to_string()))
+}
+
+/// sign ``message`` with ``key``
+pub fn sign(key: &SigningKey, message: &[u8]) -> PackageSignature {
+    PackageSignature {
+        public_key: hex::encode(key.verifying_key().to_bytes()),
+        signature: hex::encode(key.sign(message).to_bytes()),
+    }
+}
+
+/// check that ``signature`` is a valid signature of ``message`` by its public key
+pub fn is_valid(signature: &PackageSignature, message: &[u8]) -> bool {
+    let public_key = match parse_public_key(&signature.public_key) {
+        Ok(public_key) => public_key,
+        Err(_) => return false,
+    };
+    hex::decode(&signature.signature)
+        .ok()
+        .and_then(|bytes| Signature::from_slice(&bytes).ok())
+        .map(|decoded| public_key.verify(message, &decoded).is_ok())
+        .unwrap_or(false)
+}
+
+/// The public keys trusted by a profile, by name
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct TrustedKeys {
+    /// the hex-encoded public keys, by name
+    #[serde(default)]
+    pub keys: BTreeMap<String, String>,
+}
+
+impl TrustedKeys {
+    /// load the trusted keys of ``profile``. A profile without trusted keys file trust no key.
+    pub fn load(profile: &Profile) -> Result<Self, SignatureError> {
+        let path = profile.path.join(PROFILE_TRUSTED_KEYS_PATH);
+        match fs::read(&path) {
+            Ok(content) => {
+                toml::from_slice(&content).map_err(|err| SignatureError::TomlDecodeError(path, err))
+            }
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(SignatureError::FileIOError(path, err)),
+        }
+    }
+
+    /// write the trusted keys of ``profile``, replacing the file atomically
+    pub fn save(&self, profile: &Profile) -> Result<(), SignatureError> {
+        let path = profile.path.join(PROFILE_TRUSTED_KEYS_PATH);
+        let content = toml::to_vec(self).map_err(SignatureError::TomlEncodeError)?;
+        let temporary_path = path.with_extension("toml.tmp");
+        fs::write(&temporary_path, content)
+            .map_err(|err| SignatureError::FileIOError(temporary_path.clone(), err))?;
+        fs::rename(&temporary_path, &path).map_err(|err| SignatureError::FileIOError(path, err))
+    }
+
+    /// trust the hex-encoded ``public_key`` under ``name``, replacing the key with this name
+    pub fn add(&mut self, name: &str, public_key: &str) -> Result<(), SignatureError> {
+        parse_public_key(public_key)?;
+        self.keys
+            .insert(name.to_string(), public_key.trim().to_lowercase());
+        Ok(())
+    }
+
+    /// check ``signature`` of ``message`` against the trusted keys
+    pub fn check(&self, signature: Option<&PackageSignature>, message: &[u8]) -> SignatureStatus {
+        let signature = match signature {
+            Some(signature) => signature,
+            None => return SignatureStatus::Unsigned,
+        };
+        if !is_valid(signature, message) {
+            return SignatureStatus::Invalid;
+        };
+        let public_key = signature.public_key.to_lowercase();
+        match self.keys.iter().find(|(_, key)| **key == public_key) {
+            Some((name, _)) => SignatureStatus::Trusted(name.clone()),
+            None => SignatureStatus::Untrusted(public_key),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::signature::{generate_key, load_signing_key, public_key_path, write_key};
+    use crate::signature::{sign, signed_message, SignatureError, SignatureStatus, TrustedKeys};
+
+    #[test]
+    fn test_signature() {
+        let key = generate_key();
+        let message = signed_message(b"{}", b"{\"file.txt\": \"0000\"}");
+        let signature = sign(&key, &message);
+
+        let mut trusted = TrustedKeys::default();
+        assert_eq!(trusted.check(None, &message), SignatureStatus::Unsigned);
+        assert_eq!(
+            trusted.check(Some(&signature), &message),
+            SignatureStatus::Untrusted(signature.public_key.clone())
+        );
+        trusted.add("author", &signature.public_key).unwrap();
+        assert_eq!(
+            trusted.check(Some(&signature), &message),
+            SignatureStatus::Trusted("author".to_string())
+        );
+        let tampered = signed_message(b"{}", b"{\"file.txt\": \"1111\"}");
+        assert_eq!(
+            trusted.check(Some(&signature), &tampered),
+            SignatureStatus::Invalid
+        );
+        assert!(trusted.add("broken", "not a key").is_err());
+    }
+
+    #[test]
+    fn test_write_key() {
+        let work_dir = tempfile::tempdir().unwrap();
+        let secret_path = work_dir.path().join("key");
+        let key = generate_key();
+        write_key(&key, &secret_path).unwrap();
+        assert_eq!(
+            load_signing_key(&secret_path).unwrap().to_bytes(),
+            key.to_bytes()
+        );
+        assert!(public_key_path(&secret_path).is_file());
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&secret_path)
+                .unwrap()
+                .permissions()
+                .mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+        assert!(matches!(
+            write_key(&generate_key(), &secret_path),
+            Err(SignatureError::KeyAlreadyExistError(_))
+        ));
+        assert_eq!(
+            load_signing_key(&secret_path).unwrap().to_bytes(),
+            key.to_bytes()
+        );
+    }
+}
@@ -1,12 +1,37 @@
 use std::fs::File;
 use std::io;
-use std::io::{BufReader, Read};
+use std::io::{BufReader, Read, Write};
 use std::path::{Path, PathBuf};
 
-use crate::constants::TOML_CONFIG_PATH;
-use crate::package::{Package, PackageInformation, PackageInformationExtraData};
+use crate::constants::{IGNORE_PATH, TOML_CONFIG_PATH};
+use crate::display::suggestion::did_you_mean;
+use crate::package::{
+    DependencySource, Package, PackageDependency, PackageInformation, PackageInformationExtraData,
+};
 
-use serde::{Deserialize, Serialize};
+use semver::{Version, VersionReq};
+use serde::de::{self, MapAccess, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// the keys of the [`TOML_CONFIG_PATH`] file, in the order they are written
+pub const CONFIG_KEYS: &[&str] = &[
+    "creator",
+    "identifier",
+    "version",
+    "display_name",
+    "description",
+    "license",
+    "website_url",
+    "tags",
+    "install_strategies",
+    "uncompressed",
+    "include",
+    "exclude",
+    "extra_data",
+    "dependencies",
+];
 
 #[derive(Serialize, Deserialize)]
 struct StoredPackageInformation {
@@ -15,7 +40,7 @@ struct StoredPackageInformation {
     #[serde(default)]
     identifier: Option<String>,
     #[serde(default)]
-    version: Option<String>,
+    version: Option<Version>,
     #[serde(default)]
     display_name: Option<String>,
     #[serde(default)]
@@ -26,34 +51,177 @@ struct StoredPackageInformation {
     #[serde(default)]
     website_url: Option<String>,
     #[serde(default)]
-    dependencies: Vec<String>,
-    #[serde(default)]
     tags: Vec<String>,
     #[serde(default)]
     install_strategies: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    uncompressed: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    include: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    exclude: Vec<String>,
     #[serde(default)]
     extra_data: Vec<(String, String)>,
+    // kept last, as TOML tables need to be written after the plain values
+    #[serde(default, deserialize_with = "deserialize_dependencies")]
+    dependencies: BTreeMap<String, StoredDependency>,
+}
+
+/// A dependency, as written in the `[dependencies]` table. It is either a version requirement,
+/// like `cet_core = ">=1.9, <2"`, or a table, like
+/// `cet_core = { version = "1.9", optional = true, path = "../cet_core" }`
+#[derive(Serialize)]
+#[serde(untagged)]
+pub(crate) enum StoredDependency {
+    Version(VersionReq),
+    Detailed(StoredDetailedDependency),
+}
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct StoredDetailedDependency {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    version: Option<VersionReq>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    optional: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    path: Option<PathBuf>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    url: Option<String>,
+}
+
+impl<'de> Deserialize<'de> for StoredDependency {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct StoredDependencyVisitor;
+
+        impl<'de> Visitor<'de> for StoredDependencyVisitor {
+            type Value = StoredDependency;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a version requirement or a dependency table")
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value, E> {
+                VersionReq::parse(value)
+                    .map(StoredDependency::Version)
+                    .map_err(E::custom)
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<Self::Value, A::Error> {
+                let detailed = StoredDetailedDependency::deserialize(
+                    de::value::MapAccessDeserializer::new(map),
+                )?;
+                if detailed.path.is_some() && detailed.url.is_some() {
+                    return Err(de::Error::custom(
+                        "a dependency can't have both a `path` and an `url`",
+                    ));
+                };
+                Ok(StoredDependency::Detailed(detailed))
+            }
+        }
+
+        deserializer.deserialize_any(StoredDependencyVisitor)
+    }
+}
+
+/// Deserialize the `dependencies` field, either as a table of [`StoredDependency`], or as a list
+/// of identifiers, accepting any version (the format used before version requirements).
+pub(crate) fn deserialize_dependencies<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<BTreeMap<String, StoredDependency>, D::Error> {
+    struct DependenciesVisitor;
+
+    impl<'de> Visitor<'de> for DependenciesVisitor {
+        type Value = BTreeMap<String, StoredDependency>;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("a dependency table or a list of identifiers")
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+            let mut dependencies = BTreeMap::new();
+            while let Some(identifier) = seq.next_element::<String>()? {
+                dependencies.insert(identifier, StoredDependency::Version(VersionReq::STAR));
+            }
+            Ok(dependencies)
+        }
+
+        fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<Self::Value, A::Error> {
+            BTreeMap::deserialize(de::value::MapAccessDeserializer::new(map))
+        }
+    }
+
+    deserializer.deserialize_any(DependenciesVisitor)
+}
+
+impl StoredDependency {
+    pub(crate) fn into_package_dependency(self, identifier: String) -> PackageDependency {
+        match self {
+            StoredDependency::Version(version) => PackageDependency {
+                identifier,
+                version,
+                optional: false,
+                source: DependencySource::Repository,
+            },
+            StoredDependency::Detailed(detailed) => PackageDependency {
+                identifier,
+                version: detailed.version.unwrap_or(VersionReq::STAR),
+                optional: detailed.optional,
+                source: match (detailed.path, detailed.url) {
+                    (Some(path), _) => DependencySource::Path(path),
+                    (None, Some(url)) => DependencySource::Url(url),
+                    (None, None) => DependencySource::Repository,
+                },
+            },
+        }
+    }
 }
 
-impl Into<PackageInformation> for StoredPackageInformation {
-    fn into(mut self) -> PackageInformation {
-        let extra_data = self
+impl From<&PackageDependency> for StoredDependency {
+    fn from(dependency: &PackageDependency) -> Self {
+        let mut detailed = StoredDetailedDependency {
+            version: Some(dependency.version.clone()),
+            optional: dependency.optional,
+            ..StoredDetailedDependency::default()
+        };
+        match &dependency.source {
+            DependencySource::Repository if !dependency.optional => {
+                return StoredDependency::Version(dependency.version.clone())
+            }
+            DependencySource::Repository => (),
+            DependencySource::Path(path) => detailed.path = Some(path.clone()),
+            DependencySource::Url(url) => detailed.url = Some(url.clone()),
+        };
+        StoredDependency::Detailed(detailed)
+    }
+}
+
+impl From<StoredPackageInformation> for PackageInformation {
+    fn from(mut stored: StoredPackageInformation) -> Self {
+        let extra_data = stored
             .extra_data
             .drain(..)
             .map(|(key, value)| PackageInformationExtraData { key, value })
             .collect();
 
-        PackageInformation {
-            creator: self.creator,
-            identifier: self.identifier,
-            version: self.version,
-            display_name: self.display_name,
-            description: self.description,
-            license: self.license,
-            website_url: self.website_url,
-            dependencies: self.dependencies,
-            tags: self.tags,
-            install_strategies: self.install_strategies,
+        Self {
+            creator: stored.creator,
+            identifier: stored.identifier,
+            version: stored.version,
+            display_name: stored.display_name,
+            description: stored.description,
+            license: stored.license,
+            website_url: stored.website_url,
+            dependencies: stored
+                .dependencies
+                .into_iter()
+                .map(|(identifier, dependency)| dependency.into_package_dependency(identifier))
+                .collect(),
+            tags: stored.tags,
+            install_strategies: stored.install_strategies,
+            uncompressed: stored.uncompressed,
+            include: stored.include,
+            exclude: stored.exclude,
             extra_data,
         }
     }
@@ -74,9 +242,16 @@ impl From<&PackageInformation> for StoredPackageInformation {
             description: package.description.clone(),
             license: package.license.clone(),
             website_url: package.website_url.clone(),
-            dependencies: package.dependencies.clone(),
+            dependencies: package
+                .dependencies
+                .iter()
+                .map(|dependency| (dependency.identifier.clone(), dependency.into()))
+                .collect(),
             tags: package.tags.clone(),
             install_strategies: package.install_strategies.clone(),
+            uncompressed: package.uncompressed.clone(),
+            include: package.include.clone(),
+            exclude: package.exclude.clone(),
             extra_data,
         }
     }
@@ -86,13 +261,202 @@ impl From<&PackageInformation> for StoredPackageInformation {
 pub enum LoadPackageFromProjectError {
     #[error("io error with the file {0}")]
     FileIOError(PathBuf, io::Error),
-    #[error("error while parsing the toml file {0}")]
-    TomlDecodeError(PathBuf, #[source] toml::de::Error),
+    #[error("error while parsing the toml file {0}{}", .1.as_ref().map(|span| format!(", at {}", span)).unwrap_or_default())]
+    TomlDecodeError(PathBuf, Option<Box<SourceSpan>>, #[source] toml::de::Error),
+}
+
+/// A position in a configuration file, with the content of its line to show it to the user
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct SourceSpan {
+    /// the line of the position, starting at 1
+    pub line: usize,
+    /// the column of the position, in characters, starting at 1
+    pub column: usize,
+    /// the content of the line, without the line break
+    pub source_line: String,
+}
+
+impl SourceSpan {
+    /// the span at the 0-based ``line`` and ``column`` of ``content``, as given by
+    /// [`toml::de::Error::line_col`]. None if ``content`` doesn't have this line.
+    pub fn new(content: &str, line: usize, column: usize) -> Option<Self> {
+        content.lines().nth(line).map(|source_line| Self {
+            line: line + 1,
+            column: column + 1,
+            source_line: source_line.to_string(),
+        })
+    }
+
+    /// the span of a toml error in ``content``, if the error has a position
+    pub fn from_toml_error(content: &str, err: &toml::de::Error) -> Option<Self> {
+        err.line_col()
+            .and_then(|(line, column)| Self::new(content, line, column))
+    }
+
+    /// the span of the top level ``key`` in the toml ``content``, either as a value or as a
+    /// table header
+    pub fn of_key(content: &str, key: &str) -> Option<Self> {
+        let quoted = format!("\"{}\"", key);
+        let mut in_table = false;
+        for (line, source_line) in content.lines().enumerate() {
+            let trimmed = source_line.trim_start();
+            let column = source_line.chars().count() - trimmed.chars().count();
+            let name = if let Some(header) = trimmed.strip_prefix('[') {
+                in_table = true;
+                header
+                    .trim_start_matches('[')
+                    .split([']', '.'])
+                    .next()
+                    .unwrap_or_default() //split always return a first part
+                    .trim()
+            } else if in_table {
+                continue;
+            } else {
+                // the first part of a dotted key, like `license.name = ...`
+                trimmed
+                    .split(['=', '.'])
+                    .next()
+                    .unwrap_or_default() //split always return a first part
+                    .trim()
+            };
+            if name == key || name == quoted {
+                return Self::new(content, line, column);
+            };
+        }
+        None
+    }
+}
+
+impl fmt::Display for SourceSpan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let width = self.line.to_string().len();
+        writeln!(f, "line {}, column {}:", self.line, self.column)?;
+        writeln!(f, "{} | {}", self.line, self.source_line)?;
+        write!(
+            f,
+            "{:width$} | {:column$}^",
+            "",
+            "",
+            width = width,
+            column = self.column - 1
+        )
+    }
+}
+
+/// A key of the [`TOML_CONFIG_PATH`] file unknown to gpm, and so ignored. It is probably
+/// misspelled.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct UnknownKey {
+    pub key: String,
+    /// the known key it is probably a misspelling of
+    pub suggestion: Option<String>,
+    pub span: Option<SourceSpan>,
+}
+
+impl fmt::Display for UnknownKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "the key {:?} is unknown to gpm, and is ignored",
+            self.key
+        )?;
+        if let Some(suggestion) = &self.suggestion {
+            write!(f, ". Did you mean {:?}?", suggestion)?;
+        };
+        Ok(())
+    }
+}
+
+/// the keys of ``config``, the parsed toml ``content`` of a [`TOML_CONFIG_PATH`] file, that
+/// aren't in [`CONFIG_KEYS`]
+pub fn find_unknown_keys(content: &str, config: &toml::value::Table) -> Vec<UnknownKey> {
+    config
+        .keys()
+        .filter(|key| !CONFIG_KEYS.contains(&key.as_str()))
+        .map(|key| UnknownKey {
+            key: key.clone(),
+            suggestion: did_you_mean(key, CONFIG_KEYS).map(str::to_string),
+            span: SourceSpan::of_key(content, key),
+        })
+        .collect()
 }
 
+/// The content of a [`TOML_CONFIG_PATH`] file, as loaded by [`load_project_config`]
+pub struct ProjectConfig {
+    pub package: Package,
+    /// the keys that were ignored, to warn the user about
+    pub unknown_keys: Vec<UnknownKey>,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum InitProjectError {
+    #[error("a mod project already exist at {0}")]
+    AlreadyExistError(PathBuf),
+    #[error("io error with the file {0}")]
+    FileIOError(PathBuf, #[source] io::Error),
+    #[error("can't encode the toml configuration file. Probably internal error")]
+    TomlEncodeError(#[source] toml::ser::Error),
+}
+
+/// The content of the [`IGNORE_PATH`] file created by [`init_project`]
+pub const DEFAULT_IGNORE_CONTENT: &str =
+    "# files and directories listed here aren't packaged, like in a .gitignore
+.git/
+*.zip
+";
+
+/// Create a new mod project in ``project_path``, writing the [`TOML_CONFIG_PATH`] file from
+/// ``package_information`` and a starter [`IGNORE_PATH`] file. ``project_path`` is created if it
+/// doesn't exist.
+///
+/// If the project already contain a [`TOML_CONFIG_PATH`] file, return an error, unless ``force``
+/// is true, in which case it is overwritten. An existing [`IGNORE_PATH`] file is always kept.
+pub fn init_project(
+    project_path: &Path,
+    package_information: &PackageInformation,
+    force: bool,
+) -> Result<(), InitProjectError> {
+    let config_path = project_path.join(TOML_CONFIG_PATH);
+    if config_path.exists() && !force {
+        return Err(InitProjectError::AlreadyExistError(
+            project_path.to_path_buf(),
+        ));
+    };
+
+    let config_content =
+        get_project_config_toml(package_information).map_err(InitProjectError::TomlEncodeError)?;
+
+    std::fs::create_dir_all(project_path)
+        .map_err(|err| InitProjectError::FileIOError(project_path.to_path_buf(), err))?;
+
+    let mut config_file = File::create(&config_path)
+        .map_err(|err| InitProjectError::FileIOError(config_path.clone(), err))?;
+    config_file
+        .write_all(&config_content)
+        .map_err(|err| InitProjectError::FileIOError(config_path.clone(), err))?;
+
+    let ignore_path = project_path.join(IGNORE_PATH);
+    if !ignore_path.exists() {
+        std::fs::write(&ignore_path, DEFAULT_IGNORE_CONTENT)
+            .map_err(|err| InitProjectError::FileIOError(ignore_path, err))?;
+    };
+
+    Ok(())
+}
+
+/// load the package of the mod project in ``project_path``. See [`load_project_config`] to also
+/// get the unknown keys of its configuration.
 pub fn load_package_from_project(
     project_path: &Path,
 ) -> Result<Package, LoadPackageFromProjectError> {
+    Ok(load_project_config(project_path)?.package)
+}
+
+/// load the [`TOML_CONFIG_PATH`] file of the mod project in ``project_path``. Parse errors come
+/// with their position in the file.
+pub fn load_project_config(
+    project_path: &Path,
+) -> Result<ProjectConfig, LoadPackageFromProjectError> {
     let config_path = project_path.join(TOML_CONFIG_PATH);
     let mut config_file =
         BufReader::new(File::open(&config_path).map_err(|err| {
@@ -102,18 +466,280 @@ pub fn load_package_from_project(
     config_file
         .read_to_end(&mut config_content)
         .map_err(|err| LoadPackageFromProjectError::FileIOError(config_path.to_path_buf(), err))?;
-    let stored_package_information = toml::from_slice::<StoredPackageInformation>(&config_content)
-        .map_err(|err| {
-            LoadPackageFromProjectError::TomlDecodeError(config_path.to_path_buf(), err)
-        })?;
+    let decode_error = |err: toml::de::Error| {
+        let span = SourceSpan::from_toml_error(&String::from_utf8_lossy(&config_content), &err);
+        LoadPackageFromProjectError::TomlDecodeError(
+            config_path.to_path_buf(),
+            span.map(Box::new),
+            err,
+        )
+    };
+    let stored_package_information =
+        toml::from_slice::<StoredPackageInformation>(&config_content).map_err(decode_error)?;
+    let unknown_keys =
+        match toml::from_slice::<toml::Value>(&config_content).map_err(decode_error)? {
+            toml::Value::Table(config) => {
+                find_unknown_keys(&String::from_utf8_lossy(&config_content), &config)
+            }
+            _ => Vec::new(),
+        };
+    Ok(ProjectConfig {
+        package: Package {
+            information: stored_package_information.into(),
+        },
+        unknown_keys,
+    })
+}
+
+/// parse the information of a package from the parsed content of a [`TOML_CONFIG_PATH`] file
+pub fn package_information_from_toml(
+    config: toml::value::Table,
+) -> Result<PackageInformation, toml::de::Error> {
+    Ok(toml::Value::Table(config)
+        .try_into::<StoredPackageInformation>()?
+        .into())
+}
+
+/// parse a package from the content of a [`crate::constants::JSON_CONFIG_PATH`] file, as written
+/// by [`get_project_config_json`]
+pub fn load_package_from_json(config_content: &[u8]) -> Result<Package, serde_json::Error> {
+    let stored_package_information =
+        serde_json::from_slice::<StoredPackageInformation>(config_content)?;
     Ok(Package {
         information: stored_package_information.into(),
     })
 }
 
+pub fn get_project_config_toml(
+    package_information: &PackageInformation,
+) -> Result<Vec<u8>, toml::ser::Error> {
+    let stored_package_information = StoredPackageInformation::from(package_information);
+    toml::to_vec(&stored_package_information)
+}
+
 pub fn get_project_config_json(
     package_information: &PackageInformation,
 ) -> Result<Vec<u8>, serde_json::Error> {
-    let stored_package_information = StoredPackageInformation::from(package_information);
+    let mut stored_package_information = StoredPackageInformation::from(package_information);
+    // how the project is packaged only matters to the project, not to the archive
+    stored_package_information.uncompressed.clear();
+    stored_package_information.include.clear();
+    stored_package_information.exclude.clear();
     serde_json::to_vec_pretty(&stored_package_information)
 }
+
+/// serialize ``package_information`` with the keys of the configuration files. To be used with
+/// `#[serde(serialize_with)]`.
+pub fn serialize_information<S: Serializer>(
+    package_information: &PackageInformation,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    StoredPackageInformation::from(package_information).serialize(serializer)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::constants::{IGNORE_PATH, TOML_CONFIG_PATH};
+    use crate::package::PackageInformation;
+    use crate::package::{DependencySource, PackageDependency};
+    use crate::store_project::{
+        get_project_config_json, get_project_config_toml, init_project, load_package_from_json,
+        load_package_from_project, load_project_config, InitProjectError,
+        LoadPackageFromProjectError, SourceSpan,
+    };
+    use semver::Version;
+    use semver::VersionReq;
+    use std::fs;
+
+    #[test]
+    fn test_init_project() {
+        let work_dir = tempfile::tempdir().unwrap();
+        let project_path = work_dir.path().join("project");
+
+        let package_information = PackageInformation::new(
+            "a creator",
+            "an_identifier",
+            Version::new(1, 2, 3),
+            "A Display Name",
+            "the description",
+            "MIT",
+        );
+        init_project(&project_path, &package_information, false).unwrap();
+        assert!(project_path.join(IGNORE_PATH).is_file());
+
+        let package = load_package_from_project(&project_path).unwrap();
+        assert!(package.information.missing_publish_field().is_empty());
+        assert_eq!(
+            package.information.identifier.as_deref(),
+            Some("an_identifier")
+        );
+
+        assert!(matches!(
+            init_project(&project_path, &package_information, false),
+            Err(InitProjectError::AlreadyExistError(_))
+        ));
+        fs::write(project_path.join(IGNORE_PATH), "custom").unwrap();
+        init_project(&project_path, &package_information, true).unwrap();
+        assert!(project_path.join(TOML_CONFIG_PATH).is_file());
+        assert_eq!(
+            fs::read_to_string(project_path.join(IGNORE_PATH)).unwrap(),
+            "custom"
+        );
+    }
+
+    #[test]
+    fn test_invalid_version() {
+        let work_dir = tempfile::tempdir().unwrap();
+        let project_path = work_dir.path().to_path_buf();
+        fs::write(
+            project_path.join(TOML_CONFIG_PATH),
+            "identifier = \"a\"\nversion = \"banana\"\n",
+        )
+        .unwrap();
+        match load_package_from_project(&project_path) {
+            Err(LoadPackageFromProjectError::TomlDecodeError(path, span, err)) => {
+                assert_eq!(path, project_path.join(TOML_CONFIG_PATH));
+                assert_eq!(err.line_col(), Some((1, 10)));
+                let span = span.unwrap();
+                assert_eq!((span.line, span.column), (2, 11));
+                assert_eq!(span.source_line, "version = \"banana\"");
+            }
+            _ => panic!("the invalid version wasn't rejected"),
+        };
+
+        fs::write(
+            project_path.join(TOML_CONFIG_PATH),
+            "version = \"1.0.0-beta.2+build.5\"\n",
+        )
+        .unwrap();
+        let version = load_package_from_project(&project_path)
+            .unwrap()
+            .information
+            .version
+            .unwrap();
+        assert!(version < Version::new(1, 0, 0));
+        assert_eq!(version.build.as_str(), "build.5");
+    }
+
+    #[test]
+    fn test_dependencies() {
+        let work_dir = tempfile::tempdir().unwrap();
+        let project_path = work_dir.path().to_path_buf();
+        fs::write(
+            project_path.join(TOML_CONFIG_PATH),
+            "identifier = \"a\"
+[dependencies]
+cet_core = \">=1.9, <2\"
+local = { path = \"../local\", optional = true }
+remote = { version = \"1\", url = \"https://example.com/remote.zip\" }
+",
+        )
+        .unwrap();
+        let dependencies = load_package_from_project(&project_path)
+            .unwrap()
+            .information
+            .dependencies;
+        assert_eq!(
+            dependencies,
+            vec![
+                PackageDependency::new("cet_core", VersionReq::parse(">=1.9, <2").unwrap()),
+                PackageDependency {
+                    identifier: "local".into(),
+                    version: VersionReq::STAR,
+                    optional: true,
+                    source: DependencySource::Path("../local".into()),
+                },
+                PackageDependency {
+                    identifier: "remote".into(),
+                    version: VersionReq::parse("1").unwrap(),
+                    optional: false,
+                    source: DependencySource::Url("https://example.com/remote.zip".into()),
+                },
+            ]
+        );
+
+        // round trip through the toml and the packaged json
+        let package = load_package_from_project(&project_path).unwrap();
+        let json = get_project_config_json(&package.information).unwrap();
+        assert_eq!(
+            load_package_from_json(&json)
+                .unwrap()
+                .information
+                .dependencies,
+            dependencies
+        );
+        fs::write(
+            project_path.join(TOML_CONFIG_PATH),
+            get_project_config_toml(&package.information).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            load_package_from_project(&project_path)
+                .unwrap()
+                .information
+                .dependencies,
+            dependencies
+        );
+
+        // the old list format is still accepted
+        fs::write(
+            project_path.join(TOML_CONFIG_PATH),
+            "dependencies = [\"cet_core\"]\n",
+        )
+        .unwrap();
+        assert_eq!(
+            load_package_from_project(&project_path)
+                .unwrap()
+                .information
+                .dependencies,
+            vec![PackageDependency::new("cet_core", VersionReq::STAR)]
+        );
+
+        fs::write(
+            project_path.join(TOML_CONFIG_PATH),
+            "[dependencies]\nboth = { path = \"a\", url = \"b\" }\n",
+        )
+        .unwrap();
+        assert!(load_package_from_project(&project_path).is_err());
+    }
+
+    #[test]
+    fn test_unknown_keys() {
+        let work_dir = tempfile::tempdir().unwrap();
+        let project_path = work_dir.path();
+        fs::write(
+            project_path.join(TOML_CONFIG_PATH),
+            "identifier = \"a\"\n  licence = \"MIT\"\n\n[dependancies]\nb = \"1\"\n",
+        )
+        .unwrap();
+        let config = load_project_config(project_path).unwrap();
+        assert_eq!(config.package.information.license, None);
+        let unknown: Vec<(&str, Option<&str>, Option<usize>)> = config
+            .unknown_keys
+            .iter()
+            .map(|key| {
+                (
+                    key.key.as_str(),
+                    key.suggestion.as_deref(),
+                    key.span.as_ref().map(|span| span.line),
+                )
+            })
+            .collect();
+        assert_eq!(
+            unknown,
+            vec![
+                ("dependancies", Some("dependencies"), Some(4)),
+                ("licence", Some("license"), Some(2)),
+            ]
+        );
+        assert_eq!(
+            config.unknown_keys[1].span.as_ref().unwrap().to_string(),
+            "line 2, column 3:\n2 |   licence = \"MIT\"\n  |   ^"
+        );
+        assert_eq!(SourceSpan::of_key("a = 1\n", "b"), None);
+        assert_eq!(
+            SourceSpan::of_key("a = 1\nlicense.name = \"MIT\"\n", "license").map(|span| span.line),
+            Some(2)
+        );
+    }
+}
@@ -0,0 +1,12 @@
+//! Helpers shared by the tests of the modules
+
+use std::path::PathBuf;
+
+/// the path of the example mod project of the repository, `test_data/test_mod`
+pub fn test_mod_path() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .parent()
+        .unwrap()
+        .join("test_data")
+        .join("test_mod")
+}
@@ -0,0 +1,617 @@
+//! Check a mod project for the problems that would make it fail to package or install, or
+//! surprise its users.
+//!
+//! Unlike [`crate::package_writer::create_package`], that stop on the first problem, every check
+//! is run and every problem is reported, as a list of [`Diagnostic`].
+
+use std::ffi::OsStr;
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::constants::{IGNORE_PATH, TOML_CONFIG_PATH};
+use crate::install_strategy::StrategySet;
+use crate::license::parse_license;
+use crate::package::PackageInformation;
+use crate::package_writer::{entry_name, project_entries, CreatePackageError, WalkOptions};
+use crate::store_project::{find_unknown_keys, package_information_from_toml, SourceSpan};
+
+use semver::Version;
+use serde::Serialize;
+
+/// the size above which [`validate_project`] warn about a file, by default
+pub const DEFAULT_MAX_FILE_SIZE: u64 = 1024 * 1024 * 1024;
+
+#[derive(thiserror::Error, Debug)]
+pub enum ValidateError {
+    #[error("io error with the file {0}")]
+    FileIOError(PathBuf, #[source] io::Error),
+    #[error("can't list the files of the project")]
+    ListFilesError(#[from] CreatePackageError),
+}
+
+/// How bad a problem is
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// the project can be packaged, but probably not as intended
+    Warning,
+    /// the project can't be packaged or published
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Warning => "warning",
+            Self::Error => "error",
+        })
+    }
+}
+
+/// A problem found in a mod project
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// the name of the check that found the problem, like `identifier` or `file_size`
+    pub check: &'static str,
+    /// the configuration key or the file, relative to the project, the problem is about
+    pub location: Option<String>,
+    /// where the problem is in the configuration file
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub span: Option<SourceSpan>,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}[{}]", self.severity, self.check)?;
+        if let Some(location) = &self.location {
+            write!(f, " {}", location)?;
+        };
+        write!(f, ": {}", self.message)?;
+        if let Some(span) = &self.span {
+            write!(f, "\n  --> {}", span)?;
+        };
+        Ok(())
+    }
+}
+
+/// What [`validate_project`] check against
+pub struct ValidateOptions<'a> {
+    /// the install strategies the package can use
+    pub strategies: &'a StrategySet,
+    /// the size, in bytes, above which a file is reported
+    pub max_file_size: u64,
+}
+
+impl<'a> ValidateOptions<'a> {
+    pub fn new(strategies: &'a StrategySet) -> Self {
+        Self {
+            strategies,
+            max_file_size: DEFAULT_MAX_FILE_SIZE,
+        }
+    }
+}
+
+/// return true if ``identifier`` is made of lowercase ascii letters, digits, `_` and `-`,
+/// starting with a letter or a digit
+pub fn is_valid_identifier(identifier: &str) -> bool {
+    identifier
+        .chars()
+        .next()
+        .is_some_and(|first| first.is_ascii_lowercase() || first.is_ascii_digit())
+        && identifier.chars().all(|character| {
+            character.is_ascii_lowercase()
+                || character.is_ascii_digit()
+                || character == '_'
+                || character == '-'
+        })
+}
+
+/// return true if ``url`` is an absolute http or https URL
+pub fn is_valid_url(url: &str) -> bool {
+    url::Url::parse(url)
+        .map(|url| (url.scheme() == "http" || url.scheme() == "https") && url.has_host())
+        .unwrap_or(false)
+}
+
+/// the diagnostics of a project, in the order the checks are run
+struct Diagnostics {
+    list: Vec<Diagnostic>,
+    /// the content of the configuration file, to find the span of the keys
+    config_content: String,
+}
+
+impl Diagnostics {
+    fn push(
+        &mut self,
+        severity: Severity,
+        check: &'static str,
+        location: &str,
+        span: Option<SourceSpan>,
+        message: String,
+    ) {
+        self.list.push(Diagnostic {
+            severity,
+            check,
+            location: Some(location.to_string()),
+            span,
+            message,
+        });
+    }
+
+    /// report an error about the top level ``key`` of the configuration
+    fn config_error(&mut self, check: &'static str, key: &str, message: String) {
+        let span = SourceSpan::of_key(&self.config_content, key);
+        self.push(Severity::Error, check, key, span, message)
+    }
+
+    /// report a warning about the top level ``key`` of the configuration
+    fn config_warning(&mut self, check: &'static str, key: &str, message: String) {
+        let span = SourceSpan::of_key(&self.config_content, key);
+        self.push(Severity::Warning, check, key, span, message)
+    }
+
+    /// report a warning about the file at ``name`` in the project
+    fn file_warning(&mut self, check: &'static str, name: &str, message: String) {
+        self.push(Severity::Warning, check, name, None, message)
+    }
+}
+
+/// check the values of the configuration that can't be checked by its type, and remove the
+/// invalid ones from ``config``. Return the keys of the removed values.
+fn check_config_values(
+    config: &mut toml::value::Table,
+    diagnostics: &mut Diagnostics,
+) -> Vec<&'static str> {
+    for unknown_key in find_unknown_keys(&diagnostics.config_content, config) {
+        diagnostics.push(
+            Severity::Warning,
+            "unknown_key",
+            &unknown_key.key,
+            unknown_key.span.clone(),
+            unknown_key.to_string(),
+        );
+    }
+
+    let mut invalid = Vec::new();
+    for key in &[
+        "creator",
+        "identifier",
+        "version",
+        "display_name",
+        "description",
+        "license",
+        "website_url",
+    ] {
+        let value = match config.get(*key) {
+            Some(toml::Value::String(value)) => value,
+            Some(_) => {
+                diagnostics.config_error(
+                    "invalid_field",
+                    key,
+                    format!("{} should be a string", key),
+                );
+                invalid.push(*key);
+                continue;
+            }
+            None => continue,
+        };
+        let error = match *key {
+            "identifier" if !is_valid_identifier(value) => Some(format!(
+                "the identifier {:?} should only contain lowercase ascii letters, digits, _ and -, and start with a letter or a digit",
+                value
+            )),
+            "version" => Version::parse(value).err().map(|err| {
+                format!(
+                    "the version {:?} isn't a valid semantic version (like 1.0.0): {}",
+                    value, err
+                )
+            }),
+            "website_url" if !is_valid_url(value) => Some(format!(
+                "the website url {:?} isn't an absolute http or https url",
+                value
+            )),
+            "license" => {
+                if let Err(err) = parse_license(value) {
+                    diagnostics.config_warning("license", key, format!("the license {}", err));
+                };
+                None
+            }
+            _ => None,
+        };
+        if let Some(message) = error {
+            diagnostics.config_error(key, key, message);
+            invalid.push(*key);
+        };
+    }
+    for key in &invalid {
+        config.remove(*key);
+    }
+    invalid
+}
+
+/// parse the information of the package from ``config``. Each value that can't be parsed is
+/// reported and removed, so the other ones are still checked. Return None if the invalid value
+/// couldn't be found.
+fn parse_config(
+    mut config: toml::value::Table,
+    diagnostics: &mut Diagnostics,
+) -> Option<PackageInformation> {
+    loop {
+        let err = match package_information_from_toml(config.clone()) {
+            Ok(information) => return Some(information),
+            Err(err) => err,
+        };
+        // the errors of a parsed value don't have a position, look for the key on its own
+        let invalid_key = config
+            .iter()
+            .find(|(key, value)| {
+                let mut single = toml::value::Table::new();
+                single.insert(key.to_string(), (*value).clone());
+                package_information_from_toml(single).is_err()
+            })
+            .map(|(key, _)| key.clone());
+        match invalid_key {
+            Some(key) => {
+                diagnostics.config_error("invalid_field", &key, err.to_string());
+                config.remove(&key);
+            }
+            None => {
+                diagnostics.push(
+                    Severity::Error,
+                    "invalid_field",
+                    TOML_CONFIG_PATH,
+                    None,
+                    err.to_string(),
+                );
+                return None;
+            }
+        };
+    }
+}
+
+/// check the information of the package, once its configuration was parsed. The ``invalid``
+/// fields were already reported.
+fn check_information(
+    information: &PackageInformation,
+    invalid: &[&str],
+    options: &ValidateOptions,
+    diagnostics: &mut Diagnostics,
+) {
+    for field in information
+        .missing_publish_field()
+        .into_iter()
+        .filter(|field| !invalid.contains(field))
+    {
+        diagnostics.config_error(
+            "missing_field",
+            field,
+            format!("{} is required to package the mod", field),
+        );
+    }
+    for dependency in &information.dependencies {
+        if Some(&dependency.identifier) == information.identifier.as_ref() {
+            let span = SourceSpan::of_key(&diagnostics.config_content, "dependencies");
+            diagnostics.push(
+                Severity::Error,
+                "self_dependency",
+                &format!("dependencies.{}", dependency.identifier),
+                span,
+                "the mod depend on itself".to_string(),
+            );
+        } else if !is_valid_identifier(&dependency.identifier) {
+            let span = SourceSpan::of_key(&diagnostics.config_content, "dependencies");
+            diagnostics.push(
+                Severity::Error,
+                "identifier",
+                &format!("dependencies.{}", dependency.identifier),
+                span,
+                format!(
+                    "the dependency identifier {:?} isn't a valid identifier",
+                    dependency.identifier
+                ),
+            );
+        };
+    }
+    for strategy in &information.install_strategies {
+        if options.strategies.get(strategy).is_none() {
+            diagnostics.config_error(
+                "install_strategy",
+                "install_strategies",
+                format!(
+                    "the install strategy {:?} doesn't exist for {}",
+                    strategy,
+                    options.strategies.game()
+                ),
+            );
+        };
+    }
+}
+
+/// check the files that would be packaged
+fn check_files(
+    project_dir: &Path,
+    information: &PackageInformation,
+    options: &ValidateOptions,
+    diagnostics: &mut Diagnostics,
+) -> Result<(), ValidateError> {
+    let strategies_valid = options
+        .strategies
+        .check(&information.install_strategies)
+        .is_ok();
+    let identifier = information.identifier.as_deref().unwrap_or_default();
+    for entry in project_entries(project_dir, information, &WalkOptions::default())? {
+        if entry.ignored
+            || !entry.is_file
+            || entry.relative_path == Path::new(TOML_CONFIG_PATH)
+            || entry.relative_path.file_name() == Some(OsStr::new(IGNORE_PATH))
+        {
+            continue;
+        };
+        let name = entry_name(&entry.relative_path);
+        let size = entry.size;
+        if size > options.max_file_size {
+            diagnostics.file_warning(
+                "file_size",
+                &name,
+                format!(
+                    "the file is {} bytes, more than the {} bytes limit",
+                    size, options.max_file_size
+                ),
+            );
+        };
+        if strategies_valid
+            && matches!(
+                options
+                    .strategies
+                    .target(identifier, &information.install_strategies, &name),
+                Ok(None)
+            )
+        {
+            diagnostics.file_warning(
+                "uninstalled_file",
+                &name,
+                format!(
+                    "the file is packaged, but not installed by any install strategy. Add it to {} if it isn't needed",
+                    IGNORE_PATH
+                ),
+            );
+        };
+    }
+    Ok(())
+}
+
+/// check the mod project in ``project_dir``, returning every problem found. The project is valid
+/// if there is no [`Severity::Error`] diagnostic.
+///
+/// An error is only returned if the project can't be read.
+pub fn validate_project(
+    project_dir: &Path,
+    options: &ValidateOptions,
+) -> Result<Vec<Diagnostic>, ValidateError> {
+    let config_path = project_dir.join(TOML_CONFIG_PATH);
+    let config_content = std::fs::read_to_string(&config_path)
+        .map_err(|err| ValidateError::FileIOError(config_path.clone(), err))?;
+    let mut diagnostics = Diagnostics {
+        list: Vec::new(),
+        config_content,
+    };
+    let mut config = match toml::from_str::<toml::Value>(&diagnostics.config_content) {
+        Ok(toml::Value::Table(config)) => config,
+        Ok(_) => unreachable!("a toml document is always a table"),
+        Err(err) => {
+            let span = SourceSpan::from_toml_error(&diagnostics.config_content, &err);
+            diagnostics.push(
+                Severity::Error,
+                "parse",
+                TOML_CONFIG_PATH,
+                span,
+                err.to_string(),
+            );
+            return Ok(diagnostics.list);
+        }
+    };
+
+    let invalid = check_config_values(&mut config, &mut diagnostics);
+    let information = match parse_config(config, &mut diagnostics) {
+        Some(information) => information,
+        None => return Ok(diagnostics.list),
+    };
+    check_information(&information, &invalid, options, &mut diagnostics);
+    check_files(project_dir, &information, options, &mut diagnostics)?;
+    Ok(diagnostics.list)
+}
+
+/// return true if ``diagnostics`` contain an error
+pub fn has_error(diagnostics: &[Diagnostic]) -> bool {
+    diagnostics
+        .iter()
+        .any(|diagnostic| diagnostic.severity == Severity::Error)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::install_strategy::StrategySet;
+    use crate::test_utils::test_mod_path;
+    use crate::validate::{
+        has_error, is_valid_identifier, is_valid_url, validate_project, Diagnostic, ValidateOptions,
+    };
+    use std::fs;
+
+    #[test]
+    fn test_validate_test_mod() {
+        let strategies = StrategySet::default();
+        let diagnostics =
+            validate_project(&test_mod_path(), &ValidateOptions::new(&strategies)).unwrap();
+        assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+    }
+
+    #[test]
+    fn test_validate_invalid_project() {
+        let work_dir = tempfile::tempdir().unwrap();
+        let project_dir = work_dir.path();
+        fs::create_dir_all(project_dir.join("archive/pc/mod")).unwrap();
+        fs::write(
+            project_dir.join("config.toml"),
+            r#"identifier = "My Mod"
+version = "1.0"
+creator = "modder"
+description = "a broken mod"
+licence = "MIT"
+license = "some version of AGPL"
+website_url = "not an url"
+install_strategies = ["archive", "unknown"]
+
+[dependencies]
+My_Mod = "1"
+"#,
+        )
+        .unwrap();
+        fs::write(
+            project_dir.join("archive/pc/mod/big.archive"),
+            vec![0; 2048],
+        )
+        .unwrap();
+        fs::write(project_dir.join("readme.txt"), "read me").unwrap();
+
+        let strategies = StrategySet::default();
+        let mut options = ValidateOptions::new(&strategies);
+        options.max_file_size = 1024;
+        let diagnostics = validate_project(project_dir, &options).unwrap();
+        let checks: Vec<&str> = diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.check)
+            .collect();
+        assert_eq!(
+            checks,
+            vec![
+                "unknown_key",
+                "identifier",
+                "version",
+                "license",
+                "website_url",
+                "missing_field",
+                "identifier",
+                "install_strategy",
+                "file_size",
+            ]
+        );
+        assert!(has_error(&diagnostics));
+
+        // the identifier is now valid, and the dependency on itself is found
+        fs::write(
+            project_dir.join("config.toml"),
+            r#"identifier = "my_mod"
+version = "1.0.0"
+creator = "modder"
+display_name = "My Mod"
+description = "a mod"
+license = "MIT OR Apache-2.0"
+install_strategies = ["archive"]
+
+[dependencies]
+my_mod = "1"
+"#,
+        )
+        .unwrap();
+        let diagnostics = validate_project(project_dir, &options).unwrap();
+        let checks: Vec<&str> = diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.check)
+            .collect();
+        assert_eq!(
+            checks,
+            vec!["self_dependency", "file_size", "uninstalled_file"]
+        );
+    }
+
+    #[test]
+    fn test_identifier_and_url() {
+        assert!(is_valid_identifier("cet_core-2"));
+        assert!(!is_valid_identifier("_private"));
+        assert!(!is_valid_identifier("My Mod"));
+        assert!(!is_valid_identifier(""));
+        assert!(is_valid_url("https://example.com/mod"));
+        assert!(!is_valid_url("ftp://example.com"));
+        assert!(!is_valid_url("example.com"));
+    }
+
+    #[test]
+    fn test_validate_spans() {
+        let work_dir = tempfile::tempdir().unwrap();
+        let project_dir = work_dir.path();
+        fs::write(
+            project_dir.join("config.toml"),
+            "version = \"banana\"\nlicence = \"MIT\"\ntags = \"not a list\"\n",
+        )
+        .unwrap();
+        let strategies = StrategySet::default();
+        let diagnostics =
+            validate_project(project_dir, &ValidateOptions::new(&strategies)).unwrap();
+        // the invalid version doesn't hide the invalid tags, nor the missing fields
+        assert_eq!(
+            spans(&diagnostics),
+            vec![
+                ("unknown_key", Some(2)),
+                ("version", Some(1)),
+                ("invalid_field", Some(3)),
+                ("missing_field", None),
+                ("missing_field", None),
+                ("missing_field", None),
+                ("missing_field", None),
+                ("missing_field", None),
+            ]
+        );
+        assert!(diagnostics[0].message.contains("Did you mean \"license\"?"));
+    }
+
+    #[test]
+    fn test_validate_multiline_values() {
+        let work_dir = tempfile::tempdir().unwrap();
+        let project_dir = work_dir.path();
+        fs::write(
+            project_dir.join("config.toml"),
+            r#"identifier = "my_mod"
+version = "1.0.0"
+creator = "modder"
+description = "a mod"
+website_url = """
+not
+an url"""
+license.name = "MIT"
+tags = [
+    "weapons",
+    3,
+]
+"#,
+        )
+        .unwrap();
+        let strategies = StrategySet::default();
+        let diagnostics =
+            validate_project(project_dir, &ValidateOptions::new(&strategies)).unwrap();
+        // every line of the invalid values is kept out of the parsing, not only the first one
+        assert_eq!(
+            spans(&diagnostics),
+            vec![
+                ("invalid_field", Some(8)),
+                ("website_url", Some(5)),
+                ("invalid_field", Some(9)),
+                ("missing_field", None),
+            ]
+        );
+    }
+
+    fn spans(diagnostics: &[Diagnostic]) -> Vec<(&str, Option<usize>)> {
+        diagnostics
+            .iter()
+            .map(|diagnostic| {
+                (
+                    diagnostic.check,
+                    diagnostic.span.as_ref().map(|span| span.line),
+                )
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,344 @@
+//! A virtual view of a game directory with the packages of a profile layered on top of it, so
+//! mods can be used without copying them into the game directory.
+//!
+//! Each layer is a set of files placed at paths relative to the game directory. When more than
+//! one layer contain the same path, the last layer added wins. Paths use `/` separators, and the
+//! root of the view is the empty path.
+//!
+//! With the `fuse` feature, the view can be mounted read-only on Linux, see [`mount`].
+
+#[cfg(all(feature = "fuse", target_os = "linux"))]
+pub mod mount;
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+use gpm_core::conflict::{profile_staged_packages, ConflictError};
+use gpm_core::deploy::{staged_files, DeployError};
+use gpm_core::install_strategy::StrategySet;
+use gpm_core::profile::Profile;
+
+#[derive(thiserror::Error, Debug)]
+pub enum VfsError {
+    #[error("io error with the file {0}")]
+    FileIOError(PathBuf, #[source] io::Error),
+    #[error("can't read the packages of the profile")]
+    ConflictError(#[from] ConflictError),
+    #[error("can't list the files of a staged package")]
+    DeployError(#[from] DeployError),
+    #[error("{0:?} doesn't exist")]
+    NotFoundError(String),
+    #[error("{0:?} isn't a directory")]
+    NotADirectoryError(String),
+    #[error("{0:?} is a directory")]
+    IsADirectoryError(String),
+}
+
+/// A file or a directory of a [`VirtualFs`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum VfsEntry {
+    File {
+        path: String,
+        /// the real file
+        source: PathBuf,
+        /// the layer providing this file, or None if it comes from the game directory
+        layer: Option<String>,
+    },
+    Directory {
+        path: String,
+    },
+}
+
+impl VfsEntry {
+    pub fn path(&self) -> &str {
+        match self {
+            Self::File { path, .. } | Self::Directory { path } => path,
+        }
+    }
+
+    /// the last component of the path, or an empty string for the root
+    pub fn name(&self) -> &str {
+        let path = self.path();
+        path.rsplit('/').next().unwrap_or(path)
+    }
+
+    pub fn is_dir(&self) -> bool {
+        matches!(self, Self::Directory { .. })
+    }
+}
+
+/// A file provided by a layer
+#[derive(Debug, Clone)]
+struct LayerFile {
+    source: PathBuf,
+    layer: usize,
+}
+
+/// A layered view of a game directory
+#[derive(Debug, Clone)]
+pub struct VirtualFs {
+    base: PathBuf,
+    layers: Vec<String>,
+    files: BTreeMap<String, LayerFile>,
+    /// every directory containing a file of a layer, including the root
+    directories: BTreeSet<String>,
+}
+
+/// return the normalized version of ``path``, or None if it goes outside of the root
+fn normalize(path: &str) -> Option<String> {
+    let mut components = Vec::new();
+    for component in Path::new(path).components() {
+        match component {
+            Component::Normal(name) => components.push(name.to_str()?),
+            Component::CurDir | Component::RootDir => (),
+            Component::ParentDir | Component::Prefix(_) => return None,
+        }
+    }
+    Some(components.join("/"))
+}
+
+/// the parents of ``path``, from the closest one to the root
+fn ancestors(path: &str) -> impl Iterator<Item = &str> {
+    path.char_indices()
+        .rev()
+        .filter(|(_, character)| *character == '/')
+        .map(move |(index, _)| &path[..index])
+        .chain(if path.is_empty() { None } else { Some("") })
+}
+
+impl VirtualFs {
+    /// a view of the ``base`` directory, without any layer
+    pub fn new(base: &Path) -> Self {
+        let mut directories = BTreeSet::new();
+        directories.insert(String::new());
+        Self {
+            base: base.to_path_buf(),
+            layers: Vec::new(),
+            files: BTreeMap::new(),
+            directories,
+        }
+    }
+
+    /// a view of the game directory of ``profile``, with a layer for each of its staged packages
+    /// in load order. The files are placed following the install strategies of the packages.
+    pub fn from_profile(profile: &Profile, strategies: &StrategySet) -> Result<Self, VfsError> {
+        let mut vfs = Self::new(&profile.game_dir);
+        for package in profile_staged_packages(profile)? {
+            let files = staged_files(&package, strategies)?;
+            vfs.add_layer(&package.identifier, files);
+        }
+        Ok(vfs)
+    }
+
+    /// the directory under the layers
+    pub fn base(&self) -> &Path {
+        &self.base
+    }
+
+    /// the name of the layers, from the bottom one to the top one
+    pub fn layers(&self) -> &[String] {
+        &self.layers
+    }
+
+    /// add a layer named ``name`` on top of the others, placing each source file at the
+    /// associated path. A file replace the files and directories at the same path in the layers
+    /// below. Paths going outside of the root are ignored.
+    pub fn add_layer<I: IntoIterator<Item = (String, PathBuf)>>(&mut self, name: &str, files: I) {
+        let layer = self.layers.len();
+        self.layers.push(name.to_string());
+        for (path, source) in files {
+            let path = match normalize(&path) {
+                Some(path) if !path.is_empty() => path,
+                _ => continue,
+            };
+            // a file hide the directory at the same path, and the files at its parent paths
+            let prefix = format!("{}/", path);
+            let hidden = self
+                .files
+                .range(prefix.clone()..)
+                .take_while(|(child, _)| child.starts_with(&prefix))
+                .map(|(child, _)| child.clone())
+                .collect::<Vec<_>>();
+            for child in hidden {
+                self.files.remove(&child);
+            }
+            for parent in ancestors(&path) {
+                self.files.remove(parent);
+            }
+            self.files.insert(path, LayerFile { source, layer });
+        }
+
+        self.directories.clear();
+        self.directories.insert(String::new());
+        for path in self.files.keys() {
+            for parent in ancestors(path) {
+                if !self.directories.insert(parent.to_string()) {
+                    break;
+                };
+            }
+        }
+    }
+
+    /// return true if a layer file is at ``path`` or one of its parents
+    fn is_hidden_by_layer(&self, path: &str) -> bool {
+        self.files.contains_key(path)
+            || ancestors(path).any(|parent| self.files.contains_key(parent))
+    }
+
+    /// return the entry at ``path``, if it exists
+    pub fn lookup(&self, path: &str) -> Option<VfsEntry> {
+        let path = normalize(path)?;
+        if let Some(file) = self.files.get(&path) {
+            return Some(VfsEntry::File {
+                source: file.source.clone(),
+                layer: Some(self.layers[file.layer].clone()),
+                path,
+            });
+        };
+        if self.directories.contains(&path) {
+            return Some(VfsEntry::Directory { path });
+        };
+        if self.is_hidden_by_layer(&path) {
+            return None;
+        };
+        let source = self.base.join(&path);
+        let metadata = fs::metadata(&source).ok()?;
+        if metadata.is_dir() {
+            Some(VfsEntry::Directory { path })
+        } else {
+            Some(VfsEntry::File {
+                path,
+                source,
+                layer: None,
+            })
+        }
+    }
+
+    /// list the entries of the directory at ``path``, sorted by name
+    pub fn read_dir(&self, path: &str) -> Result<Vec<VfsEntry>, VfsError> {
+        let path = match self.lookup(path) {
+            Some(VfsEntry::Directory { path }) => path,
+            Some(entry) => return Err(VfsError::NotADirectoryError(entry.path().to_string())),
+            None => return Err(VfsError::NotFoundError(path.to_string())),
+        };
+        let prefix = if path.is_empty() {
+            String::new()
+        } else {
+            format!("{}/", path)
+        };
+
+        let mut names = BTreeSet::new();
+        let base_dir = self.base.join(&path);
+        if !self.is_hidden_by_layer(&path) && base_dir.is_dir() {
+            for entry in fs::read_dir(&base_dir)
+                .map_err(|err| VfsError::FileIOError(base_dir.clone(), err))?
+            {
+                let entry = entry.map_err(|err| VfsError::FileIOError(base_dir.clone(), err))?;
+                if let Some(name) = entry.file_name().to_str() {
+                    names.insert(name.to_string());
+                };
+            }
+        };
+        for layer_path in self
+            .files
+            .keys()
+            .chain(self.directories.iter())
+            .filter_map(|layer_path| layer_path.strip_prefix(&prefix))
+        {
+            if !layer_path.is_empty() && !layer_path.contains('/') {
+                names.insert(layer_path.to_string());
+            };
+        }
+
+        Ok(names
+            .into_iter()
+            .filter_map(|name| self.lookup(&format!("{}{}", prefix, name)))
+            .collect())
+    }
+
+    /// open the file at ``path`` for reading
+    pub fn open(&self, path: &str) -> Result<File, VfsError> {
+        match self.lookup(path) {
+            Some(VfsEntry::File { source, .. }) => {
+                File::open(&source).map_err(|err| VfsError::FileIOError(source, err))
+            }
+            Some(VfsEntry::Directory { path }) => Err(VfsError::IsADirectoryError(path)),
+            None => Err(VfsError::NotFoundError(path.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{VfsEntry, VfsError, VirtualFs};
+    use std::fs;
+    use std::io::Read;
+
+    #[test]
+    fn test_layers() {
+        let temporary_dir = tempfile::tempdir().unwrap();
+        let work_dir = temporary_dir.path();
+        let game_dir = work_dir.join("game");
+        fs::create_dir_all(game_dir.join("data").join("textures")).unwrap();
+        fs::write(game_dir.join("game.exe"), "game").unwrap();
+        fs::write(game_dir.join("data").join("base.txt"), "game").unwrap();
+        fs::write(game_dir.join("data").join("textures").join("a.dds"), "game").unwrap();
+        let first = work_dir.join("first.txt");
+        fs::write(&first, "first").unwrap();
+        let second = work_dir.join("second.txt");
+        fs::write(&second, "second").unwrap();
+
+        let mut vfs = VirtualFs::new(&game_dir);
+        vfs.add_layer(
+            "first",
+            vec![
+                ("data/base.txt".to_string(), first.clone()),
+                ("mods/first/init.lua".to_string(), first.clone()),
+                ("../outside.txt".to_string(), first),
+            ],
+        );
+        vfs.add_layer(
+            "second",
+            vec![
+                ("data/base.txt".to_string(), second.clone()),
+                ("data/textures".to_string(), second),
+            ],
+        );
+
+        let mut content = String::new();
+        vfs.open("data/base.txt")
+            .unwrap()
+            .read_to_string(&mut content)
+            .unwrap();
+        assert_eq!(content, "second");
+        assert!(matches!(
+            vfs.lookup("/game.exe"),
+            Some(VfsEntry::File { layer: None, .. })
+        ));
+        assert!(vfs.lookup("mods/first").unwrap().is_dir());
+        assert!(vfs.lookup("data/textures/a.dds").is_none());
+        assert!(vfs.lookup("../outside.txt").is_none());
+
+        let root = vfs
+            .read_dir("")
+            .unwrap()
+            .iter()
+            .map(|entry| entry.name().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(root, vec!["data", "game.exe", "mods"]);
+        let data = vfs.read_dir("data").unwrap();
+        assert_eq!(data.len(), 2);
+        assert!(!data[1].is_dir());
+        assert!(matches!(
+            vfs.read_dir("game.exe"),
+            Err(VfsError::NotADirectoryError(_))
+        ));
+        assert!(matches!(
+            vfs.open("mods"),
+            Err(VfsError::IsADirectoryError(_))
+        ));
+    }
+}
@@ -0,0 +1,230 @@
+//! Mount a [`VirtualFs`] read-only with FUSE.
+
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::fs;
+use std::io;
+use std::os::unix::fs::{FileExt, MetadataExt};
+use std::path::Path;
+use std::time::{Duration, UNIX_EPOCH};
+
+use fuser::{
+    FileAttr, FileType, Filesystem, MountOption, ReplyAttr, ReplyData, ReplyDirectory, ReplyEntry,
+    ReplyOpen, Request,
+};
+
+use crate::{VfsEntry, VirtualFs};
+
+/// how long the kernel can cache the attributes. The view doesn't change while mounted.
+const TTL: Duration = Duration::from_secs(60);
+const ROOT_INODE: u64 = 1;
+
+/// A [`VirtualFs`] served through FUSE. Inodes are given to paths as they are looked up.
+struct VfsFilesystem {
+    vfs: VirtualFs,
+    paths: Vec<String>,
+    inodes: HashMap<String, u64>,
+}
+
+impl VfsFilesystem {
+    fn new(vfs: VirtualFs) -> Self {
+        let mut inodes = HashMap::new();
+        inodes.insert(String::new(), ROOT_INODE);
+        Self {
+            vfs,
+            paths: vec![String::new()],
+            inodes,
+        }
+    }
+
+    fn inode(&mut self, path: &str) -> u64 {
+        if let Some(inode) = self.inodes.get(path) {
+            return *inode;
+        };
+        self.paths.push(path.to_string());
+        let inode = self.paths.len() as u64;
+        self.inodes.insert(path.to_string(), inode);
+        inode
+    }
+
+    fn path(&self, inode: u64) -> Option<&str> {
+        self.paths
+            .get((inode as usize).checked_sub(1)?)
+            .map(String::as_str)
+    }
+
+    fn entry(&self, inode: u64) -> Option<VfsEntry> {
+        self.vfs.lookup(self.path(inode)?)
+    }
+
+    fn attr(&self, inode: u64, entry: &VfsEntry) -> FileAttr {
+        let source = match entry {
+            VfsEntry::File { source, .. } => source.clone(),
+            VfsEntry::Directory { path } => self.vfs.base().join(path),
+        };
+        let metadata = fs::metadata(source).ok();
+        let (kind, perm) = if entry.is_dir() {
+            (FileType::Directory, 0o555)
+        } else {
+            (FileType::RegularFile, 0o444)
+        };
+        let size = match (entry, &metadata) {
+            (VfsEntry::File { .. }, Some(metadata)) => metadata.len(),
+            _ => 0,
+        };
+        let mtime = metadata
+            .as_ref()
+            .and_then(|metadata| metadata.modified().ok())
+            .unwrap_or(UNIX_EPOCH);
+        FileAttr {
+            ino: inode,
+            size,
+            blocks: size.div_ceil(512),
+            atime: mtime,
+            mtime,
+            ctime: mtime,
+            crtime: mtime,
+            kind,
+            perm,
+            nlink: 1,
+            uid: metadata
+                .as_ref()
+                .map(|metadata| metadata.uid())
+                .unwrap_or(0),
+            gid: metadata
+                .as_ref()
+                .map(|metadata| metadata.gid())
+                .unwrap_or(0),
+            rdev: 0,
+            blksize: 4096,
+            flags: 0,
+        }
+    }
+}
+
+impl Filesystem for VfsFilesystem {
+    fn lookup(&mut self, _req: &Request<'_>, parent: u64, name: &OsStr, reply: ReplyEntry) {
+        let path = match (self.path(parent), name.to_str()) {
+            (Some(""), Some(name)) => name.to_string(),
+            (Some(parent), Some(name)) => format!("{}/{}", parent, name),
+            _ => return reply.error(libc::ENOENT),
+        };
+        match self.vfs.lookup(&path) {
+            Some(entry) => {
+                let inode = self.inode(entry.path());
+                reply.entry(&TTL, &self.attr(inode, &entry), 0);
+            }
+            None => reply.error(libc::ENOENT),
+        }
+    }
+
+    fn getattr(&mut self, _req: &Request<'_>, ino: u64, reply: ReplyAttr) {
+        match self.entry(ino) {
+            Some(entry) => reply.attr(&TTL, &self.attr(ino, &entry)),
+            None => reply.error(libc::ENOENT),
+        }
+    }
+
+    fn open(&mut self, _req: &Request<'_>, ino: u64, flags: i32, reply: ReplyOpen) {
+        if flags & libc::O_ACCMODE != libc::O_RDONLY {
+            return reply.error(libc::EROFS);
+        };
+        match self.entry(ino) {
+            Some(VfsEntry::File { .. }) => reply.opened(0, 0),
+            Some(VfsEntry::Directory { .. }) => reply.error(libc::EISDIR),
+            None => reply.error(libc::ENOENT),
+        }
+    }
+
+    fn read(
+        &mut self,
+        _req: &Request<'_>,
+        ino: u64,
+        _fh: u64,
+        offset: i64,
+        size: u32,
+        _flags: i32,
+        _lock_owner: Option<u64>,
+        reply: ReplyData,
+    ) {
+        let source = match self.entry(ino) {
+            Some(VfsEntry::File { source, .. }) => source,
+            Some(VfsEntry::Directory { .. }) => return reply.error(libc::EISDIR),
+            None => return reply.error(libc::ENOENT),
+        };
+        let mut buffer = vec![0; size as usize];
+        let result = fs::File::open(source).and_then(|file| {
+            let mut read = 0;
+            while read < buffer.len() {
+                match file.read_at(&mut buffer[read..], offset as u64 + read as u64)? {
+                    0 => break,
+                    count => read += count,
+                }
+            }
+            Ok(read)
+        });
+        match result {
+            Ok(read) => reply.data(&buffer[..read]),
+            Err(err) => reply.error(err.raw_os_error().unwrap_or(libc::EIO)),
+        }
+    }
+
+    fn readdir(
+        &mut self,
+        _req: &Request<'_>,
+        ino: u64,
+        _fh: u64,
+        offset: i64,
+        mut reply: ReplyDirectory,
+    ) {
+        let path = match self.path(ino) {
+            Some(path) => path.to_string(),
+            None => return reply.error(libc::ENOENT),
+        };
+        let entries = match self.vfs.read_dir(&path) {
+            Ok(entries) => entries,
+            Err(_) => return reply.error(libc::ENOTDIR),
+        };
+        let parent = path
+            .rsplit_once('/')
+            .map(|(parent, _)| parent)
+            .unwrap_or("");
+        let mut listing = vec![
+            (ino, FileType::Directory, ".".to_string()),
+            (self.inode(parent), FileType::Directory, "..".to_string()),
+        ];
+        for entry in entries {
+            let kind = if entry.is_dir() {
+                FileType::Directory
+            } else {
+                FileType::RegularFile
+            };
+            listing.push((self.inode(entry.path()), kind, entry.name().to_string()));
+        }
+        for (index, (inode, kind, name)) in listing.into_iter().enumerate().skip(offset as usize) {
+            if reply.add(inode, index as i64 + 1, kind, name) {
+                break;
+            };
+        }
+        reply.ok();
+    }
+}
+
+/// mount ``vfs`` read-only at ``mountpoint``, and serve it until it is unmounted
+pub fn mount(vfs: VirtualFs, mountpoint: &Path) -> io::Result<()> {
+    fuser::mount2(
+        VfsFilesystem::new(vfs),
+        mountpoint,
+        &[MountOption::RO, MountOption::FSName("gpm".to_string())],
+    )
+}
+
+/// mount ``vfs`` read-only at ``mountpoint`` in a background thread. It is unmounted when the
+/// returned session is dropped.
+pub fn spawn_mount(vfs: VirtualFs, mountpoint: &Path) -> io::Result<fuser::BackgroundSession> {
+    fuser::spawn_mount2(
+        VfsFilesystem::new(vfs),
+        mountpoint,
+        &[MountOption::RO, MountOption::FSName("gpm".to_string())],
+    )
+}